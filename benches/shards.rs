@@ -0,0 +1,41 @@
+// compares write throughput of the keyspace at a few shard amounts:
+//   cargo bench --bench shards
+use std::{thread, time::Instant};
+
+use simple_redis::{backend::Backend, config::ServerConfig, RespBulkString};
+
+const WRITES_PER_THREAD: usize = 100_000;
+
+fn main() {
+    let writers = thread::available_parallelism().map_or(4, usize::from);
+    let default = ServerConfig::default().shard_amount;
+    for shard_amount in [2, 8, 64, default] {
+        let backend = Backend::with_config(ServerConfig {
+            shard_amount,
+            ..Default::default()
+        });
+
+        let start = Instant::now();
+        let handles: Vec<_> = (0..writers)
+            .map(|writer| {
+                let backend = backend.clone();
+                thread::spawn(move || {
+                    for i in 0..WRITES_PER_THREAD {
+                        let key = format!("{}:{}", writer, i);
+                        backend.set(&key, RespBulkString::new("value").into());
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().expect("writer thread panicked");
+        }
+        let elapsed = start.elapsed();
+
+        let ops = (writers * WRITES_PER_THREAD) as f64 / elapsed.as_secs_f64();
+        println!(
+            "shards {:>4}: {} writers, {:>10.0} sets/s",
+            shard_amount, writers, ops
+        );
+    }
+}
@@ -0,0 +1,409 @@
+use std::{
+    fs,
+    io::Write,
+    path::Path,
+    sync::{Mutex, MutexGuard},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use bytes::BytesMut;
+use tokio::{
+    fs::OpenOptions,
+    io::{AsyncWriteExt, BufWriter},
+    sync::{mpsc, oneshot},
+    time::{interval, MissedTickBehavior},
+};
+use tracing::warn;
+
+use crate::{
+    backend::Backend,
+    cmd::{Command, CommandExecutor},
+    config::AppendFsync,
+    frame_len, FrameLimits, RespArray, RespBulkString, RespDecode, RespDecodeError, RespEncode,
+    RespFrame,
+};
+
+// how often appendfsync everysec syncs the file
+const FSYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+// the append only file: every successful write command, in the RESP array form the client
+// sent it under the command's original name, with a SELECT in front whenever the database
+// changes. ttls are recorded as absolute PXAT times
+#[derive(Debug)]
+pub struct Aof {
+    writer: AofWriter,
+    order: Mutex<()>,
+}
+
+#[derive(Debug)]
+enum AofWriter {
+    // appendfsync always, the command writes and syncs the file itself before it replies
+    Direct(Mutex<DirectWriter>),
+    // commands are queued and written by a background task, so they never wait on the disk
+    Queued(mpsc::UnboundedSender<AofOp>),
+}
+
+#[derive(Debug)]
+struct DirectWriter {
+    file: fs::File,
+    current_db: usize,
+    buf: BytesMut,
+}
+
+#[derive(Debug)]
+enum AofOp {
+    Append { db: usize, command: RespArray },
+    Flush(oneshot::Sender<()>),
+}
+
+impl Aof {
+    // opens `path` for appending and starts the writer, must run inside a tokio runtime
+    pub async fn open(path: impl AsRef<Path>, fsync: AppendFsync) -> Result<Self> {
+        let writer = if fsync == AppendFsync::Always {
+            let file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?;
+            AofWriter::Direct(Mutex::new(DirectWriter {
+                file,
+                // replay starts on database 0 as well
+                current_db: 0,
+                buf: BytesMut::new(),
+            }))
+        } else {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .await?;
+            let (tx, rx) = mpsc::unbounded_channel();
+            tokio::spawn(write_loop(BufWriter::new(file), rx, fsync));
+            AofWriter::Queued(tx)
+        };
+        Ok(Self {
+            writer,
+            order: Mutex::new(()),
+        })
+    }
+
+    // held while a write command runs and is appended, so writes are recorded in the
+    // order they were applied
+    pub fn write_order(&self) -> MutexGuard<'_, ()> {
+        self.order.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn append(&self, db: usize, command: RespArray) {
+        match &self.writer {
+            AofWriter::Direct(writer) => {
+                let mut writer = writer.lock().unwrap_or_else(|e| e.into_inner());
+                let DirectWriter {
+                    file,
+                    current_db,
+                    buf,
+                } = &mut *writer;
+                encode_command(buf, current_db, db, command);
+                let ret = file.write_all(buf).and_then(|()| file.sync_data());
+                if let Err(e) = ret {
+                    warn!("Failed to write the append only file: {}", e);
+                }
+                buf.clear();
+            }
+            AofWriter::Queued(tx) => {
+                // the writer only goes away with the runtime
+                let _ = tx.send(AofOp::Append { db, command });
+            }
+        }
+    }
+
+    // resolves once everything appended so far has been handed to the OS, and synced
+    // unless appendfsync is no
+    pub async fn flush(&self) {
+        let AofWriter::Queued(tx) = &self.writer else {
+            return;
+        };
+        let (done, rx) = oneshot::channel();
+        if tx.send(AofOp::Flush(done)).is_ok() {
+            let _ = rx.await;
+        }
+    }
+}
+
+// adds command to buf, behind a SELECT if it runs on another database than the last one
+fn encode_command(buf: &mut BytesMut, current_db: &mut usize, db: usize, command: RespArray) {
+    if db != *current_db {
+        let select = RespArray::new(vec![
+            RespBulkString::new("select").into(),
+            RespBulkString::from(db.to_string()).into(),
+        ]);
+        let _ = select.encode_to(buf);
+        *current_db = db;
+    }
+    let _ = command.encode_to(buf);
+}
+
+async fn write_loop(
+    mut file: BufWriter<tokio::fs::File>,
+    mut rx: mpsc::UnboundedReceiver<AofOp>,
+    fsync: AppendFsync,
+) {
+    // replay starts on database 0 as well
+    let mut current_db = 0;
+    let mut buf = BytesMut::new();
+    let mut flushed = Vec::new();
+    // whether something was written since the last sync
+    let mut dirty = false;
+    let mut fsync_timer = interval(FSYNC_INTERVAL);
+    fsync_timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        let op = tokio::select! {
+            op = rx.recv() => match op {
+                Some(op) => op,
+                None => break,
+            },
+            _ = fsync_timer.tick(), if fsync == AppendFsync::EverySec => {
+                if dirty {
+                    sync(&file).await;
+                    dirty = false;
+                }
+                continue;
+            }
+        };
+
+        // batch whatever queued up while the last write was in flight
+        let mut next = Some(op);
+        while let Some(op) = next {
+            match op {
+                AofOp::Append { db, command } => {
+                    encode_command(&mut buf, &mut current_db, db, command)
+                }
+                AofOp::Flush(done) => flushed.push(done),
+            }
+            next = rx.try_recv().ok();
+        }
+
+        let ret = match file.write_all(&buf).await {
+            Ok(()) => file.flush().await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = ret {
+            warn!("Failed to write the append only file: {}", e);
+        }
+        dirty |= !buf.is_empty();
+        buf.clear();
+        if !flushed.is_empty() {
+            if dirty && fsync != AppendFsync::No {
+                sync(&file).await;
+                dirty = false;
+            }
+            for done in flushed.drain(..) {
+                let _ = done.send(());
+            }
+        }
+    }
+}
+
+async fn sync(file: &BufWriter<tokio::fs::File>) {
+    if let Err(e) = file.get_ref().sync_data().await {
+        warn!("Failed to sync the append only file: {}", e);
+    }
+}
+
+// runs every command recorded in `path` against `backend`, returns how many there were.
+// a command cut off at the end of the file, say by a crash mid-write, is dropped and the
+// file truncated to the last complete one so new commands don't append to half a frame
+pub fn replay(backend: &Backend, path: impl AsRef<Path>) -> Result<usize> {
+    let path = path.as_ref();
+    let data = fs::read(path)?;
+    let mut buf = BytesMut::from(&data[..]);
+    // SELECTs in the file only affect this replay
+    let session = backend.new_session();
+    let mut commands = 0;
+    while !buf.is_empty() {
+        if frame_len(&buf, &FrameLimits::UNLIMITED) == Err(RespDecodeError::NotComplete) {
+            let valid_len = data.len() - buf.len();
+            warn!(
+                "Truncating {} bytes of an incomplete command at the end of {}",
+                buf.len(),
+                path.display()
+            );
+            fs::OpenOptions::new()
+                .write(true)
+                .open(path)?
+                .set_len(valid_len as u64)?;
+            break;
+        }
+        let RespFrame::Array(frame) = RespFrame::decode(&mut buf)? else {
+            return Err(anyhow!("command {}: not an array", commands));
+        };
+        Command::try_from(frame)
+            .map_err(|e| anyhow!("command {}: {}", commands, e))?
+            .execute(&session);
+        commands += 1;
+    }
+    Ok(commands)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use anyhow::Result;
+
+    use crate::{
+        backend::Backend,
+        cmd::{command, dispatch},
+        config::AppendFsync,
+        RespBulkString, RespFrame,
+    };
+
+    use super::{replay, Aof};
+
+    fn aof_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("simple-redis-{}-{}.aof", std::process::id(), name))
+    }
+
+    #[tokio::test]
+    async fn test_aof_replay_rebuilds_state() -> Result<()> {
+        let path = aof_path("replay");
+        let _ = fs::remove_file(&path);
+
+        let backend = Backend::new();
+        assert!(backend.enable_aof(Aof::open(&path, AppendFsync::default()).await?));
+        let conn = backend.new_session();
+        dispatch(command(&["set", "hello", "world"]), &conn);
+        dispatch(command(&["hset", "map", "a", "1"]), &conn);
+        dispatch(command(&["hincrby", "map", "a", "41"]), &conn);
+        // reads and failed writes are not recorded
+        dispatch(command(&["get", "hello"]), &conn);
+        let ret = dispatch(command(&["hset", "hello", "a", "1"]), &conn);
+        assert!(matches!(ret, RespFrame::Error(_)));
+        dispatch(command(&["select", "2"]), &conn);
+        dispatch(command(&["set", "hello", "db2"]), &conn);
+        backend.aof().unwrap().flush().await;
+
+        let loaded = Backend::new();
+        // 4 writes plus the SELECT in front of the last one
+        assert_eq!(replay(&loaded, &path)?, 5);
+        fs::remove_file(&path)?;
+
+        assert_eq!(
+            loaded.get("hello")?,
+            Some(RespBulkString::new("world").into())
+        );
+        assert_eq!(
+            loaded.hget("map", "a")?,
+            Some(RespBulkString::new("42").into())
+        );
+        assert!(loaded.select(2));
+        assert_eq!(
+            loaded.get("hello")?,
+            Some(RespBulkString::new("db2").into())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_aof_replay_truncates_incomplete_tail() -> Result<()> {
+        let path = aof_path("truncated");
+        let complete = b"*3\r\n$3\r\nset\r\n$3\r\nkey\r\n$5\r\nvalue\r\n";
+        let mut data = complete.to_vec();
+        data.extend_from_slice(b"*3\r\n$3\r\nset\r\n$3\r\nkey\r\n$5\r\nva");
+        fs::write(&path, &data)?;
+
+        let backend = Backend::new();
+        assert_eq!(replay(&backend, &path)?, 1);
+        assert_eq!(
+            backend.get("key")?,
+            Some(RespBulkString::new("value").into())
+        );
+        assert_eq!(fs::read(&path)?, complete);
+        fs::remove_file(&path)?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_aof_records_absolute_expiry() -> Result<()> {
+        let path = aof_path("expiry");
+        let _ = fs::remove_file(&path);
+
+        let backend = Backend::new();
+        assert!(backend.enable_aof(Aof::open(&path, AppendFsync::default()).await?));
+        dispatch(command(&["setex", "a", "100", "1"]), &backend);
+        dispatch(command(&["set", "b", "2", "PX", "100000"]), &backend);
+        backend.aof().unwrap().flush().await;
+
+        let recorded = String::from_utf8(fs::read(&path)?)?;
+        assert!(!recorded.contains("setex") && !recorded.contains("PX\r\n"));
+        assert_eq!(recorded.matches("pxat").count(), 2);
+
+        let loaded = Backend::new();
+        assert_eq!(replay(&loaded, &path)?, 2);
+        assert!(loaded.ttl("a").unwrap() > std::time::Duration::from_secs(98));
+        assert!(loaded.ttl("b").unwrap() > std::time::Duration::from_secs(98));
+
+        // keys that expired while the server was down stay gone
+        fs::write(
+            &path,
+            "*5\r\n$3\r\nset\r\n$1\r\na\r\n$1\r\n1\r\n$4\r\npxat\r\n$4\r\n1000\r\n",
+        )?;
+        let loaded = Backend::new();
+        assert_eq!(replay(&loaded, &path)?, 1);
+        assert_eq!(loaded.get("a")?, None);
+        fs::remove_file(&path)?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_aof_write_waits_for_write_order() -> Result<()> {
+        let path = aof_path("order");
+        let _ = fs::remove_file(&path);
+
+        let backend = Backend::new();
+        assert!(backend.enable_aof(Aof::open(&path, AppendFsync::default()).await?));
+        // while another write holds the lock, this one neither runs nor gets appended
+        let order = backend.aof().unwrap().write_order();
+        let conn = backend.new_session();
+        let writer = std::thread::spawn(move || dispatch(command(&["set", "k", "v"]), &conn));
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(!writer.is_finished());
+        assert_eq!(backend.get("k")?, None);
+
+        drop(order);
+        writer.join().unwrap();
+        assert_eq!(backend.get("k")?, Some(RespBulkString::new("v").into()));
+        backend.aof().unwrap().flush().await;
+        fs::remove_file(&path)?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_aof_always_writes_before_replying() -> Result<()> {
+        let path = aof_path("always");
+        let _ = fs::remove_file(&path);
+
+        let backend = Backend::new();
+        assert!(backend.enable_aof(Aof::open(&path, AppendFsync::Always).await?));
+        let conn = backend.new_session();
+        dispatch(command(&["set", "k", "v"]), &conn);
+        // nothing is flushed, the reply alone means the command is in the file
+        assert_eq!(
+            fs::read(&path)?,
+            b"*3\r\n$3\r\nset\r\n$1\r\nk\r\n$1\r\nv\r\n"
+        );
+
+        dispatch(command(&["select", "1"]), &conn);
+        dispatch(command(&["set", "k", "v"]), &conn);
+        let loaded = Backend::new();
+        assert_eq!(replay(&loaded, &path)?, 3);
+        assert!(loaded.select(1));
+        assert_eq!(loaded.get("k")?, Some(RespBulkString::new("v").into()));
+        fs::remove_file(&path)?;
+
+        Ok(())
+    }
+}
@@ -1,33 +1,367 @@
-use std::{ops::Deref, sync::Arc};
+mod glob;
+mod notify;
+mod session;
+mod snapshot;
+mod value;
 
-use dashmap::DashMap;
+use std::{
+    collections::{hash_map::RandomState, HashMap, HashSet, VecDeque},
+    hash::BuildHasher,
+    mem::size_of,
+    ops::{Deref, Range},
+    sync::{
+        atomic::{AtomicU16, AtomicU64, AtomicUsize, Ordering},
+        Arc, OnceLock,
+    },
+    time::{Duration, Instant},
+};
 
-use crate::RespFrame;
+use dashmap::{mapref::entry::Entry, DashMap};
+use thiserror::Error;
+use tokio::sync::broadcast;
 
+use crate::{aof::Aof, config::ServerConfig, RespBulkString, RespFrame, RespSimpleError};
+
+use self::value::detach;
+
+pub use glob::glob_match;
+pub use notify::{KeyEvent, NotifyFlags};
+pub use session::Session;
+pub use value::Value;
+
+// messages a channel holds for each subscription's forwarding task, which moves them into
+// the subscriber's bounded push queue right away
+const CHANNEL_CAPACITY: usize = 1024;
+
+// loops bounded by `command_deadline` look at the clock once per this many items
+const DEADLINE_CHECK_INTERVAL: usize = 1024;
+
+// SET's optional behaviour, the defaults replace the value and drop its ttl
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SetOptions {
+    pub condition: Option<SetCondition>,
+    pub expiry: SetExpiry,
+    // hand back the old value, like GETSET
+    pub get: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SetCondition {
+    // NX
+    IfMissing,
+    // XX
+    IfExists,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum SetExpiry {
+    #[default]
+    Clear,
+    // EX and PX
+    After(Duration),
+    // EXAT and PXAT, already converted from unix time
+    At(Instant),
+    // KEEPTTL
+    Keep,
+}
+
+// shared storage plus the state of the connection this handle belongs to,
+// each connection gets its own handle through `new_session`
 #[derive(Debug, Clone)]
-pub struct Backend(Arc<BackendInner>);
+pub struct Backend {
+    inner: Arc<BackendInner>,
+    session: Arc<Session>,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum BackendError {
+    #[error("WRONGTYPE Operation against a key holding the wrong kind of value")]
+    WrongType,
+
+    #[error("ERR string exceeds maximum allowed size")]
+    StringTooLong,
+
+    #[error("ERR hash value is not an integer")]
+    NotInteger,
+
+    #[error("ERR increment or decrement would overflow")]
+    Overflow,
+
+    #[error("ERR command execution timed out")]
+    Timeout,
+
+    #[error("ERR invalid expire time")]
+    InvalidExpireTime,
+
+    #[error("ERR Unknown option or number of arguments for CONFIG SET - '{0}'")]
+    UnknownConfig(String),
+
+    #[error("ERR CONFIG SET failed (possibly related to argument '{0}') - Invalid argument")]
+    InvalidConfigValue(String),
+}
+
+// commands reply with the error text as is
+impl From<BackendError> for RespFrame {
+    fn from(err: BackendError) -> Self {
+        RespSimpleError::new(err.to_string()).into()
+    }
+}
 
 impl Backend {
     pub fn new() -> Self {
-        Self(Arc::new(BackendInner::new()))
+        Self::from_inner(BackendInner::new())
+    }
+
+    pub fn with_config(config: ServerConfig) -> Self {
+        Self::from_inner(BackendInner::with_config(config))
+    }
+
+    fn from_inner(inner: BackendInner) -> Self {
+        let session = Session::new(0, inner.pubsub_clients.clone());
+        Self {
+            inner: Arc::new(inner),
+            session: Arc::new(session),
+        }
+    }
+
+    pub fn new_session(&self) -> Self {
+        let id = self.next_client_id.fetch_add(1, Ordering::Relaxed);
+        Self {
+            inner: self.inner.clone(),
+            session: Arc::new(Session::new(id, self.pubsub_clients.clone())),
+        }
+    }
+
+    // counts a client as connected until the returned guard is dropped
+    pub fn client_connected(&self) -> ClientGuard {
+        self.connected_clients.fetch_add(1, Ordering::Relaxed);
+        ClientGuard {
+            backend: self.clone(),
+        }
+    }
+
+    pub fn connected_clients(&self) -> usize {
+        self.connected_clients.load(Ordering::Relaxed)
+    }
+
+    // connections subscribed to at least one channel
+    pub fn pubsub_clients(&self) -> usize {
+        self.pubsub_clients.load(Ordering::Relaxed)
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    // the database selected by this connection
+    pub fn db(&self) -> &Db {
+        &self.dbs[self.session.db()]
+    }
+
+    // the selected database, with key already gone if it has expired
+    fn db_for(&self, key: &str) -> &Db {
+        let db = self.db();
+        if db.expire_if_due(key) {
+            self.notify(KeyEvent::Expired, key);
+        }
+        db
+    }
+
+    // publishes a keyspace notification for key, if notify-keyspace-events asks for one.
+    // keys expire lazily, so `expired` is published once an expired key is next touched
+    fn notify(&self, event: KeyEvent, key: &str) {
+        let flags = NotifyFlags::from_bits(self.notify_flags.load(Ordering::Relaxed));
+        if !flags.wants(event) {
+            return;
+        }
+        let db = self.session.db();
+        if flags.keyspace() {
+            let channel = format!("__keyspace@{}__:{}", db, key);
+            self.publish(&channel, RespBulkString::new(event.name()).into());
+        }
+        if flags.keyevent() {
+            let channel = format!("__keyevent@{}__:{}", db, event.name());
+            self.publish(&channel, RespBulkString::new(key).into());
+        }
+    }
+
+    pub fn aof(&self) -> Option<&Aof> {
+        self.aof.get()
+    }
+
+    // starts recording write commands, false if a file is already attached
+    pub fn enable_aof(&self, aof: Aof) -> bool {
+        self.aof.set(aof).is_ok()
+    }
+
+    pub fn select(&self, index: usize) -> bool {
+        if index >= self.dbs.len() {
+            return false;
+        }
+        self.session.select(index);
+        true
+    }
+}
+
+// held by a connection for as long as it is open, see `Backend::client_connected`
+#[derive(Debug)]
+pub struct ClientGuard {
+    backend: Backend,
+}
+
+impl Drop for ClientGuard {
+    fn drop(&mut self) {
+        self.backend
+            .connected_clients
+            .fetch_sub(1, Ordering::Relaxed);
     }
 }
 
 #[derive(Debug)]
 pub struct BackendInner {
-    pub map: DashMap<String, RespFrame>,
-    pub hmap: DashMap<String, DashMap<String, RespFrame>>,
+    pub dbs: Vec<Db>,
+    pub config: ServerConfig,
+    pub runtime_config: DashMap<String, String>,
+    pub channels: DashMap<String, broadcast::Sender<RespFrame>>,
+    // random id of this server instance, reported by INFO
+    pub run_id: String,
+    // set once the append only file is open, see `enable_aof`
+    aof: OnceLock<Aof>,
+    next_client_id: AtomicU64,
+    connected_clients: AtomicUsize,
+    // notify-keyspace-events, parsed whenever CONFIG SET changes it
+    notify_flags: AtomicU16,
+    // shared with every session, which keeps it up to date as it subscribes
+    pubsub_clients: Arc<AtomicUsize>,
+}
+
+// one logical database, picked per connection with SELECT
+#[derive(Debug, Default)]
+pub struct Db {
+    pub data: DashMap<String, Value>,
+    // key -> when it expires, keys missing here never do. expired keys are only
+    // removed once something touches them
+    pub expires: DashMap<String, Instant>,
+}
+
+impl Db {
+    pub fn with_shard_amount(shard_amount: usize) -> Self {
+        Self {
+            data: DashMap::with_shard_amount(shard_amount),
+            expires: DashMap::with_shard_amount(shard_amount),
+        }
+    }
+
+    pub fn is_expired(&self, key: &str) -> bool {
+        matches!(self.expires.get(key), Some(at) if *at <= Instant::now())
+    }
+
+    // true if key had expired and was removed
+    fn expire_if_due(&self, key: &str) -> bool {
+        let now = Instant::now();
+        if self.expires.remove_if(key, |_, at| *at <= now).is_some() {
+            self.data.remove(key);
+            return true;
+        }
+        false
+    }
 }
 
 impl BackendInner {
     fn new() -> Self {
+        Self::with_config(ServerConfig::default())
+    }
+
+    fn with_config(config: ServerConfig) -> Self {
+        let runtime_config = default_runtime_config();
+        if config.appendonly {
+            runtime_config.insert("appendonly".to_string(), "yes".to_string());
+        }
+        runtime_config.insert(
+            "appendfsync".to_string(),
+            config.appendfsync.as_str().to_string(),
+        );
         Self {
-            map: DashMap::new(),
-            hmap: DashMap::new(),
+            dbs: (0..config.databases)
+                .map(|_| Db::with_shard_amount(config.shard_amount))
+                .collect(),
+            config,
+            runtime_config,
+            channels: DashMap::new(),
+            run_id: generate_run_id(),
+            aof: OnceLock::new(),
+            next_client_id: AtomicU64::new(1),
+            connected_clients: AtomicUsize::new(0),
+            notify_flags: AtomicU16::new(0),
+            pubsub_clients: Arc::new(AtomicUsize::new(0)),
         }
     }
 }
 
+pub fn check_deadline(deadline: Option<Instant>) -> Result<(), BackendError> {
+    match deadline {
+        Some(deadline) if Instant::now() >= deadline => Err(BackendError::Timeout),
+        _ => Ok(()),
+    }
+}
+
+// yields the items of iter, failing with `Timeout` once the deadline has passed
+fn within_deadline<I: Iterator>(
+    iter: I,
+    deadline: Option<Instant>,
+) -> impl Iterator<Item = Result<I::Item, BackendError>> {
+    iter.enumerate().map(move |(i, item)| {
+        if i % DEADLINE_CHECK_INTERVAL == 0 {
+            check_deadline(deadline)?;
+        }
+        Ok(item)
+    })
+}
+
+// resolves an inclusive start..=end pair where negative offsets count from the end,
+// clamped to a sequence of len items like redis does for GETRANGE and LRANGE
+pub fn index_range(len: usize, start: i64, end: i64) -> Range<usize> {
+    let len = len as i64;
+    let start = if start < 0 {
+        (len + start).max(0)
+    } else {
+        start
+    };
+    let end = if end < 0 { len + end } else { end.min(len - 1) };
+    if start > end || len == 0 {
+        return 0..0;
+    }
+    start as usize..end as usize + 1
+}
+
+fn key_memory_usage(key: &str, value: &Value) -> usize {
+    size_of::<String>() + key.len() + value.memory_usage()
+}
+
+// 40 hex chars like redis, std's randomly keyed hasher is enough of a random source here
+fn generate_run_id() -> String {
+    let state = RandomState::new();
+    (0..3u64)
+        .map(|i| format!("{:016x}", state.hash_one((i, std::process::id()))))
+        .collect::<String>()[..40]
+        .to_string()
+}
+
+// parameters reported by CONFIG GET, clients such as redis-cli ask for a few of these on connect
+fn default_runtime_config() -> DashMap<String, String> {
+    [
+        ("maxmemory", "0"),
+        ("maxmemory-policy", "noeviction"),
+        ("appendonly", "no"),
+        ("save", ""),
+        ("timeout", "0"),
+        ("notify-keyspace-events", ""),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect()
+}
+
 impl BackendInner {
     fn default() -> BackendInner {
         Self::new()
@@ -37,41 +371,675 @@ impl BackendInner {
 impl Deref for Backend {
     type Target = BackendInner;
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.inner
     }
 }
 
 impl Default for Backend {
     fn default() -> Self {
-        Self(Arc::new(BackendInner::default()))
+        Self::from_inner(BackendInner::default())
     }
 }
 
 impl Backend {
-    pub fn get(&self, key: &str) -> Option<RespFrame> {
-        self.map.get(key).map(|v| v.value().clone())
+    pub fn get(&self, key: &str) -> Result<Option<RespFrame>, BackendError> {
+        match self.db_for(key).data.get(key).as_deref() {
+            Some(Value::String(value)) => Ok(Some(value.clone())),
+            Some(_) => Err(BackendError::WrongType),
+            None => Ok(None),
+        }
     }
 
+    // replaces the value and any expiration the key had
     pub fn set(&self, key: &str, value: RespFrame) {
-        self.map.insert(key.to_string(), value);
+        let db = self.db();
+        db.data
+            .insert(key.to_string(), Value::String(detach(value)));
+        db.expires.remove(key);
+        self.notify(KeyEvent::Set, key);
     }
 
-    pub fn hget(&self, key: &str, field: &str) -> Option<RespFrame> {
-        self.hmap
-            .get(key)
-            .and_then(|v| v.get(field).map(|v| v.value().clone()))
+    // returns whether the value was stored and, with `options.get`, the old value. a key of
+    // another type fails GET, so the SET is aborted along with it. without an expiry of its
+    // own the key gets `default_ttl`, if one is configured
+    pub fn set_with_options(
+        &self,
+        key: &str,
+        value: RespFrame,
+        options: &SetOptions,
+    ) -> Result<(bool, Option<RespFrame>), BackendError> {
+        let expiry = match (options.expiry, self.config.default_ttl) {
+            (SetExpiry::Clear, Some(ttl)) => SetExpiry::After(ttl),
+            (expiry, _) => expiry,
+        };
+        let expires_at = match expiry {
+            SetExpiry::After(ttl) => Some(
+                Instant::now()
+                    .checked_add(ttl)
+                    .ok_or(BackendError::InvalidExpireTime)?,
+            ),
+            SetExpiry::At(at) => Some(at),
+            _ => None,
+        };
+
+        let db = self.db_for(key);
+        let (stored, old) = match db.data.entry(key.to_string()) {
+            Entry::Occupied(mut entry) => {
+                let old = match entry.get() {
+                    Value::String(old) => options.get.then(|| old.clone()),
+                    _ if options.get => return Err(BackendError::WrongType),
+                    _ => None,
+                };
+                if options.condition == Some(SetCondition::IfMissing) {
+                    return Ok((false, old));
+                }
+                entry.insert(Value::String(detach(value)));
+                (true, old)
+            }
+            Entry::Vacant(entry) => {
+                if options.condition == Some(SetCondition::IfExists) {
+                    return Ok((false, None));
+                }
+                entry.insert(Value::String(detach(value)));
+                (true, None)
+            }
+        };
+
+        match (expiry, expires_at) {
+            (_, Some(at)) => {
+                db.expires.insert(key.to_string(), at);
+            }
+            (SetExpiry::Keep, _) => {}
+            _ => {
+                db.expires.remove(key);
+            }
+        }
+        self.notify(KeyEvent::Set, key);
+        Ok((stored, old))
+    }
+
+    pub fn set_ex(&self, key: &str, value: RespFrame, ttl: Duration) -> Result<(), BackendError> {
+        let at = Instant::now()
+            .checked_add(ttl)
+            .ok_or(BackendError::InvalidExpireTime)?;
+        let db = self.db();
+        db.data
+            .insert(key.to_string(), Value::String(detach(value)));
+        db.expires.insert(key.to_string(), at);
+        self.notify(KeyEvent::Set, key);
+        Ok(())
     }
 
-    pub fn hset(&self, key: &str, field: &str, value: RespFrame) {
-        let hmap: dashmap::mapref::one::RefMut<
-            String,
-            DashMap<String, RespFrame>,
-            std::hash::RandomState,
-        > = self.hmap.entry(key.to_string()).or_default();
-        hmap.insert(field.to_string(), value);
+    // time left before key expires, None if it doesn't exist or never expires
+    pub fn ttl(&self, key: &str) -> Option<Duration> {
+        let at = *self.db_for(key).expires.get(key)?;
+        Some(at.saturating_duration_since(Instant::now()))
     }
 
-    pub fn hgetall(&self, key: &str) -> Option<DashMap<String, RespFrame>> {
-        self.hmap.get(key).map(|v| v.value().clone())
+    // a new key gets `default_ttl`, if one is configured
+    pub fn set_nx(&self, key: &str, value: RespFrame) -> bool {
+        let db = self.db_for(key);
+        match db.data.entry(key.to_string()) {
+            Entry::Occupied(_) => return false,
+            Entry::Vacant(entry) => {
+                entry.insert(Value::String(detach(value)));
+            }
+        }
+        if let Some(at) = self.default_expiry() {
+            db.expires.insert(key.to_string(), at);
+        }
+        self.notify(KeyEvent::Set, key);
+        true
+    }
+
+    // when a key stored now without an expiry of its own expires, see `default_ttl`
+    fn default_expiry(&self) -> Option<Instant> {
+        Instant::now().checked_add(self.config.default_ttl?)
+    }
+
+    // replaces the ttl like SET does, with `default_ttl` if one is configured
+
+    pub fn get_set(&self, key: &str, value: RespFrame) -> Result<Option<RespFrame>, BackendError> {
+        let db = self.db_for(key);
+        let old = match db.data.entry(key.to_string()) {
+            Entry::Occupied(mut entry) => match entry.get_mut() {
+                Value::String(old) => Some(std::mem::replace(old, detach(value))),
+                _ => return Err(BackendError::WrongType),
+            },
+            Entry::Vacant(entry) => {
+                entry.insert(Value::String(detach(value)));
+                None
+            }
+        };
+        match self.default_expiry() {
+            Some(at) => {
+                db.expires.insert(key.to_string(), at);
+            }
+            None => {
+                db.expires.remove(key);
+            }
+        }
+        self.notify(KeyEvent::Set, key);
+        Ok(old)
+    }
+
+    pub fn get_del(&self, key: &str) -> Result<Option<RespFrame>, BackendError> {
+        let db = self.db_for(key);
+        let removed = db
+            .data
+            .remove_if(key, |_, value| matches!(value, Value::String(_)));
+        match removed {
+            Some((_, Value::String(value))) => {
+                db.expires.remove(key);
+                self.notify(KeyEvent::Del, key);
+                Ok(Some(value))
+            }
+            _ if db.data.contains_key(key) => Err(BackendError::WrongType),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn check_string_size(&self, len: usize) -> Result<(), BackendError> {
+        if len > self.config.proto_max_bulk_len {
+            return Err(BackendError::StringTooLong);
+        }
+        Ok(())
+    }
+
+    pub fn append(&self, key: &str, value: &[u8]) -> Result<usize, BackendError> {
+        let len = match self.db_for(key).data.entry(key.to_string()) {
+            Entry::Occupied(mut entry) => {
+                let Value::String(frame) = entry.get_mut() else {
+                    return Err(BackendError::WrongType);
+                };
+                // counters share the string type, so continue from their decimal text
+                if !matches!(frame, RespFrame::BulkString(_)) {
+                    let current = frame.as_bytes().ok_or(BackendError::WrongType)?;
+                    *frame = RespBulkString::new(current.into_owned()).into();
+                }
+                match frame {
+                    RespFrame::BulkString(ref mut s) => {
+                        self.check_string_size(s.len() + value.len())?;
+                        // reuses the allocation when this is the only handle to the data
+                        let mut data = Vec::from(std::mem::take(&mut s.0));
+                        data.extend_from_slice(value);
+                        s.0 = data.into();
+                        Ok(s.len())
+                    }
+                    _ => Err(BackendError::WrongType),
+                }
+            }
+            Entry::Vacant(entry) => {
+                self.check_string_size(value.len())?;
+                entry.insert(Value::String(RespBulkString::new(value).into()));
+                Ok(value.len())
+            }
+        }?;
+        self.notify(KeyEvent::Append, key);
+        Ok(len)
+    }
+
+    pub fn key_type(&self, key: &str) -> &'static str {
+        match self.db_for(key).data.get(key) {
+            Some(value) => value.type_name(),
+            None => "none",
+        }
+    }
+
+    pub fn dbsize(&self) -> usize {
+        self.db().data.len()
+    }
+
+    // approximate bytes used by a key and its value, None if the key doesn't exist
+    pub fn memory_usage(&self, key: &str) -> Option<usize> {
+        let value = self.db_for(key).data.get(key)?;
+        Some(key_memory_usage(key, &value))
+    }
+
+    // approximate bytes used by the keys and values of all databases
+    pub fn dataset_bytes(&self) -> usize {
+        self.dbs
+            .iter()
+            .flat_map(|db| db.data.iter())
+            .map(|entry| key_memory_usage(entry.key(), entry.value()))
+            .sum()
+    }
+
+    pub fn flushdb(&self) {
+        self.db().data.clear();
+        self.db().expires.clear();
+    }
+
+    pub fn keys(&self, pattern: &str) -> Result<Vec<String>, BackendError> {
+        let pattern = pattern.as_bytes();
+        let deadline = self.deadline();
+        let mut keys = Vec::new();
+        let db = self.db();
+        for (i, entry) in db.data.iter().enumerate() {
+            if i % DEADLINE_CHECK_INTERVAL == 0 {
+                check_deadline(deadline)?;
+            }
+            if glob_match(pattern, entry.key().as_bytes()) && !db.is_expired(entry.key()) {
+                keys.push(entry.key().to_owned());
+            }
+        }
+        Ok(keys)
+    }
+
+    // when a command started now has to give up, if `command_deadline` is set
+    pub fn deadline(&self) -> Option<Instant> {
+        self.config
+            .command_deadline
+            .map(|budget| Instant::now() + budget)
+    }
+
+    // runs f on the hash stored at key, None if the key doesn't exist
+    fn with_hash<T>(
+        &self,
+        key: &str,
+        f: impl FnOnce(&HashMap<String, RespFrame>) -> T,
+    ) -> Result<Option<T>, BackendError> {
+        match self.db_for(key).data.get(key).as_deref() {
+            Some(Value::Hash(hash)) => Ok(Some(f(hash))),
+            Some(_) => Err(BackendError::WrongType),
+            None => Ok(None),
+        }
+    }
+
+    // runs f on the hash stored at key, creating an empty one if the key doesn't exist
+    fn with_hash_mut<T>(
+        &self,
+        key: &str,
+        f: impl FnOnce(&mut HashMap<String, RespFrame>) -> T,
+    ) -> Result<T, BackendError> {
+        let mut entry = self
+            .db_for(key)
+            .data
+            .entry(key.to_string())
+            .or_insert_with(|| Value::Hash(HashMap::new()));
+        match entry.value_mut() {
+            Value::Hash(hash) => Ok(f(hash)),
+            _ => Err(BackendError::WrongType),
+        }
+    }
+
+    pub fn hget(&self, key: &str, field: &str) -> Result<Option<RespFrame>, BackendError> {
+        Ok(self
+            .with_hash(key, |hash| hash.get(field).cloned())?
+            .flatten())
+    }
+
+    pub fn hset(&self, key: &str, field: &str, value: RespFrame) -> Result<(), BackendError> {
+        self.with_hash_mut(key, |hash| {
+            hash.insert(field.to_string(), detach(value));
+        })?;
+        self.notify(KeyEvent::HSet, key);
+        Ok(())
+    }
+
+    pub fn hmget(
+        &self,
+        key: &str,
+        fields: &[String],
+    ) -> Result<Option<Vec<Option<RespFrame>>>, BackendError> {
+        self.with_hash(key, |hash| {
+            fields
+                .iter()
+                .map(|field| hash.get(field).cloned())
+                .collect()
+        })
+    }
+
+    pub fn hexists(&self, key: &str, field: &str) -> Result<bool, BackendError> {
+        Ok(self
+            .with_hash(key, |hash| hash.contains_key(field))?
+            .unwrap_or(false))
+    }
+
+    pub fn hlen(&self, key: &str) -> Result<usize, BackendError> {
+        Ok(self.with_hash(key, |hash| hash.len())?.unwrap_or(0))
+    }
+
+    pub fn hdel(&self, key: &str, field: &str) -> Result<bool, BackendError> {
+        let removed = match self.db_for(key).data.get_mut(key).as_deref_mut() {
+            Some(Value::Hash(hash)) => hash.remove(field).is_some(),
+            Some(_) => return Err(BackendError::WrongType),
+            None => false,
+        };
+        if removed {
+            let db = self.db();
+            let emptied = db.data.remove_if(
+                key,
+                |_, value| matches!(value, Value::Hash(hash) if hash.is_empty()),
+            );
+            self.notify(KeyEvent::HDel, key);
+            if emptied.is_some() {
+                db.expires.remove(key);
+                self.notify(KeyEvent::Del, key);
+            }
+        }
+        Ok(removed)
+    }
+
+    pub fn hincrby(&self, key: &str, field: &str, increment: i64) -> Result<i64, BackendError> {
+        let value = self.with_hash_mut(key, |hash| {
+            let entry = hash
+                .entry(field.to_string())
+                .or_insert_with(|| RespBulkString::new("0").into());
+
+            let current = match entry {
+                RespFrame::BulkString(s) => std::str::from_utf8(s)
+                    .ok()
+                    .and_then(|s| s.parse::<i64>().ok())
+                    .ok_or(BackendError::NotInteger)?,
+                RespFrame::Integer(i) => **i,
+                _ => return Err(BackendError::NotInteger),
+            };
+            let value = current
+                .checked_add(increment)
+                .ok_or(BackendError::Overflow)?;
+            *entry = RespBulkString::from(value.to_string()).into();
+            Ok(value)
+        })??;
+        self.notify(KeyEvent::HIncrBy, key);
+        Ok(value)
+    }
+
+    pub fn hgetall(&self, key: &str) -> Result<Option<HashMap<String, RespFrame>>, BackendError> {
+        self.with_hash(key, |hash| hash.clone())
+    }
+
+    pub fn hkeys(&self, key: &str) -> Result<Vec<String>, BackendError> {
+        Ok(self
+            .with_hash(key, |hash| hash.keys().cloned().collect())?
+            .unwrap_or_default())
+    }
+
+    pub fn hvals(&self, key: &str) -> Result<Vec<RespFrame>, BackendError> {
+        Ok(self
+            .with_hash(key, |hash| hash.values().cloned().collect())?
+            .unwrap_or_default())
+    }
+
+    // runs f on the list stored at key, None if the key doesn't exist
+    fn with_list<T>(
+        &self,
+        key: &str,
+        f: impl FnOnce(&VecDeque<RespFrame>) -> T,
+    ) -> Result<Option<T>, BackendError> {
+        match self.db_for(key).data.get(key).as_deref() {
+            Some(Value::List(list)) => Ok(Some(f(list))),
+            Some(_) => Err(BackendError::WrongType),
+            None => Ok(None),
+        }
+    }
+
+    // runs f on the list stored at key, creating an empty one if the key doesn't exist
+    fn with_list_mut<T>(
+        &self,
+        key: &str,
+        f: impl FnOnce(&mut VecDeque<RespFrame>) -> T,
+    ) -> Result<T, BackendError> {
+        let mut entry = self
+            .db_for(key)
+            .data
+            .entry(key.to_string())
+            .or_insert_with(|| Value::List(VecDeque::new()));
+        match entry.value_mut() {
+            Value::List(list) => Ok(f(list)),
+            _ => Err(BackendError::WrongType),
+        }
+    }
+
+    // pushes the values to the head one at a time, so they end up in reverse order.
+    // returns the length of the list afterwards
+    pub fn lpush(&self, key: &str, values: Vec<RespFrame>) -> Result<usize, BackendError> {
+        let len = self.with_list_mut(key, |list| {
+            for value in values {
+                list.push_front(detach(value));
+            }
+            list.len()
+        })?;
+        self.notify(KeyEvent::LPush, key);
+        Ok(len)
+    }
+
+    pub fn rpush(&self, key: &str, values: Vec<RespFrame>) -> Result<usize, BackendError> {
+        let len = self.with_list_mut(key, |list| {
+            list.extend(values.into_iter().map(detach));
+            list.len()
+        })?;
+        self.notify(KeyEvent::RPush, key);
+        Ok(len)
+    }
+
+    // pops up to count values from the head, None if the key doesn't exist
+    pub fn lpop(&self, key: &str, count: usize) -> Result<Option<Vec<RespFrame>>, BackendError> {
+        self.list_pop(key, count, VecDeque::pop_front, KeyEvent::LPop)
+    }
+
+    // pops up to count values from the tail, None if the key doesn't exist
+    pub fn rpop(&self, key: &str, count: usize) -> Result<Option<Vec<RespFrame>>, BackendError> {
+        self.list_pop(key, count, VecDeque::pop_back, KeyEvent::RPop)
+    }
+
+    fn list_pop(
+        &self,
+        key: &str,
+        count: usize,
+        pop: fn(&mut VecDeque<RespFrame>) -> Option<RespFrame>,
+        event: KeyEvent,
+    ) -> Result<Option<Vec<RespFrame>>, BackendError> {
+        let db = self.db_for(key);
+        let popped: Vec<_> = match db.data.get_mut(key).as_deref_mut() {
+            Some(Value::List(list)) => (0..count).map_while(|_| pop(list)).collect(),
+            Some(_) => return Err(BackendError::WrongType),
+            None => return Ok(None),
+        };
+        if !popped.is_empty() {
+            self.notify(event, key);
+        }
+        // like redis, a list that runs out of values stops existing
+        let emptied = db.data.remove_if(
+            key,
+            |_, value| matches!(value, Value::List(list) if list.is_empty()),
+        );
+        if emptied.is_some() {
+            db.expires.remove(key);
+            self.notify(KeyEvent::Del, key);
+        }
+        Ok(Some(popped))
+    }
+
+    pub fn llen(&self, key: &str) -> Result<usize, BackendError> {
+        Ok(self.with_list(key, |list| list.len())?.unwrap_or(0))
+    }
+
+    // inclusive range, negative indexes count from the tail
+    pub fn lrange(&self, key: &str, start: i64, stop: i64) -> Result<Vec<RespFrame>, BackendError> {
+        let deadline = self.deadline();
+        Ok(self
+            .with_list(key, |list| {
+                let range = list.range(index_range(list.len(), start, stop));
+                within_deadline(range.cloned(), deadline).collect::<Result<_, _>>()
+            })?
+            .transpose()?
+            .unwrap_or_default())
+    }
+
+    // runs f on the set stored at key, None if the key doesn't exist
+    fn with_set<T>(
+        &self,
+        key: &str,
+        f: impl FnOnce(&HashSet<String>) -> T,
+    ) -> Result<Option<T>, BackendError> {
+        match self.db_for(key).data.get(key).as_deref() {
+            Some(Value::Set(set)) => Ok(Some(f(set))),
+            Some(_) => Err(BackendError::WrongType),
+            None => Ok(None),
+        }
+    }
+
+    // returns how many of the members weren't in the set yet
+    pub fn sadd(&self, key: &str, members: Vec<String>) -> Result<usize, BackendError> {
+        let mut entry = self
+            .db_for(key)
+            .data
+            .entry(key.to_string())
+            .or_insert_with(|| Value::Set(HashSet::new()));
+        let added = match entry.value_mut() {
+            Value::Set(set) => members
+                .into_iter()
+                .filter(|member| set.insert(member.clone()))
+                .count(),
+            _ => return Err(BackendError::WrongType),
+        };
+        drop(entry);
+        if added > 0 {
+            self.notify(KeyEvent::SAdd, key);
+        }
+        Ok(added)
+    }
+
+    // returns how many of the members were removed
+    pub fn srem(&self, key: &str, members: &[String]) -> Result<usize, BackendError> {
+        let db = self.db_for(key);
+        let removed = match db.data.get_mut(key).as_deref_mut() {
+            Some(Value::Set(set)) => members.iter().filter(|member| set.remove(*member)).count(),
+            Some(_) => return Err(BackendError::WrongType),
+            None => 0,
+        };
+        if removed > 0 {
+            let emptied = db.data.remove_if(
+                key,
+                |_, value| matches!(value, Value::Set(set) if set.is_empty()),
+            );
+            self.notify(KeyEvent::SRem, key);
+            if emptied.is_some() {
+                db.expires.remove(key);
+                self.notify(KeyEvent::Del, key);
+            }
+        }
+        Ok(removed)
+    }
+
+    pub fn sismember(&self, key: &str, member: &str) -> Result<bool, BackendError> {
+        Ok(self
+            .with_set(key, |set| set.contains(member))?
+            .unwrap_or(false))
+    }
+
+    pub fn smembers(&self, key: &str) -> Result<Vec<String>, BackendError> {
+        Ok(self
+            .with_set(key, |set| set.iter().cloned().collect())?
+            .unwrap_or_default())
+    }
+
+    pub fn scard(&self, key: &str) -> Result<usize, BackendError> {
+        Ok(self.with_set(key, |set| set.len())?.unwrap_or(0))
+    }
+
+    // SINTER, SUNION and SDIFF copy one set at a time rather than holding several
+    // shard locks at once. a missing key counts as an empty set
+    fn set_or_empty(
+        &self,
+        key: &str,
+        deadline: Option<Instant>,
+    ) -> Result<HashSet<String>, BackendError> {
+        Ok(self
+            .with_set(key, |set| {
+                within_deadline(set.iter().cloned(), deadline).collect::<Result<_, _>>()
+            })?
+            .transpose()?
+            .unwrap_or_default())
+    }
+
+    pub fn sinter(&self, keys: &[String]) -> Result<HashSet<String>, BackendError> {
+        self.set_filter(keys, true)
+    }
+
+    pub fn sunion(&self, keys: &[String]) -> Result<HashSet<String>, BackendError> {
+        let deadline = self.deadline();
+        let mut result = HashSet::new();
+        for key in keys {
+            result.extend(self.set_or_empty(key, deadline)?);
+        }
+        Ok(result)
+    }
+
+    // members of the first set that are in none of the others
+    pub fn sdiff(&self, keys: &[String]) -> Result<HashSet<String>, BackendError> {
+        self.set_filter(keys, false)
+    }
+
+    // members of the first set that are in every other set, or in none of them
+    fn set_filter(
+        &self,
+        keys: &[String],
+        in_others: bool,
+    ) -> Result<HashSet<String>, BackendError> {
+        let deadline = self.deadline();
+        let mut keys = keys.iter();
+        let mut result = match keys.next() {
+            Some(key) => self.set_or_empty(key, deadline)?,
+            None => return Ok(HashSet::new()),
+        };
+        for key in keys {
+            let other = self.set_or_empty(key, deadline)?;
+            result = within_deadline(result.into_iter(), deadline)
+                .filter(|member| match member {
+                    Ok(member) => other.contains(member) == in_others,
+                    Err(_) => true,
+                })
+                .collect::<Result<_, _>>()?;
+        }
+        Ok(result)
+    }
+
+    // returns how many subscribers the message was delivered to
+    pub fn publish(&self, channel: &str, message: RespFrame) -> usize {
+        let receivers = match self.channels.get(channel) {
+            Some(sender) => sender.send(message).unwrap_or(0),
+            None => return 0,
+        };
+        if receivers == 0 {
+            self.channels
+                .remove_if(channel, |_, sender| sender.receiver_count() == 0);
+        }
+        receivers
+    }
+
+    pub fn subscribe(&self, channel: &str) -> broadcast::Receiver<RespFrame> {
+        self.channels
+            .entry(channel.to_string())
+            .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+
+    pub fn config_get(&self, pattern: &str) -> Vec<(String, String)> {
+        let pattern = pattern.to_lowercase();
+        let mut params: Vec<_> = self
+            .runtime_config
+            .iter()
+            .filter(|v| glob_match(pattern.as_bytes(), v.key().as_bytes()))
+            .map(|v| (v.key().to_owned(), v.value().to_owned()))
+            .collect();
+        params.sort();
+        params
+    }
+
+    // only parameters that already exist can be changed, like redis
+    pub fn config_set(&self, param: &str, value: &str) -> Result<(), BackendError> {
+        let param = param.to_lowercase();
+        let Some(mut entry) = self.runtime_config.get_mut(&param) else {
+            return Err(BackendError::UnknownConfig(param));
+        };
+        if param == "notify-keyspace-events" {
+            let flags = NotifyFlags::parse(value)
+                .ok_or_else(|| BackendError::InvalidConfigValue(param.clone()))?;
+            self.notify_flags.store(flags.bits(), Ordering::Relaxed);
+        }
+        *entry = value.to_string();
+        Ok(())
     }
 }
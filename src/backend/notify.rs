@@ -0,0 +1,129 @@
+// what happened to a key, each is published under the event name redis uses for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyEvent {
+    Set,
+    Append,
+    Del,
+    Expired,
+    HSet,
+    HDel,
+    HIncrBy,
+    LPush,
+    RPush,
+    LPop,
+    RPop,
+    SAdd,
+    SRem,
+}
+
+impl KeyEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyEvent::Set => "set",
+            KeyEvent::Append => "append",
+            KeyEvent::Del => "del",
+            KeyEvent::Expired => "expired",
+            KeyEvent::HSet => "hset",
+            KeyEvent::HDel => "hdel",
+            KeyEvent::HIncrBy => "hincrby",
+            KeyEvent::LPush => "lpush",
+            KeyEvent::RPush => "rpush",
+            KeyEvent::LPop => "lpop",
+            KeyEvent::RPop => "rpop",
+            KeyEvent::SAdd => "sadd",
+            KeyEvent::SRem => "srem",
+        }
+    }
+
+    fn class(&self) -> u16 {
+        match self {
+            KeyEvent::Del => GENERIC,
+            KeyEvent::Set | KeyEvent::Append => STRING,
+            KeyEvent::Expired => EXPIRED,
+            KeyEvent::HSet | KeyEvent::HDel | KeyEvent::HIncrBy => HASH,
+            KeyEvent::LPush | KeyEvent::RPush | KeyEvent::LPop | KeyEvent::RPop => LIST,
+            KeyEvent::SAdd | KeyEvent::SRem => SET,
+        }
+    }
+}
+
+const KEYSPACE: u16 = 1 << 0;
+const KEYEVENT: u16 = 1 << 1;
+const GENERIC: u16 = 1 << 2;
+const STRING: u16 = 1 << 3;
+const LIST: u16 = 1 << 4;
+const SET: u16 = 1 << 5;
+const HASH: u16 = 1 << 6;
+const EXPIRED: u16 = 1 << 7;
+const ALL: u16 = GENERIC | STRING | LIST | SET | HASH | EXPIRED;
+
+// the notify-keyspace-events setting, with redis' flags: K and E pick the channels
+// events go to, g $ l s h x pick the events and A stands for all of those
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NotifyFlags(u16);
+
+impl NotifyFlags {
+    // None if flags has a character redis wouldn't accept either
+    pub fn parse(flags: &str) -> Option<Self> {
+        let mut bits = 0;
+        for flag in flags.chars() {
+            bits |= match flag {
+                'K' => KEYSPACE,
+                'E' => KEYEVENT,
+                'g' => GENERIC,
+                '$' => STRING,
+                'l' => LIST,
+                's' => SET,
+                'h' => HASH,
+                'x' => EXPIRED,
+                'A' => ALL,
+                // sorted sets, streams, evictions and the like never happen here
+                'z' | 't' | 'e' | 'm' | 'n' | 'd' => 0,
+                _ => return None,
+            };
+        }
+        Some(Self(bits))
+    }
+
+    pub fn from_bits(bits: u16) -> Self {
+        Self(bits)
+    }
+
+    pub fn bits(&self) -> u16 {
+        self.0
+    }
+
+    pub fn wants(&self, event: KeyEvent) -> bool {
+        self.0 & event.class() != 0
+    }
+
+    pub fn keyspace(&self) -> bool {
+        self.0 & KEYSPACE != 0
+    }
+
+    pub fn keyevent(&self) -> bool {
+        self.0 & KEYEVENT != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyEvent, NotifyFlags};
+
+    #[test]
+    fn test_notify_flags_parse() {
+        assert_eq!(NotifyFlags::parse(""), Some(NotifyFlags::default()));
+        assert!(NotifyFlags::parse("Kq").is_none());
+        assert_eq!(NotifyFlags::parse("z"), Some(NotifyFlags::default()));
+
+        let flags = NotifyFlags::parse("Ex").unwrap();
+        assert!(flags.keyevent() && !flags.keyspace());
+        assert!(flags.wants(KeyEvent::Expired));
+        assert!(!flags.wants(KeyEvent::Set));
+
+        let flags = NotifyFlags::parse("KA").unwrap();
+        assert!(flags.keyspace() && !flags.keyevent());
+        assert!(flags.wants(KeyEvent::SAdd) && flags.wants(KeyEvent::Del));
+        assert_eq!(NotifyFlags::from_bits(flags.bits()), flags);
+    }
+}
@@ -0,0 +1,252 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use tokio::{
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc::{self, error::TrySendError},
+        Notify,
+    },
+    task::JoinHandle,
+};
+
+use crate::{RespArray, RespAttributed, RespBulkString, RespFrame, RespMap, RespPush};
+
+pub const DEFAULT_PROTOCOL: u8 = 2;
+
+// frames waiting to be written to a client outside of command replies. a client that lets
+// this many pile up is disconnected, like redis does past its pubsub output buffer limit
+pub const PUSH_QUEUE_CAPACITY: usize = 1024;
+
+// per-connection state, commands reach it through `Backend::session`
+#[derive(Debug)]
+pub struct Session {
+    id: u64,
+    // shared with the forwarding tasks, which pick the push layout per message
+    protocol: Arc<AtomicU8>,
+    db: AtomicUsize,
+    authenticated: AtomicBool,
+    // frames sent to the client outside of a command reply, like pub/sub messages
+    pushes: mpsc::Sender<RespFrame>,
+    pushes_rx: Mutex<Option<mpsc::Receiver<RespFrame>>>,
+    // notified once a push didn't fit into the queue
+    overflow: Arc<Notify>,
+    // channel -> task forwarding its messages into `pushes`
+    subscriptions: Mutex<HashMap<String, JoinHandle<()>>>,
+    // server wide count of sessions with at least one subscription, reported by INFO
+    pubsub_clients: Arc<AtomicUsize>,
+}
+
+impl Session {
+    pub fn new(id: u64, pubsub_clients: Arc<AtomicUsize>) -> Self {
+        let (pushes, pushes_rx) = mpsc::channel(PUSH_QUEUE_CAPACITY);
+        Self {
+            id,
+            protocol: Arc::new(AtomicU8::new(DEFAULT_PROTOCOL)),
+            db: AtomicUsize::new(0),
+            authenticated: AtomicBool::new(false),
+            pushes,
+            pushes_rx: Mutex::new(Some(pushes_rx)),
+            overflow: Arc::new(Notify::new()),
+            subscriptions: Mutex::new(HashMap::new()),
+            pubsub_clients,
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn protocol(&self) -> u8 {
+        self.protocol.load(Ordering::Relaxed)
+    }
+
+    pub fn set_protocol(&self, protocol: u8) {
+        self.protocol.store(protocol, Ordering::Relaxed);
+    }
+
+    pub fn db(&self) -> usize {
+        self.db.load(Ordering::Relaxed)
+    }
+
+    pub fn select(&self, db: usize) {
+        self.db.store(db, Ordering::Relaxed);
+    }
+
+    pub fn is_authenticated(&self) -> bool {
+        self.authenticated.load(Ordering::Relaxed)
+    }
+
+    pub fn set_authenticated(&self, authenticated: bool) {
+        self.authenticated.store(authenticated, Ordering::Relaxed);
+    }
+
+    // the connection handler takes the receiving end once and writes everything to the client
+    pub fn take_pushes(&self) -> Option<mpsc::Receiver<RespFrame>> {
+        self.pushes_rx.lock().unwrap().take()
+    }
+
+    pub fn push(&self, frame: RespFrame) {
+        send_push(&self.pushes, &self.overflow, frame);
+    }
+
+    // resolves once the push queue has overflowed, the connection is dropped then
+    pub async fn overflowed(&self) {
+        self.overflow.notified().await
+    }
+
+    pub fn is_subscribed(&self, channel: &str) -> bool {
+        self.subscriptions.lock().unwrap().contains_key(channel)
+    }
+
+    pub fn subscription_count(&self) -> usize {
+        self.subscriptions.lock().unwrap().len()
+    }
+
+    // stops forwarding channel, false if this connection wasn't subscribed to it
+    pub fn unsubscribe(&self, channel: &str) -> bool {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        match subscriptions.remove(channel) {
+            Some(handle) => {
+                handle.abort();
+                if subscriptions.is_empty() {
+                    self.pubsub_clients.fetch_sub(1, Ordering::Relaxed);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn subscribed_channels(&self) -> Vec<String> {
+        self.subscriptions.lock().unwrap().keys().cloned().collect()
+    }
+
+    // out-of-band frames are RESP3 pushes, RESP2 connections get a plain array
+    pub fn push_frame(&self, items: Vec<RespFrame>) -> RespFrame {
+        push_frame(self.protocol(), items)
+    }
+
+    // attributes are RESP3 only, RESP2 connections get the bare frame
+    pub fn attributed(&self, attrs: RespMap, frame: RespFrame) -> RespFrame {
+        if self.protocol() >= 3 {
+            RespAttributed::new(attrs, frame).into()
+        } else {
+            frame
+        }
+    }
+
+    // forwards every message published to channel to this connection
+    pub fn forward(&self, channel: String, mut rx: broadcast::Receiver<RespFrame>) {
+        let pushes = self.pushes.clone();
+        let overflow = self.overflow.clone();
+        let protocol = self.protocol.clone();
+        let name = channel.clone();
+        let handle = tokio::spawn(async move {
+            loop {
+                let message = match rx.recv().await {
+                    Ok(message) => message,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return,
+                };
+                let frame = push_frame(
+                    protocol.load(Ordering::Relaxed),
+                    vec![
+                        RespBulkString::new("message").into(),
+                        RespBulkString::from(name.clone()).into(),
+                        message,
+                    ],
+                );
+                if !send_push(&pushes, &overflow, frame) {
+                    return;
+                }
+            }
+        });
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        if subscriptions.is_empty() {
+            self.pubsub_clients.fetch_add(1, Ordering::Relaxed);
+        }
+        if let Some(old) = subscriptions.insert(channel, handle) {
+            old.abort();
+        }
+    }
+}
+
+// false once the connection is gone or has fallen too far behind
+fn send_push(pushes: &mpsc::Sender<RespFrame>, overflow: &Notify, frame: RespFrame) -> bool {
+    match pushes.try_send(frame) {
+        Ok(()) => true,
+        Err(TrySendError::Full(_)) => {
+            overflow.notify_one();
+            false
+        }
+        Err(TrySendError::Closed(_)) => false,
+    }
+}
+
+fn push_frame(protocol: u8, items: Vec<RespFrame>) -> RespFrame {
+    if protocol >= 3 {
+        RespPush::new(items).into()
+    } else {
+        RespArray::new(items).into()
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let subscriptions = self.subscriptions.get_mut().unwrap();
+        for handle in subscriptions.values() {
+            handle.abort();
+        }
+        if !subscriptions.is_empty() {
+            self.pubsub_clients.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{RespAttributed, RespBulkString, RespInteger, RespMap, RespSimpleString};
+
+    use super::{Session, PUSH_QUEUE_CAPACITY};
+
+    #[test]
+    fn test_attributed_depends_on_protocol() {
+        let mut attrs = RespMap::new();
+        attrs.insert(RespSimpleString::new("ttl"), RespInteger::new(3600).into());
+        let session = Session::new(1, Default::default());
+
+        let frame = session.attributed(attrs.clone(), RespInteger::new(42).into());
+        assert_eq!(frame, RespInteger::new(42).into());
+
+        session.set_protocol(3);
+        let frame = session.attributed(attrs.clone(), RespInteger::new(42).into());
+        assert_eq!(
+            frame,
+            RespAttributed::new(attrs, RespInteger::new(42)).into()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_push_queue_overflow_is_reported() {
+        let session = Session::new(1, Default::default());
+        let _pushes = session.take_pushes().unwrap();
+        for _ in 0..PUSH_QUEUE_CAPACITY {
+            session.push(RespBulkString::new("message").into());
+        }
+        let overflowed = tokio::time::timeout(Duration::from_millis(50), session.overflowed());
+        assert!(overflowed.await.is_err());
+
+        // the queue stays bounded, the push that doesn't fit is what gets reported
+        session.push(RespBulkString::new("message").into());
+        let overflowed = tokio::time::timeout(Duration::from_secs(1), session.overflowed());
+        assert!(overflowed.await.is_ok());
+    }
+}
@@ -0,0 +1,299 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fs,
+    path::Path,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use bytes::BytesMut;
+
+use crate::{
+    RespArray, RespBulkString, RespDecode, RespEncode, RespFrame, RespInteger, RespSimpleString,
+};
+
+use super::{value::detach, Backend, Value};
+
+// the dump is plain RESP, one array per record so a frame held by a key is stored as is:
+// - string: [<db>, <key>, "string", <value>]
+// - hash: [<db>, <key>, "hash", <field>, <value>], one record per field so a large hash
+//   never hits the decoder's aggregate limit
+// - list: [<db>, <key>, "list", <value>], one record per element from head to tail
+// - set: [<db>, <key>, "set", <member>], one record per member
+// - expiration: [<db>, <key>, "expireat", <unix time in ms>], after the key's value
+impl Backend {
+    // writes every database to `path`, replacing it only once the whole dump is written
+    pub fn save_to(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let mut buf = BytesMut::new();
+        for (index, db) in self.dbs.iter().enumerate() {
+            for entry in db.data.iter() {
+                let key = entry.key();
+                let expires_at = db.expires.get(key).map(|at| *at);
+                if db.is_expired(key) {
+                    continue;
+                }
+                match entry.value() {
+                    Value::String(value) => {
+                        record(index, key, "string", vec![value.clone()]).encode_to(&mut buf)?;
+                    }
+                    Value::Hash(hash) => {
+                        for (field, value) in hash {
+                            let field = RespBulkString::from(field.clone()).into();
+                            record(index, key, "hash", vec![field, value.clone()])
+                                .encode_to(&mut buf)?;
+                        }
+                    }
+                    Value::List(list) => {
+                        for value in list {
+                            record(index, key, "list", vec![value.clone()]).encode_to(&mut buf)?;
+                        }
+                    }
+                    Value::Set(set) => {
+                        for member in set {
+                            let member = RespBulkString::from(member.clone()).into();
+                            record(index, key, "set", vec![member]).encode_to(&mut buf)?;
+                        }
+                    }
+                }
+                if let Some(at) = expires_at {
+                    let at = unix_millis(at);
+                    record(index, key, "expireat", vec![RespInteger::new(at).into()])
+                        .encode_to(&mut buf)?;
+                }
+            }
+        }
+
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, &buf)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    // adds the keys dumped to `path` to this backend, returns how many records were read
+    pub fn load_from(&self, path: impl AsRef<Path>) -> Result<usize> {
+        let mut buf = BytesMut::from(&fs::read(path)?[..]);
+        let mut records = 0;
+        while !buf.is_empty() {
+            let frame = RespFrame::decode(&mut buf)?;
+            self.load_record(frame)
+                .map_err(|e| anyhow!("record {}: {}", records, e))?;
+            records += 1;
+        }
+        Ok(records)
+    }
+
+    fn load_record(&self, frame: RespFrame) -> Result<()> {
+        let RespFrame::Array(RespArray(items)) = frame else {
+            return Err(anyhow!("not an array"));
+        };
+        let mut items = items.into_iter();
+        let (Some(RespFrame::Integer(index)), Some(key), Some(RespFrame::SimpleString(kind))) =
+            (items.next(), items.next(), items.next())
+        else {
+            return Err(anyhow!("malformed header"));
+        };
+        let db = usize::try_from(*index)
+            .ok()
+            .and_then(|index| self.dbs.get(index))
+            .ok_or_else(|| anyhow!("no database {}", *index))?;
+        let key = string(key)?;
+
+        match (kind.as_str(), items.next(), items.next(), items.next()) {
+            ("string", Some(value), None, None) => {
+                db.data.insert(key, Value::String(detach(value)));
+            }
+            ("hash", Some(field), Some(value), None) => {
+                let field = string(field)?;
+                let mut entry = db
+                    .data
+                    .entry(key)
+                    .or_insert_with(|| Value::Hash(HashMap::new()));
+                match entry.value_mut() {
+                    Value::Hash(hash) => {
+                        hash.insert(field, detach(value));
+                    }
+                    _ => return Err(anyhow!("hash field for a key of another type")),
+                }
+            }
+            ("list", Some(value), None, None) => {
+                let mut entry = db
+                    .data
+                    .entry(key)
+                    .or_insert_with(|| Value::List(VecDeque::new()));
+                match entry.value_mut() {
+                    Value::List(list) => list.push_back(detach(value)),
+                    _ => return Err(anyhow!("list element for a key of another type")),
+                }
+            }
+            ("set", Some(member), None, None) => {
+                let member = string(member)?;
+                let mut entry = db
+                    .data
+                    .entry(key)
+                    .or_insert_with(|| Value::Set(HashSet::new()));
+                match entry.value_mut() {
+                    Value::Set(set) => {
+                        set.insert(member);
+                    }
+                    _ => return Err(anyhow!("set member for a key of another type")),
+                }
+            }
+            ("expireat", Some(RespFrame::Integer(at)), None, None) => {
+                // keys that expired while the server was down are dropped right away
+                match instant_from_unix_millis(*at) {
+                    Some(at) => {
+                        db.expires.insert(key, at);
+                    }
+                    None => {
+                        db.data.remove(&key);
+                    }
+                }
+            }
+            (kind, ..) => return Err(anyhow!("unexpected {:?} record", kind)),
+        }
+        Ok(())
+    }
+}
+
+fn record(db: usize, key: &str, kind: &str, value: Vec<RespFrame>) -> RespArray {
+    let mut items = vec![
+        RespInteger::new(db as i64).into(),
+        RespBulkString::from(key.to_string()).into(),
+        RespSimpleString::new(kind).into(),
+    ];
+    items.extend(value);
+    RespArray::new(items)
+}
+
+// expirations are kept as instants, which mean nothing to another process
+fn unix_millis(at: Instant) -> i64 {
+    let now = SystemTime::now();
+    let at = now + at.saturating_duration_since(Instant::now());
+    at.duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis() as i64)
+}
+
+// None once the time has passed
+fn instant_from_unix_millis(millis: i64) -> Option<Instant> {
+    let at = UNIX_EPOCH + Duration::from_millis(u64::try_from(millis).ok()?);
+    let left = at.duration_since(SystemTime::now()).ok()?;
+    Instant::now().checked_add(left)
+}
+
+fn string(frame: RespFrame) -> Result<String> {
+    match frame {
+        RespFrame::BulkString(s) => Ok(String::from_utf8(s.to_vec())?),
+        frame => Err(anyhow!("expected a bulk string, got {:?}", frame)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf, time::Duration};
+
+    use anyhow::Result;
+
+    use crate::{backend::Backend, RespBulkString, RespInteger};
+
+    fn dump_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("simple-redis-{}-{}.rdb", std::process::id(), name))
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("hello", RespBulkString::new("world").into());
+        backend.set("counter", RespInteger::new(42).into());
+        backend.hset("map", "a", RespBulkString::new("1").into())?;
+        backend.hset("map", "b", RespBulkString::new("2").into())?;
+        backend.rpush(
+            "list",
+            vec![
+                RespBulkString::new("x").into(),
+                RespBulkString::new("y").into(),
+            ],
+        )?;
+        backend.sadd("set", vec!["m".to_string()])?;
+        let other = backend.new_session();
+        other.select(3);
+        other.set("hello", RespBulkString::new("db3").into());
+
+        let path = dump_path("round-trip");
+        backend.save_to(&path)?;
+
+        let loaded = Backend::new();
+        assert_eq!(loaded.load_from(&path)?, 8);
+        fs::remove_file(&path)?;
+
+        assert_eq!(
+            loaded.get("hello")?,
+            Some(RespBulkString::new("world").into())
+        );
+        assert_eq!(loaded.get("counter")?, Some(RespInteger::new(42).into()));
+        assert_eq!(
+            loaded.hget("map", "a")?,
+            Some(RespBulkString::new("1").into())
+        );
+        assert_eq!(
+            loaded.hget("map", "b")?,
+            Some(RespBulkString::new("2").into())
+        );
+        assert_eq!(
+            loaded.lrange("list", 0, -1)?,
+            vec![
+                RespBulkString::new("x").into(),
+                RespBulkString::new("y").into()
+            ]
+        );
+        assert!(loaded.sismember("set", "m")?);
+        assert!(loaded.select(3));
+        assert_eq!(
+            loaded.get("hello")?,
+            Some(RespBulkString::new("db3").into())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_and_load_expirations() -> Result<()> {
+        let backend = Backend::new();
+        let ttl = Duration::from_secs(100);
+        backend.set_ex("session", RespBulkString::new("token").into(), ttl)?;
+        backend.set_ex(
+            "gone",
+            RespBulkString::new("x").into(),
+            Duration::from_millis(1),
+        )?;
+        backend.set("forever", RespBulkString::new("value").into());
+        std::thread::sleep(Duration::from_millis(5));
+
+        let path = dump_path("expirations");
+        backend.save_to(&path)?;
+        let loaded = Backend::new();
+        // the expired key is not written at all
+        assert_eq!(loaded.load_from(&path)?, 3);
+        fs::remove_file(&path)?;
+
+        let left = loaded.ttl("session").expect("session should keep its ttl");
+        assert!(left <= ttl && left > ttl - Duration::from_secs(5));
+        assert_eq!(loaded.ttl("forever"), None);
+        assert_eq!(loaded.get("gone")?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_rejects_garbage() -> Result<()> {
+        let path = dump_path("garbage");
+        fs::write(&path, b"*1\r\n+oops\r\n")?;
+        let ret = Backend::new().load_from(&path);
+        fs::remove_file(&path)?;
+        assert!(ret.is_err());
+
+        let ret: Result<usize> = Backend::new().load_from(dump_path("missing"));
+        assert!(ret.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    mem::size_of,
+};
+
+use bytes::Bytes;
+
+use crate::{RespBulkString, RespFrame};
+
+// decoded bulk strings are slices of the connection's read buffer, stored as they are they
+// would keep that whole buffer alive for as long as the key exists. stored values get an
+// allocation of their own instead
+pub(crate) fn detach(frame: RespFrame) -> RespFrame {
+    match frame {
+        RespFrame::BulkString(s) => RespBulkString(Bytes::copy_from_slice(&s)).into(),
+        frame => frame,
+    }
+}
+
+// what a key holds, a key has exactly one type at a time
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(RespFrame),
+    Hash(HashMap<String, RespFrame>),
+    List(VecDeque<RespFrame>),
+    Set(HashSet<String>),
+}
+
+impl Value {
+    // rough number of bytes held by this value, see `RespFrame::memory_usage`
+    pub fn memory_usage(&self) -> usize {
+        match self {
+            Value::String(frame) => frame.memory_usage(),
+            Value::Hash(hash) => hash
+                .iter()
+                .map(|(field, value)| size_of::<String>() + field.len() + value.memory_usage())
+                .sum(),
+            Value::List(list) => list.iter().map(RespFrame::memory_usage).sum(),
+            Value::Set(set) => set
+                .iter()
+                .map(|member| size_of::<String>() + member.len())
+                .sum(),
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Hash(_) => "hash",
+            Value::List(_) => "list",
+            Value::Set(_) => "set",
+        }
+    }
+}
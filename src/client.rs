@@ -0,0 +1,124 @@
+use anyhow::{anyhow, Result};
+use futures::SinkExt;
+use tokio::net::{TcpStream, ToSocketAddrs};
+use tokio_stream::StreamExt;
+use tokio_util::codec::Framed;
+
+use crate::{network::RespFrameCodec, RespArray, RespFrame};
+
+// a minimal client speaking RESP over one connection, one request at a time
+#[derive(Debug)]
+pub struct Client {
+    framed: Framed<TcpStream, RespFrameCodec>,
+}
+
+impl Client {
+    pub async fn connect(addr: impl ToSocketAddrs) -> Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        Ok(Self {
+            framed: Framed::new(stream, RespFrameCodec::default()),
+        })
+    }
+
+    // sends a raw command and waits for its reply, error replies are returned as frames
+    pub async fn command(&mut self, command: RespArray) -> Result<RespFrame> {
+        self.framed.send(command.into()).await?;
+        match self.framed.next().await {
+            Some(frame) => frame,
+            None => Err(anyhow!("connection closed by server")),
+        }
+    }
+
+    pub async fn get(&mut self, key: &str) -> Result<RespFrame> {
+        self.command(RespArray::of(["get", key])).await
+    }
+
+    pub async fn set(&mut self, key: &str, value: &str) -> Result<RespFrame> {
+        self.command(RespArray::of(["set", key, value])).await
+    }
+
+    pub async fn hset(&mut self, key: &str, field: &str, value: &str) -> Result<RespFrame> {
+        self.command(RespArray::of(["hset", key, field, value]))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use tokio::net::TcpListener;
+
+    use crate::{
+        backend::Backend, network::stream_handler, RespArray, RespBulkString, RespFrame,
+        RespInteger, RespNull, RespSimpleString,
+    };
+
+    use super::Client;
+
+    #[tokio::test]
+    async fn test_client_talks_to_server() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            let backend = Backend::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(stream_handler(stream, backend.clone()));
+            }
+        });
+
+        let mut client = Client::connect(addr).await?;
+        assert_eq!(client.get("key").await?, RespNull.into());
+        let ok: RespFrame = RespSimpleString::new("OK").into();
+        assert_eq!(client.set("key", "value").await?, ok);
+        assert_eq!(
+            client.get("key").await?,
+            RespBulkString::new("value").into()
+        );
+
+        assert_eq!(client.hset("map", "field", "1").await?, ok);
+        let ret = client
+            .command(RespArray::of(["hget", "map", "field"]))
+            .await?;
+        assert_eq!(ret, RespBulkString::new("1").into());
+
+        // a second connection sees the same keyspace
+        let mut other = Client::connect(addr).await?;
+        let ret = other.command(RespArray::of(["hlen", "map"])).await?;
+        assert_eq!(ret, RespInteger::new(1).into());
+
+        let ret = other.command(RespArray::of(["foobar"])).await?;
+        assert!(matches!(ret, RespFrame::Error(_)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_malformed_command_gets_error_reply() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await?;
+            stream_handler(stream, Backend::new()).await
+        });
+
+        let mut client = Client::connect(addr).await?;
+        let ret = client.command(RespArray::of(["set", "key"])).await?;
+        assert!(matches!(ret, RespFrame::Error(_)));
+        let ret = client
+            .command(RespArray::of(["hset", "map", "field"]))
+            .await?;
+        assert!(matches!(ret, RespFrame::Error(_)));
+
+        // the connection is still usable afterwards
+        let ret = client.set("key", "value").await?;
+        assert_eq!(ret, RespSimpleString::new("OK").into());
+        assert_eq!(
+            client.get("key").await?,
+            RespBulkString::new("value").into()
+        );
+
+        drop(client);
+        server.await??;
+        Ok(())
+    }
+}
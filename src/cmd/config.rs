@@ -0,0 +1,160 @@
+use crate::{backend::Backend, RespArray, RespBulkString, RespFrame};
+
+use super::{
+    bulk_to_string, extract_args, validate_command, CommandError, CommandExecutor, RESP_OK,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum CommandConfig {
+    Get(String),
+    Set(String, String),
+}
+
+impl CommandExecutor for CommandConfig {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match self {
+            CommandConfig::Get(pattern) => {
+                let params = backend.config_get(&pattern);
+                let mut reply = Vec::with_capacity(params.len() * 2);
+                for (param, value) in params {
+                    reply.push(RespBulkString::from(param).into());
+                    reply.push(RespBulkString::from(value).into());
+                }
+                RespArray::new(reply).into()
+            }
+            CommandConfig::Set(param, value) => match backend.config_set(&param, &value) {
+                Ok(()) => RESP_OK.clone(),
+                Err(e) => e.into(),
+            },
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandConfig {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let subcommand = match value.get(1) {
+            Some(RespFrame::BulkString(subcommand)) => subcommand.to_ascii_lowercase(),
+            _ => {
+                return Err(CommandError::InvalidCommandArguments(
+                    "CONFIG command requires a subcommand".to_string(),
+                ))
+            }
+        };
+
+        match subcommand.as_slice() {
+            b"get" => {
+                validate_command(&value, &["config", "get"], 1)?;
+                let mut args = extract_args(value, 2)?.into_iter();
+                match args.next() {
+                    Some(pattern) => Ok(CommandConfig::Get(bulk_to_string(pattern)?)),
+                    _ => Err(CommandError::InvalidCommandArguments(
+                        "Invalid parameter".to_string(),
+                    )),
+                }
+            }
+            b"set" => {
+                validate_command(&value, &["config", "set"], 2)?;
+                let mut args = extract_args(value, 2)?.into_iter();
+                match (args.next(), args.next()) {
+                    (Some(param), Some(value)) => Ok(CommandConfig::Set(
+                        bulk_to_string(param)?,
+                        bulk_to_string(value)?,
+                    )),
+                    _ => Err(CommandError::InvalidCommandArguments(
+                        "Invalid parameter or value".to_string(),
+                    )),
+                }
+            }
+            _ => Err(CommandError::InvalidCommandArguments(format!(
+                "Unknown CONFIG subcommand: {}",
+                String::from_utf8_lossy(&subcommand)
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    use crate::{
+        backend::Backend,
+        cmd::{config::CommandConfig, CommandExecutor, RESP_OK},
+        RespArray, RespBulkString, RespDecode, RespFrame,
+    };
+
+    fn pairs(pairs: &[(&str, &str)]) -> RespFrame {
+        let mut frames = Vec::new();
+        for (param, value) in pairs {
+            frames.push(RespBulkString::new(param.as_bytes().to_vec()).into());
+            frames.push(RespBulkString::new(value.as_bytes().to_vec()).into());
+        }
+        RespArray::new(frames).into()
+    }
+
+    #[test]
+    fn test_config_get_command() -> Result<()> {
+        let backend = Backend::new();
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$6\r\nCONFIG\r\n$3\r\nGET\r\n$9\r\nmaxmemory\r\n");
+        let command: CommandConfig = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command, CommandConfig::Get("maxmemory".to_string()));
+        assert_eq!(command.execute(&backend), pairs(&[("maxmemory", "0")]));
+
+        let command = CommandConfig::Get("missing".to_string());
+        assert_eq!(command.execute(&backend), pairs(&[]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_get_pattern() {
+        let backend = Backend::new();
+
+        let command = CommandConfig::Get("maxmemory*".to_string());
+        assert_eq!(
+            command.execute(&backend),
+            pairs(&[("maxmemory", "0"), ("maxmemory-policy", "noeviction")])
+        );
+
+        let command = CommandConfig::Get("*".to_string());
+        let RespFrame::Array(ret) = command.execute(&backend) else {
+            panic!("CONFIG GET should reply with an array");
+        };
+        assert_eq!(ret.len(), backend.runtime_config.len() * 2);
+    }
+
+    #[test]
+    fn test_config_set_command() -> Result<()> {
+        let backend = Backend::new();
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*4\r\n$6\r\nconfig\r\n$3\r\nset\r\n$10\r\nappendonly\r\n$3\r\nyes\r\n",
+        );
+        let command: CommandConfig = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command.execute(&backend), RESP_OK.clone());
+
+        let command = CommandConfig::Get("appendonly".to_string());
+        assert_eq!(command.execute(&backend), pairs(&[("appendonly", "yes")]));
+
+        let command = CommandConfig::Set("missing".to_string(), "1".to_string());
+        assert!(matches!(command.execute(&backend), RespFrame::Error(_)));
+        assert!(backend.config_get("missing").is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_unknown_subcommand() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$6\r\nconfig\r\n$7\r\nrewrite\r\n");
+        let ret: Result<CommandConfig, _> = RespArray::decode(&mut buf)?.try_into();
+        assert!(ret.is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,375 @@
+use crate::{
+    backend::Backend, RespArray, RespBulkString, RespFrame, RespInteger, RespMap, RespSimpleError,
+    RespSimpleString,
+};
+
+use super::{
+    bulk_to_string, extract_args, validate_command, CommandError, CommandExecutor, RESP_OK,
+};
+
+#[derive(Debug, PartialEq)]
+pub struct CommandHello {
+    protocol: Option<i64>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandSelect {
+    index: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandAuth {
+    username: Option<String>,
+    password: String,
+}
+
+impl CommandExecutor for CommandHello {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let session = backend.session();
+        if let Some(protocol) = self.protocol {
+            match protocol {
+                2 if !backend.config.resp3_only => session.set_protocol(2),
+                3 => session.set_protocol(3),
+                _ => {
+                    return RespSimpleError::new("NOPROTO unsupported protocol version").into();
+                }
+            }
+        }
+
+        let fields: [(&str, RespFrame); 6] = [
+            ("server", RespBulkString::new("redis").into()),
+            (
+                "version",
+                RespBulkString::new(env!("CARGO_PKG_VERSION")).into(),
+            ),
+            ("proto", RespInteger::new(session.protocol() as i64).into()),
+            ("id", RespInteger::new(session.id() as i64).into()),
+            ("mode", RespBulkString::new("standalone").into()),
+            ("role", RespBulkString::new("master").into()),
+        ];
+
+        // RESP2 has no map type, so the fields are flattened into an array
+        if session.protocol() == 2 {
+            let mut reply = Vec::with_capacity(fields.len() * 2);
+            for (name, value) in fields {
+                reply.push(RespBulkString::new(name).into());
+                reply.push(value);
+            }
+            RespArray::new(reply).into()
+        } else {
+            let mut reply = RespMap::new();
+            for (name, value) in fields {
+                reply.insert(RespSimpleString::new(name), value);
+            }
+            reply.into()
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandHello {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // hello [protover]
+        let n_args = if value.len() == 2 { 1 } else { 0 };
+        validate_command(&value, &["hello"], n_args)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let protocol = match args.next() {
+            Some(protocol) => Some(bulk_to_string(protocol)?.parse().map_err(|_| {
+                CommandError::InvalidCommandArguments(
+                    "Protocol version is not an integer or out of range".to_string(),
+                )
+            })?),
+            None => None,
+        };
+        Ok(CommandHello { protocol })
+    }
+}
+
+impl CommandExecutor for CommandSelect {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if backend.select(self.index) {
+            RESP_OK.clone()
+        } else {
+            RespSimpleError::new("ERR DB index is out of range").into()
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandSelect {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["select"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match args.next() {
+            Some(index) => Ok(CommandSelect {
+                index: bulk_to_string(index)?.parse().map_err(|_| {
+                    CommandError::InvalidCommandArguments(
+                        "value is not an integer or out of range".to_string(),
+                    )
+                })?,
+            }),
+            _ => Err(CommandError::InvalidCommandArguments(
+                "SELECT command argument must be a bulk string".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for CommandAuth {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let Some(requirepass) = &backend.config.requirepass else {
+            return RespSimpleError::new(
+                "ERR AUTH <password> called without any password configured for the default user",
+            )
+            .into();
+        };
+
+        // only the default user exists
+        let username_ok = self.username.as_deref().unwrap_or("default") == "default";
+        if username_ok && &self.password == requirepass {
+            backend.session().set_authenticated(true);
+            RESP_OK.clone()
+        } else {
+            RespSimpleError::new("WRONGPASS invalid username-password pair or user is disabled.")
+                .into()
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandAuth {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // auth [username] password
+        let n_args = if value.len() == 3 { 2 } else { 1 };
+        validate_command(&value, &["auth"], n_args)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match (args.next(), args.next()) {
+            (Some(password), None) => Ok(CommandAuth {
+                username: None,
+                password: bulk_to_string(password)?,
+            }),
+            (Some(username), Some(password)) => Ok(CommandAuth {
+                username: Some(bulk_to_string(username)?),
+                password: bulk_to_string(password)?,
+            }),
+            _ => Err(CommandError::InvalidCommandArguments(
+                "Invalid username or password".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    use crate::{
+        backend::Backend,
+        cmd::{
+            connection::{CommandAuth, CommandHello, CommandSelect},
+            CommandExecutor, RESP_OK,
+        },
+        RespArray, RespBulkString, RespDecode, RespFrame, RespInteger, RespSimpleError,
+        RespSimpleString,
+    };
+
+    #[test]
+    fn test_hello_command() -> Result<()> {
+        let backend = Backend::new().new_session();
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*1\r\n$5\r\nhello\r\n");
+        let command: CommandHello = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command, CommandHello { protocol: None });
+
+        let RespFrame::Array(reply) = command.execute(&backend) else {
+            panic!("HELLO on a RESP2 connection should reply with an array");
+        };
+        assert_eq!(reply.len(), 12);
+        assert_eq!(reply[0], RespBulkString::new("server").into());
+        assert_eq!(reply[4], RespBulkString::new("proto").into());
+        assert_eq!(reply[5], RespInteger::new(2).into());
+        assert_eq!(reply[7], RespInteger::new(1).into());
+        assert_eq!(backend.session().protocol(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hello_command_resp3() -> Result<()> {
+        let backend = Backend::new().new_session();
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$5\r\nhello\r\n$1\r\n3\r\n");
+        let command: CommandHello = RespArray::decode(&mut buf)?.try_into()?;
+
+        let RespFrame::Map(reply) = command.execute(&backend) else {
+            panic!("HELLO 3 should reply with a map");
+        };
+        assert_eq!(
+            reply.get(RespSimpleString::new("proto")),
+            Some(&RespInteger::new(3).into())
+        );
+        assert_eq!(
+            reply.get(RespSimpleString::new("role")),
+            Some(&RespBulkString::new("master").into())
+        );
+        assert_eq!(backend.session().protocol(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hello_command_unsupported_protocol() {
+        let backend = Backend::new().new_session();
+
+        let command = CommandHello { protocol: Some(4) };
+        assert_eq!(
+            command.execute(&backend),
+            RespSimpleError::new("NOPROTO unsupported protocol version").into()
+        );
+        assert_eq!(backend.session().protocol(), 2);
+    }
+
+    #[test]
+    fn test_sessions_are_per_connection() {
+        let backend = Backend::new();
+        let first = backend.new_session();
+        let second = backend.new_session();
+        assert_ne!(first.session().id(), second.session().id());
+
+        CommandHello { protocol: Some(3) }.execute(&first);
+        assert_eq!(first.session().protocol(), 3);
+        assert_eq!(second.session().protocol(), 2);
+    }
+
+    #[test]
+    fn test_hello_command_resp3_only() {
+        let backend = Backend::with_config(crate::config::ServerConfig {
+            resp3_only: true,
+            ..Default::default()
+        })
+        .new_session();
+
+        let command = CommandHello { protocol: Some(2) };
+        assert_eq!(
+            command.execute(&backend),
+            RespSimpleError::new("NOPROTO unsupported protocol version").into()
+        );
+
+        let command = CommandHello { protocol: Some(3) };
+        assert!(matches!(command.execute(&backend), RespFrame::Map(_)));
+        assert_eq!(backend.session().protocol(), 3);
+    }
+
+    #[test]
+    fn test_select_command() -> Result<()> {
+        let backend = Backend::new().new_session();
+        backend.set("key", RespBulkString::new("value").into());
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$6\r\nselect\r\n$1\r\n1\r\n");
+        let command: CommandSelect = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command.execute(&backend), RESP_OK.clone());
+        assert_eq!(backend.get("key")?, None);
+        assert_eq!(backend.dbsize(), 0);
+
+        let command = CommandSelect { index: 0 };
+        assert_eq!(command.execute(&backend), RESP_OK.clone());
+        assert_eq!(
+            backend.get("key")?,
+            Some(RespBulkString::new("value").into())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_command_out_of_range() -> Result<()> {
+        let backend = Backend::new().new_session();
+
+        let command = CommandSelect { index: 16 };
+        assert!(matches!(command.execute(&backend), RespFrame::Error(_)));
+        assert_eq!(backend.session().db(), 0);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$6\r\nselect\r\n$2\r\n-1\r\n");
+        let ret: Result<CommandSelect, _> = RespArray::decode(&mut buf)?.try_into();
+        assert!(ret.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_selected_db_is_per_connection() -> Result<()> {
+        let backend = Backend::new();
+        let first = backend.new_session();
+        let second = backend.new_session();
+
+        CommandSelect { index: 1 }.execute(&first);
+        first.set("key", RespBulkString::new("value").into());
+        assert_eq!(second.get("key")?, None);
+
+        CommandSelect { index: 1 }.execute(&second);
+        assert_eq!(
+            second.get("key")?,
+            Some(RespBulkString::new("value").into())
+        );
+
+        Ok(())
+    }
+
+    fn auth(username: Option<&str>, password: &str) -> CommandAuth {
+        CommandAuth {
+            username: username.map(|s| s.to_string()),
+            password: password.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_auth_command() -> Result<()> {
+        let backend = Backend::with_config(crate::config::ServerConfig {
+            requirepass: Some("secret".to_string()),
+            ..Default::default()
+        })
+        .new_session();
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$4\r\nauth\r\n$5\r\nwrong\r\n");
+        let command: CommandAuth = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command, auth(None, "wrong"));
+        assert_eq!(
+            command.execute(&backend),
+            RespSimpleError::new("WRONGPASS invalid username-password pair or user is disabled.")
+                .into()
+        );
+        assert!(!backend.session().is_authenticated());
+
+        let ret = auth(Some("admin"), "secret").execute(&backend);
+        assert!(matches!(ret, RespFrame::Error(_)));
+        assert!(!backend.session().is_authenticated());
+
+        assert_eq!(auth(None, "secret").execute(&backend), RESP_OK.clone());
+        assert!(backend.session().is_authenticated());
+
+        let other = backend.new_session();
+        assert!(!other.session().is_authenticated());
+        assert_eq!(
+            auth(Some("default"), "secret").execute(&other),
+            RESP_OK.clone()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_auth_command_without_requirepass() {
+        let backend = Backend::new().new_session();
+        let ret = auth(None, "secret").execute(&backend);
+        assert!(matches!(ret, RespFrame::Error(_)));
+    }
+}
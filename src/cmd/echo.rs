@@ -1,6 +1,6 @@
 use crate::{backend::Backend, RespArray, RespBulkString, RespFrame};
 
-use super::{extract_args, validate_command, CommandError, CommandExecutor};
+use super::{bulk_to_string, extract_args, validate_command, CommandError, CommandExecutor};
 
 #[derive(Debug, PartialEq)]
 pub struct CommandEcho {
@@ -26,9 +26,7 @@ impl TryFrom<RespArray> for CommandEcho {
         let mut args = extract_args(frame, 1)?.into_iter();
 
         match args.next() {
-            Some(RespFrame::BulkString(value)) => Ok(CommandEcho::new(
-                String::from_utf8_lossy(&value).to_string(),
-            )),
+            Some(value) => Ok(CommandEcho::new(bulk_to_string(value)?)),
             _ => Err(CommandError::InvalidCommandArguments(
                 "Echo command argument must be a bulk string".to_string(),
             )),
@@ -1,8 +1,9 @@
-use tracing::info;
+use crate::{backend::Backend, RespArray, RespBulkString, RespFrame, RespInteger, RespNull};
 
-use crate::{backend::Backend, RespArray, RespBulkString, RespFrame, RespNull};
-
-use super::{extract_args, validate_command, CommandError, CommandExecutor, RESP_OK};
+use super::{
+    args_as_strings, bulk_to_bytes, bulk_to_string, extract_args, validate_command, CommandError,
+    CommandExecutor, RESP_OK,
+};
 
 #[derive(Debug, PartialEq)]
 pub struct CommandHGet {
@@ -29,6 +30,41 @@ pub struct CommandHMGet {
     fields: Vec<String>,
 }
 
+#[derive(Debug, PartialEq)]
+pub struct CommandHDel {
+    key: String,
+    fields: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandHExists {
+    key: String,
+    field: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandHLen {
+    key: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandHIncrBy {
+    key: String,
+    field: String,
+    increment: i64,
+}
+
+// HKEYS and HVALS reply in the inner map's iteration order, which is unspecified
+#[derive(Debug, PartialEq)]
+pub struct CommandHKeys {
+    key: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandHVals {
+    key: String,
+}
+
 impl TryFrom<RespArray> for CommandHGet {
     type Error = CommandError;
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
@@ -36,12 +72,10 @@ impl TryFrom<RespArray> for CommandHGet {
         let mut args = extract_args(value, 1)?.into_iter();
 
         match (args.next(), args.next()) {
-            (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(field))) => {
-                Ok(CommandHGet {
-                    key: String::from_utf8(key.0)?,
-                    field: String::from_utf8(field.0)?,
-                })
-            }
+            (Some(key), Some(field)) => Ok(CommandHGet {
+                key: bulk_to_string(key)?,
+                field: bulk_to_string(field)?,
+            }),
             _ => Err(CommandError::InvalidCommandArguments(
                 "Invalid key or field".to_string(),
             )),
@@ -52,8 +86,9 @@ impl TryFrom<RespArray> for CommandHGet {
 impl CommandExecutor for CommandHGet {
     fn execute(self, backend: &crate::backend::Backend) -> RespFrame {
         match backend.hget(&self.key, &self.field) {
-            Some(value) => value,
-            None => RespFrame::Null(RespNull),
+            Ok(Some(value)) => value,
+            Ok(None) => RespFrame::Null(RespNull),
+            Err(e) => e.into(),
         }
     }
 }
@@ -65,14 +100,10 @@ impl TryFrom<RespArray> for CommandHSet {
         let mut args = extract_args(value, 1)?.into_iter();
 
         match (args.next(), args.next(), args.next()) {
-            (
-                Some(RespFrame::BulkString(key)),
-                Some(RespFrame::BulkString(field)),
-                Some(RespFrame::BulkString(value)),
-            ) => Ok(CommandHSet {
-                key: String::from_utf8(key.0)?,
-                field: String::from_utf8(field.0)?,
-                value: RespFrame::BulkString(value),
+            (Some(key), Some(field), Some(value)) => Ok(CommandHSet {
+                key: bulk_to_string(key)?,
+                field: bulk_to_string(field)?,
+                value: RespBulkString::new(bulk_to_bytes(value)?).into(),
             }),
             _ => Err(CommandError::InvalidCommandArguments(
                 "Invalid key or field".to_string(),
@@ -90,25 +121,17 @@ impl TryFrom<RespArray> for CommandHMGet {
         let mut args = extract_args(value, 1)?.into_iter();
 
         match args.next() {
-            Some(RespFrame::BulkString(key)) => {
-                let mut string_fields: Vec<String> = Vec::new();
-                args.for_each(|field| match field {
-                    RespFrame::BulkString(field) => {
-                        string_fields.push(String::from_utf8(field.0).unwrap())
-                    }
-                    _ => {
-                        info!("unexpected hmget all field: {:?}", field);
-                    }
-                });
-                if string_fields.len() != n_args - 1 {
+            Some(key) => {
+                let fields = args_as_strings(args)?;
+                if fields.is_empty() {
                     return Err(CommandError::InvalidCommandArguments(
                         "Invalid hmget field".to_string(),
                     ));
                 }
 
                 Ok(CommandHMGet {
-                    key: String::from_utf8(key.0)?,
-                    fields: string_fields,
+                    key: bulk_to_string(key)?,
+                    fields,
                 })
             }
             err => Err(CommandError::InvalidCommandArguments(format!(
@@ -121,22 +144,34 @@ impl TryFrom<RespArray> for CommandHMGet {
 
 impl CommandExecutor for CommandHSet {
     fn execute(self, backend: &crate::backend::Backend) -> RespFrame {
-        backend.hset(&self.key, &self.field, self.value);
-        RESP_OK.to_owned()
+        match backend.hset(&self.key, &self.field, self.value) {
+            Ok(()) => RESP_OK.to_owned(),
+            Err(e) => e.into(),
+        }
     }
 }
 
 impl TryFrom<RespArray> for CommandHGetAll {
     type Error = CommandError;
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        validate_command(&value, &["hgetall"], 1)?;
+        // hgetall key [SORT]
+        let n_args = if value.len() == 3 { 2 } else { 1 };
+        validate_command(&value, &["hgetall"], n_args)?;
         let mut args = extract_args(value, 1)?.into_iter();
 
-        match args.next() {
-            Some(RespFrame::BulkString(field)) => Ok(CommandHGetAll {
-                key: String::from_utf8(field.0)?,
+        match (args.next(), args.next()) {
+            (Some(key), None) => Ok(CommandHGetAll {
+                key: bulk_to_string(key)?,
                 sort: false,
             }),
+            (Some(key), Some(RespFrame::BulkString(option)))
+                if option.eq_ignore_ascii_case(b"sort") =>
+            {
+                Ok(CommandHGetAll {
+                    key: bulk_to_string(key)?,
+                    sort: true,
+                })
+            }
             _ => Err(CommandError::InvalidCommandArguments(
                 "Invalid key or field".to_string(),
             )),
@@ -146,47 +181,224 @@ impl TryFrom<RespArray> for CommandHGetAll {
 
 impl CommandExecutor for CommandHGetAll {
     fn execute(self, backend: &Backend) -> RespFrame {
-        let hmap = backend.hmap.get(&self.key);
-
-        match hmap {
-            Some(hmap) => {
-                let mut data = Vec::with_capacity(hmap.len());
-                for v in hmap.iter() {
-                    let key = v.key().to_owned();
-                    data.push((key, v.value().to_owned()));
-                }
-
+        match backend.hgetall(&self.key) {
+            Ok(Some(hmap)) => {
+                let mut data: Vec<_> = hmap.into_iter().collect();
                 if self.sort {
                     data.sort_by(|a, b| a.0.cmp(&b.0));
                 }
-                let ret = data
-                    .into_iter()
-                    .flat_map(|(k, v)| vec![RespBulkString::from(k).into(), v])
-                    .collect::<Vec<RespFrame>>();
+                let mut ret = Vec::with_capacity(data.len() * 2);
+                for (k, v) in data {
+                    ret.push(RespBulkString::from(k).into());
+                    ret.push(v);
+                }
                 RespArray::new(ret).into()
             }
-            None => RespFrame::Null(RespNull),
+            Ok(None) => RespFrame::Null(RespNull),
+            Err(e) => e.into(),
         }
     }
 }
 
 impl CommandExecutor for CommandHMGet {
     fn execute(self, backend: &Backend) -> RespFrame {
-        let hmap = backend.hmap.get(&self.key);
-
-        match hmap {
-            Some(hmap) => {
-                let mut data = Vec::with_capacity(self.fields.len());
-                for v in self.fields {
-                    if let Some(v) = hmap.get(&v) {
-                        data.push(v.value().to_owned());
-                    } else {
-                        data.push(RespFrame::Null(RespNull));
-                    }
+        match backend.hmget(&self.key, &self.fields) {
+            Ok(Some(values)) => {
+                let data = values
+                    .into_iter()
+                    .map(|v| v.unwrap_or(RespFrame::Null(RespNull)))
+                    .collect();
+                RespArray::new(data).into()
+            }
+            Ok(None) => RespFrame::Null(RespNull),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandHDel {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let n_args = value.len() - 1;
+        validate_command(&value, &["hdel"], n_args)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match args.next() {
+            Some(key) => {
+                let fields = args_as_strings(args)?;
+                if fields.is_empty() {
+                    return Err(CommandError::InvalidCommandArguments(
+                        "Invalid hdel field".to_string(),
+                    ));
                 }
+
+                Ok(CommandHDel {
+                    key: bulk_to_string(key)?,
+                    fields,
+                })
+            }
+            err => Err(CommandError::InvalidCommandArguments(format!(
+                "Invalid key or field: {:?}",
+                err
+            ))),
+        }
+    }
+}
+
+impl CommandExecutor for CommandHDel {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let mut removed = 0;
+        for field in &self.fields {
+            match backend.hdel(&self.key, field) {
+                Ok(true) => removed += 1,
+                Ok(false) => {}
+                Err(e) => return e.into(),
+            }
+        }
+        RespInteger::new(removed).into()
+    }
+}
+
+impl TryFrom<RespArray> for CommandHExists {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["hexists"], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match (args.next(), args.next()) {
+            (Some(key), Some(field)) => Ok(CommandHExists {
+                key: bulk_to_string(key)?,
+                field: bulk_to_string(field)?,
+            }),
+            _ => Err(CommandError::InvalidCommandArguments(
+                "Invalid key or field".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for CommandHExists {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.hexists(&self.key, &self.field) {
+            Ok(exists) => RespInteger::new(exists as i64).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandHKeys {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["hkeys"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match args.next() {
+            Some(key) => Ok(CommandHKeys {
+                key: bulk_to_string(key)?,
+            }),
+            _ => Err(CommandError::InvalidCommandArguments(
+                "Invalid key".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for CommandHKeys {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.hkeys(&self.key) {
+            Ok(keys) => {
+                let data = keys
+                    .into_iter()
+                    .map(|key| RespBulkString::from(key).into())
+                    .collect();
                 RespArray::new(data).into()
             }
-            None => RespFrame::Null(RespNull),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandHVals {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["hvals"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match args.next() {
+            Some(key) => Ok(CommandHVals {
+                key: bulk_to_string(key)?,
+            }),
+            _ => Err(CommandError::InvalidCommandArguments(
+                "Invalid key".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for CommandHVals {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.hvals(&self.key) {
+            Ok(values) => RespArray::new(values).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandHLen {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["hlen"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match args.next() {
+            Some(key) => Ok(CommandHLen {
+                key: bulk_to_string(key)?,
+            }),
+            _ => Err(CommandError::InvalidCommandArguments(
+                "Invalid key".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for CommandHLen {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.hlen(&self.key) {
+            Ok(len) => RespInteger::new(len as i64).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandHIncrBy {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["hincrby"], 3)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match (args.next(), args.next(), args.next()) {
+            (Some(key), Some(field), Some(increment)) => Ok(CommandHIncrBy {
+                key: bulk_to_string(key)?,
+                field: bulk_to_string(field)?,
+                increment: bulk_to_string(increment)?.parse().map_err(|_| {
+                    CommandError::InvalidCommandArguments(
+                        "value is not an integer or out of range".to_string(),
+                    )
+                })?,
+            }),
+            _ => Err(CommandError::InvalidCommandArguments(
+                "Invalid key or field".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for CommandHIncrBy {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.hincrby(&self.key, &self.field, self.increment) {
+            Ok(value) => RespInteger::new(value).into(),
+            Err(e) => e.into(),
         }
     }
 }
@@ -195,10 +407,13 @@ impl CommandExecutor for CommandHMGet {
 mod tests {
     use crate::{
         cmd::{
-            hmap::{CommandHGet, CommandHGetAll, CommandHMGet, CommandHSet},
+            hmap::{
+                CommandHDel, CommandHExists, CommandHGet, CommandHGetAll, CommandHIncrBy,
+                CommandHKeys, CommandHLen, CommandHMGet, CommandHSet, CommandHVals,
+            },
             CommandExecutor,
         },
-        RespArray, RespBulkString, RespFrame, RespNull,
+        RespArray, RespBulkString, RespFrame, RespInteger, RespNull, RespSimpleError,
     };
     use anyhow::{Ok, Result};
 
@@ -249,8 +464,8 @@ mod tests {
     #[test]
     fn test_hmget_command_from_resp_array() -> Result<()> {
         let backend = crate::backend::Backend::new();
-        backend.hset("map", "hello", RespBulkString::new("world").into());
-        backend.hset("map", "hello2", RespBulkString::new("world2").into());
+        backend.hset("map", "hello", RespBulkString::new("world").into())?;
+        backend.hset("map", "hello2", RespBulkString::new("world2").into())?;
 
         let resp_array = RespArray::new(vec![
             RespFrame::BulkString(RespBulkString::new(b"hmget".to_vec())),
@@ -270,7 +485,7 @@ mod tests {
     #[test]
     fn test_hgetall_execute() -> Result<()> {
         let backend = crate::backend::Backend::new();
-        backend.hset("map", "hello", RespBulkString::new("world").into());
+        backend.hset("map", "hello", RespBulkString::new("world").into())?;
 
         let resp_array = RespArray::new(vec![
             RespFrame::BulkString(RespBulkString::new(b"hgetall".to_vec())),
@@ -293,8 +508,8 @@ mod tests {
     #[test]
     fn test_hmget_execute() -> Result<()> {
         let backend = crate::backend::Backend::new();
-        backend.hset("map", "hello", RespBulkString::new("world").into());
-        backend.hset("map", "hello2", RespBulkString::new("world2").into());
+        backend.hset("map", "hello", RespBulkString::new("world").into())?;
+        backend.hset("map", "hello2", RespBulkString::new("world2").into())?;
 
         let resp_array = RespArray::new(vec![
             RespFrame::BulkString(RespBulkString::new(b"hmget".to_vec())),
@@ -318,4 +533,305 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_hgetall_reply_is_presized() -> Result<()> {
+        let backend = crate::backend::Backend::new();
+        for i in 0..1000 {
+            backend.hset(
+                "map",
+                &format!("field{}", i),
+                RespBulkString::new("value").into(),
+            )?;
+        }
+
+        let hgetall_command = CommandHGetAll {
+            key: "map".to_string(),
+            sort: false,
+        };
+        match hgetall_command.execute(&backend) {
+            RespFrame::Array(array) => {
+                assert_eq!(array.len(), 2000);
+                assert_eq!(array.0.capacity(), array.len());
+            }
+            frame => panic!("unexpected hgetall reply: {:?}", frame),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hdel_execute() -> Result<()> {
+        let backend = crate::backend::Backend::new();
+        backend.hset("map", "hello", RespBulkString::new("world").into())?;
+        backend.hset("map", "hello2", RespBulkString::new("world2").into())?;
+        backend.hset("map", "hello3", RespBulkString::new("world3").into())?;
+
+        let resp_array = RespArray::new(vec![
+            RespFrame::BulkString(RespBulkString::new(b"hdel".to_vec())),
+            RespFrame::BulkString(RespBulkString::new(b"map".to_vec())),
+            RespFrame::BulkString(RespBulkString::new(b"hello".to_vec())),
+            RespFrame::BulkString(RespBulkString::new(b"missing".to_vec())),
+        ]);
+        let hdel_command: CommandHDel = resp_array.try_into()?;
+        assert_eq!(hdel_command.execute(&backend), RespInteger::new(1).into());
+        assert_eq!(backend.hget("map", "hello")?, None);
+
+        let hdel_command = CommandHDel {
+            key: "map".to_string(),
+            fields: vec!["missing".to_string()],
+        };
+        assert_eq!(hdel_command.execute(&backend), RespInteger::new(0).into());
+
+        let hdel_command = CommandHDel {
+            key: "map".to_string(),
+            fields: vec!["hello2".to_string(), "hello3".to_string()],
+        };
+        assert_eq!(hdel_command.execute(&backend), RespInteger::new(2).into());
+        assert!(!backend.db().data.contains_key("map"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hexists_execute() -> Result<()> {
+        let backend = crate::backend::Backend::new();
+        backend.hset("map", "hello", RespBulkString::new("world").into())?;
+
+        let resp_array = RespArray::new(vec![
+            RespFrame::BulkString(RespBulkString::new(b"hexists".to_vec())),
+            RespFrame::BulkString(RespBulkString::new(b"map".to_vec())),
+            RespFrame::BulkString(RespBulkString::new(b"hello".to_vec())),
+        ]);
+        let hexists_command: CommandHExists = resp_array.try_into()?;
+        assert_eq!(
+            hexists_command.execute(&backend),
+            RespInteger::new(1).into()
+        );
+
+        let hexists_command = CommandHExists {
+            key: "map".to_string(),
+            field: "missing".to_string(),
+        };
+        assert_eq!(
+            hexists_command.execute(&backend),
+            RespInteger::new(0).into()
+        );
+
+        let hexists_command = CommandHExists {
+            key: "missing".to_string(),
+            field: "hello".to_string(),
+        };
+        assert_eq!(
+            hexists_command.execute(&backend),
+            RespInteger::new(0).into()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hkeys_hvals_execute() -> Result<()> {
+        let backend = crate::backend::Backend::new();
+        backend.hset("map", "hello", RespBulkString::new("world").into())?;
+        backend.hset("map", "hello2", RespBulkString::new("world2").into())?;
+
+        let resp_array = RespArray::new(vec![
+            RespFrame::BulkString(RespBulkString::new(b"hkeys".to_vec())),
+            RespFrame::BulkString(RespBulkString::new(b"map".to_vec())),
+        ]);
+        let hkeys_command: CommandHKeys = resp_array.try_into()?;
+        let RespFrame::Array(mut keys) = hkeys_command.execute(&backend) else {
+            panic!("hkeys should reply with an array");
+        };
+        keys.0.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(
+            keys,
+            RespArray::new(vec![
+                RespBulkString::new("hello").into(),
+                RespBulkString::new("hello2").into(),
+            ])
+        );
+
+        let resp_array = RespArray::new(vec![
+            RespFrame::BulkString(RespBulkString::new(b"hvals".to_vec())),
+            RespFrame::BulkString(RespBulkString::new(b"map".to_vec())),
+        ]);
+        let hvals_command: CommandHVals = resp_array.try_into()?;
+        let RespFrame::Array(mut values) = hvals_command.execute(&backend) else {
+            panic!("hvals should reply with an array");
+        };
+        values.0.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(
+            values,
+            RespArray::new(vec![
+                RespBulkString::new("world").into(),
+                RespBulkString::new("world2").into(),
+            ])
+        );
+
+        let hkeys_command = CommandHKeys {
+            key: "missing".to_string(),
+        };
+        assert_eq!(
+            hkeys_command.execute(&backend),
+            RespArray::new(Vec::new()).into()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hlen_execute() -> Result<()> {
+        let backend = crate::backend::Backend::new();
+        backend.hset("map", "hello", RespBulkString::new("world").into())?;
+        backend.hset("map", "hello2", RespBulkString::new("world2").into())?;
+
+        let resp_array = RespArray::new(vec![
+            RespFrame::BulkString(RespBulkString::new(b"hlen".to_vec())),
+            RespFrame::BulkString(RespBulkString::new(b"map".to_vec())),
+        ]);
+        let hlen_command: CommandHLen = resp_array.try_into()?;
+        assert_eq!(hlen_command.execute(&backend), RespInteger::new(2).into());
+
+        let hlen_command = CommandHLen {
+            key: "missing".to_string(),
+        };
+        assert_eq!(hlen_command.execute(&backend), RespInteger::new(0).into());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hincrby_execute() -> Result<()> {
+        let backend = crate::backend::Backend::new();
+
+        let resp_array = RespArray::new(vec![
+            RespFrame::BulkString(RespBulkString::new(b"hincrby".to_vec())),
+            RespFrame::BulkString(RespBulkString::new(b"map".to_vec())),
+            RespFrame::BulkString(RespBulkString::new(b"counter".to_vec())),
+            RespFrame::BulkString(RespBulkString::new(b"5".to_vec())),
+        ]);
+        let hincrby_command: CommandHIncrBy = resp_array.try_into()?;
+        assert_eq!(
+            hincrby_command.execute(&backend),
+            RespInteger::new(5).into()
+        );
+
+        let hincrby_command = CommandHIncrBy {
+            key: "map".to_string(),
+            field: "counter".to_string(),
+            increment: -7,
+        };
+        assert_eq!(
+            hincrby_command.execute(&backend),
+            RespInteger::new(-2).into()
+        );
+        assert_eq!(
+            backend.hget("map", "counter")?,
+            Some(RespBulkString::new("-2").into())
+        );
+
+        backend.hset("map", "hello", RespBulkString::new("world").into())?;
+        let hincrby_command = CommandHIncrBy {
+            key: "map".to_string(),
+            field: "hello".to_string(),
+            increment: 1,
+        };
+        assert_eq!(
+            hincrby_command.execute(&backend),
+            RespSimpleError::new("ERR hash value is not an integer").into()
+        );
+        assert_eq!(
+            backend.hget("map", "hello")?,
+            Some(RespBulkString::new("world").into())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hgetall_sort_execute() -> Result<()> {
+        let backend = crate::backend::Backend::new();
+        for field in ["c", "a", "d", "b"] {
+            backend.hset(
+                "map",
+                field,
+                RespBulkString::new(field.to_uppercase()).into(),
+            )?;
+        }
+
+        let resp_array = RespArray::new(vec![
+            RespFrame::BulkString(RespBulkString::new(b"hgetall".to_vec())),
+            RespFrame::BulkString(RespBulkString::new(b"map".to_vec())),
+        ]);
+        let hgetall_command: CommandHGetAll = resp_array.try_into()?;
+        assert!(!hgetall_command.sort);
+
+        let resp_array = RespArray::new(vec![
+            RespFrame::BulkString(RespBulkString::new(b"hgetall".to_vec())),
+            RespFrame::BulkString(RespBulkString::new(b"map".to_vec())),
+            RespFrame::BulkString(RespBulkString::new(b"SORT".to_vec())),
+        ]);
+        let hgetall_command: CommandHGetAll = resp_array.try_into()?;
+        assert!(hgetall_command.sort);
+        assert_eq!(
+            hgetall_command.execute(&backend),
+            RespArray::new(vec![
+                RespBulkString::new("a").into(),
+                RespBulkString::new("A").into(),
+                RespBulkString::new("b").into(),
+                RespBulkString::new("B").into(),
+                RespBulkString::new("c").into(),
+                RespBulkString::new("C").into(),
+                RespBulkString::new("d").into(),
+                RespBulkString::new("D").into(),
+            ])
+            .into()
+        );
+
+        let resp_array = RespArray::new(vec![
+            RespFrame::BulkString(RespBulkString::new(b"hgetall".to_vec())),
+            RespFrame::BulkString(RespBulkString::new(b"map".to_vec())),
+            RespFrame::BulkString(RespBulkString::new(b"unknown".to_vec())),
+        ]);
+        let ret: Result<CommandHGetAll, _> = resp_array.try_into();
+        assert!(ret.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_commands_on_string_key() -> Result<()> {
+        let backend = crate::backend::Backend::new();
+        backend.set("key", RespBulkString::new("value").into());
+
+        let command = CommandHSet {
+            key: "key".to_string(),
+            field: "field".to_string(),
+            value: RespBulkString::new("value").into(),
+        };
+        assert_eq!(
+            command.execute(&backend),
+            crate::cmd::RESP_WRONGTYPE.clone()
+        );
+
+        let command = CommandHGet {
+            key: "key".to_string(),
+            field: "field".to_string(),
+        };
+        assert_eq!(
+            command.execute(&backend),
+            crate::cmd::RESP_WRONGTYPE.clone()
+        );
+
+        // the string is left untouched
+        assert_eq!(
+            backend.get("key")?,
+            Some(RespBulkString::new("value").into())
+        );
+        assert_eq!(backend.key_type("key"), "string");
+
+        Ok(())
+    }
 }
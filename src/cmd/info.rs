@@ -0,0 +1,197 @@
+use crate::{backend::Backend, RespArray, RespBulkString, RespFrame};
+
+use super::{bulk_to_string, extract_args, validate_command, CommandError, CommandExecutor};
+
+#[derive(Debug, PartialEq)]
+pub struct CommandInfo {
+    section: Option<String>,
+}
+
+impl CommandInfo {
+    fn wants(&self, section: &str) -> bool {
+        match self.section.as_deref() {
+            None | Some("all" | "default" | "everything") => true,
+            Some(name) => name == section,
+        }
+    }
+}
+
+// sections in the order redis prints them, each a list of "key:value" fields
+fn sections(backend: &Backend) -> Vec<(&'static str, Vec<(String, String)>)> {
+    let field = |key: &str, value: String| (key.to_string(), value);
+    // only databases holding keys are listed, avg_ttl isn't tracked
+    let keyspace = backend
+        .dbs
+        .iter()
+        .enumerate()
+        .filter(|(_, db)| !db.data.is_empty())
+        .map(|(index, db)| {
+            let stats = format!(
+                "keys={},expires={},avg_ttl=0",
+                db.data.len(),
+                db.expires.len()
+            );
+            (format!("db{}", index), stats)
+        })
+        .collect();
+
+    vec![
+        (
+            "Server",
+            vec![
+                field("redis_version", env!("CARGO_PKG_VERSION").to_string()),
+                field("redis_mode", "standalone".to_string()),
+                field("run_id", backend.run_id.clone()),
+            ],
+        ),
+        (
+            "Clients",
+            vec![
+                field("connected_clients", backend.connected_clients().to_string()),
+                field("pubsub_clients", backend.pubsub_clients().to_string()),
+            ],
+        ),
+        ("Keyspace", keyspace),
+    ]
+}
+
+impl CommandExecutor for CommandInfo {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let mut report = Vec::new();
+        for (name, fields) in sections(backend) {
+            if !self.wants(&name.to_ascii_lowercase()) {
+                continue;
+            }
+            let mut section = format!("# {}\r\n", name);
+            for (key, value) in fields {
+                section.push_str(&format!("{}:{}\r\n", key, value));
+            }
+            report.push(section);
+        }
+        RespBulkString::from(report.join("\r\n")).into()
+    }
+}
+
+impl TryFrom<RespArray> for CommandInfo {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // info [section]
+        let n_args = if value.len() == 2 { 1 } else { 0 };
+        validate_command(&value, &["info"], n_args)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let section = match args.next() {
+            Some(section) => Some(bulk_to_string(section)?.to_ascii_lowercase()),
+            None => None,
+        };
+        Ok(CommandInfo { section })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    use crate::{
+        backend::Backend,
+        cmd::{info::CommandInfo, CommandExecutor},
+        RespArray, RespBulkString, RespDecode, RespFrame,
+    };
+
+    fn info(backend: &Backend, section: Option<&str>) -> String {
+        let command = CommandInfo {
+            section: section.map(|s| s.to_string()),
+        };
+        match command.execute(backend) {
+            RespFrame::BulkString(report) => String::from_utf8_lossy(&report).into_owned(),
+            frame => panic!("INFO should reply with a bulk string, got {:?}", frame),
+        }
+    }
+
+    #[test]
+    fn test_info_reports_run_id() -> Result<()> {
+        let backend = Backend::new();
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$4\r\ninfo\r\n$6\r\nSERVER\r\n");
+        let command: CommandInfo = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(
+            command,
+            CommandInfo {
+                section: Some("server".to_string())
+            }
+        );
+
+        let report = info(&backend, None);
+        assert!(report.starts_with("# Server\r\n"));
+        let run_id = report
+            .lines()
+            .find_map(|line| line.strip_prefix("run_id:"))
+            .expect("INFO should report run_id");
+        assert_eq!(run_id.len(), 40);
+        assert!(run_id.chars().all(|c| c.is_ascii_hexdigit()));
+
+        // stable for the lifetime of the server, but not shared between servers
+        assert!(info(&backend, Some("server")).contains(run_id));
+        assert!(!info(&Backend::new(), None).contains(run_id));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_info_unknown_section() {
+        assert_eq!(info(&Backend::new(), Some("missing")), "");
+    }
+
+    #[test]
+    fn test_info_clients_and_keyspace() {
+        let backend = Backend::new();
+        backend.set("hello", RespBulkString::new("world").into());
+        backend.set("foo", RespBulkString::new("bar").into());
+        let other = backend.new_session();
+        other.select(3);
+        other.set("hello", RespBulkString::new("db3").into());
+        let _client = backend.client_connected();
+
+        let report = info(&backend, None);
+        assert!(report.contains("redis_version:"));
+        assert!(report.contains("# Clients\r\nconnected_clients:1\r\n"));
+        assert!(report.contains("\r\ndb0:keys=2,expires=0,avg_ttl=0\r\n"));
+        assert!(report.contains("\r\ndb3:keys=1,"));
+        assert!(!report.contains("db1:"));
+
+        let report = info(&backend, Some("keyspace"));
+        assert!(report.starts_with("# Keyspace\r\ndb0:keys=2"));
+        assert!(!report.contains("redis_version:"));
+    }
+
+    #[tokio::test]
+    async fn test_info_pubsub_clients() {
+        let backend = Backend::new();
+        assert!(info(&backend, Some("clients")).contains("pubsub_clients:0\r\n"));
+
+        let subscriber = backend.new_session();
+        subscriber
+            .session()
+            .forward("news".to_string(), backend.subscribe("news"));
+        subscriber
+            .session()
+            .forward("sports".to_string(), backend.subscribe("sports"));
+        assert!(info(&backend, Some("clients")).contains("pubsub_clients:1\r\n"));
+
+        subscriber.session().unsubscribe("news");
+        assert!(info(&backend, Some("clients")).contains("pubsub_clients:1\r\n"));
+        subscriber.session().unsubscribe("sports");
+        assert!(info(&backend, Some("clients")).contains("pubsub_clients:0\r\n"));
+
+        // a subscribed connection stops counting once it goes away
+        let other = backend.new_session();
+        other
+            .session()
+            .forward("news".to_string(), backend.subscribe("news"));
+        assert!(info(&backend, Some("clients")).contains("pubsub_clients:1\r\n"));
+        drop(other);
+        assert!(info(&backend, Some("clients")).contains("pubsub_clients:0\r\n"));
+    }
+}
@@ -0,0 +1,275 @@
+use crate::{
+    backend::Backend, RespArray, RespBulkString, RespFrame, RespInteger, RespSimpleString,
+};
+
+use super::{
+    bulk_to_string, extract_args, validate_command, CommandError, CommandExecutor, RESP_OK,
+};
+
+#[derive(Debug, PartialEq)]
+pub struct CommandType {
+    key: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandKeys {
+    pattern: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandDbSize;
+
+#[derive(Debug, PartialEq)]
+pub struct CommandFlushDb;
+
+impl CommandExecutor for CommandType {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespSimpleString::new(backend.key_type(&self.key)).into()
+    }
+}
+
+impl TryFrom<RespArray> for CommandType {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["type"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match args.next() {
+            Some(key) => Ok(CommandType {
+                key: bulk_to_string(key)?,
+            }),
+            _ => Err(CommandError::InvalidCommandArguments(
+                "TYPE command argument must be a bulk string".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for CommandKeys {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.keys(&self.pattern) {
+            Ok(keys) => RespArray::new(
+                keys.into_iter()
+                    .map(|key| RespBulkString::from(key).into())
+                    .collect(),
+            )
+            .into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandKeys {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["keys"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match args.next() {
+            Some(pattern) => Ok(CommandKeys {
+                pattern: bulk_to_string(pattern)?,
+            }),
+            _ => Err(CommandError::InvalidCommandArguments(
+                "KEYS command argument must be a bulk string".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for CommandDbSize {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespInteger::new(backend.dbsize() as i64).into()
+    }
+}
+
+impl TryFrom<RespArray> for CommandDbSize {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["dbsize"], 0)?;
+        Ok(CommandDbSize)
+    }
+}
+
+impl CommandExecutor for CommandFlushDb {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        backend.flushdb();
+        RESP_OK.clone()
+    }
+}
+
+impl TryFrom<RespArray> for CommandFlushDb {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["flushdb"], 0)?;
+        Ok(CommandFlushDb)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    use crate::{
+        backend::Backend,
+        cmd::{
+            keyspace::{CommandDbSize, CommandFlushDb, CommandKeys, CommandType},
+            CommandExecutor, RESP_OK,
+        },
+        RespArray, RespBulkString, RespDecode, RespFrame, RespInteger, RespSimpleError,
+        RespSimpleString,
+    };
+
+    #[test]
+    fn test_type_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("string", RespBulkString::new("world").into());
+        backend.hset("hash", "hello", RespBulkString::new("world").into())?;
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$4\r\ntype\r\n$6\r\nstring\r\n");
+        let command: CommandType = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(
+            command.execute(&backend),
+            RespSimpleString::new("string").into()
+        );
+
+        let command = CommandType {
+            key: "hash".to_string(),
+        };
+        assert_eq!(
+            command.execute(&backend),
+            RespSimpleString::new("hash").into()
+        );
+
+        let command = CommandType {
+            key: "missing".to_string(),
+        };
+        assert_eq!(
+            command.execute(&backend),
+            RespSimpleString::new("none").into()
+        );
+
+        Ok(())
+    }
+
+    fn keys(backend: &Backend, pattern: &str) -> Vec<RespFrame> {
+        let command = CommandKeys {
+            pattern: pattern.to_string(),
+        };
+        match command.execute(backend) {
+            RespFrame::Array(mut keys) => {
+                keys.0.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                keys.0
+            }
+            frame => panic!("unexpected keys reply: {:?}", frame),
+        }
+    }
+
+    #[test]
+    fn test_keys_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("user:1", RespBulkString::new("alice").into());
+        backend.set("user:2", RespBulkString::new("bob").into());
+        backend.set("user:10", RespBulkString::new("carol").into());
+        backend.hset("session", "user:1", RespBulkString::new("token").into())?;
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$4\r\nkeys\r\n$1\r\n*\r\n");
+        let command: CommandKeys = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command.pattern, "*");
+        assert_eq!(keys(&backend, "*").len(), 4);
+
+        assert_eq!(
+            keys(&backend, "user:*"),
+            vec![
+                RespBulkString::new("user:1").into(),
+                RespBulkString::new("user:10").into(),
+                RespBulkString::new("user:2").into(),
+            ]
+        );
+        assert_eq!(
+            keys(&backend, "user:?"),
+            vec![
+                RespBulkString::new("user:1").into(),
+                RespBulkString::new("user:2").into(),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dbsize_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("hello", RespBulkString::new("world").into());
+        backend.set("foo", RespBulkString::new("bar").into());
+        backend.hset("map", "hello", RespBulkString::new("world").into())?;
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*1\r\n$6\r\ndbsize\r\n");
+        let command: CommandDbSize = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command.execute(&backend), RespInteger::new(3).into());
+
+        buf.extend_from_slice(b"*2\r\n$6\r\ndbsize\r\n$5\r\nextra\r\n");
+        let ret: Result<CommandDbSize, _> = RespArray::decode(&mut buf)?.try_into();
+        assert!(ret.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_flushdb_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("hello", RespBulkString::new("world").into());
+        backend.hset("map", "hello", RespBulkString::new("world").into())?;
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*1\r\n$7\r\nflushdb\r\n");
+        let command: CommandFlushDb = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command.execute(&backend), RESP_OK.clone());
+        assert_eq!(CommandDbSize.execute(&backend), RespInteger::new(0).into());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keys_command_deadline() {
+        let backend = Backend::with_config(crate::config::ServerConfig {
+            command_deadline: Some(std::time::Duration::from_nanos(1)),
+            ..Default::default()
+        });
+        for i in 0..100_000 {
+            backend.set(&format!("key:{}", i), RespInteger::new(i).into());
+        }
+        let command = CommandKeys {
+            pattern: "*".to_string(),
+        };
+        assert_eq!(
+            command.execute(&backend),
+            RespSimpleError::new("ERR command execution timed out").into()
+        );
+
+        // the keyspace is untouched and cheap commands still run
+        assert_eq!(
+            CommandDbSize.execute(&backend),
+            RespInteger::new(100_000).into()
+        );
+    }
+
+    #[test]
+    fn test_keyspace_with_shard_amount() {
+        let backend = Backend::with_config(crate::config::ServerConfig {
+            shard_amount: 2,
+            ..Default::default()
+        });
+        for i in 0..1000 {
+            backend.set(&format!("key:{}", i), RespInteger::new(i).into());
+        }
+        assert_eq!(
+            CommandDbSize.execute(&backend),
+            RespInteger::new(1000).into()
+        );
+        assert_eq!(backend.keys("key:99*").map(|keys| keys.len()), Ok(11));
+    }
+}
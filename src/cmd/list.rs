@@ -0,0 +1,479 @@
+use crate::{
+    backend::{Backend, BackendError},
+    RespArray, RespBulkString, RespFrame, RespInteger, RespNull,
+};
+
+use super::{
+    bulk_to_bytes, bulk_to_string, extract_args, validate_command, CommandError, CommandExecutor,
+};
+
+#[derive(Debug, PartialEq)]
+pub struct CommandLPush {
+    key: String,
+    values: Vec<RespFrame>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandRPush {
+    key: String,
+    values: Vec<RespFrame>,
+}
+
+// without a count LPOP and RPOP reply with a single value, with one they reply with an array
+#[derive(Debug, PartialEq)]
+pub struct CommandLPop {
+    key: String,
+    count: Option<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandRPop {
+    key: String,
+    count: Option<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandLLen {
+    key: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandLRange {
+    key: String,
+    start: i64,
+    stop: i64,
+}
+
+// LPUSH and RPUSH take the same arguments: key value [value ...]
+fn parse_push(
+    value: RespArray,
+    name: &'static str,
+) -> Result<(String, Vec<RespFrame>), CommandError> {
+    let n_args = value.len() - 1;
+    validate_command(&value, &[name], n_args)?;
+    let mut args = extract_args(value, 1)?.into_iter();
+
+    let key = match args.next() {
+        Some(key) => bulk_to_string(key)?,
+        None => {
+            return Err(CommandError::InvalidCommandArguments(format!(
+                "{} command must have a key",
+                name
+            )))
+        }
+    };
+    let values = args
+        .map(|value| Ok(RespBulkString::new(bulk_to_bytes(value)?).into()))
+        .collect::<Result<Vec<_>, CommandError>>()?;
+    if values.is_empty() {
+        return Err(CommandError::InvalidCommandArguments(format!(
+            "Invalid {} value",
+            name
+        )));
+    }
+    Ok((key, values))
+}
+
+impl TryFrom<RespArray> for CommandLPush {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, values) = parse_push(value, "lpush")?;
+        Ok(CommandLPush { key, values })
+    }
+}
+
+impl CommandExecutor for CommandLPush {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.lpush(&self.key, self.values) {
+            Ok(len) => RespInteger::new(len as i64).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandRPush {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, values) = parse_push(value, "rpush")?;
+        Ok(CommandRPush { key, values })
+    }
+}
+
+impl CommandExecutor for CommandRPush {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.rpush(&self.key, self.values) {
+            Ok(len) => RespInteger::new(len as i64).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+// LPOP and RPOP take the same arguments: key [count]
+fn parse_pop(
+    value: RespArray,
+    name: &'static str,
+) -> Result<(String, Option<usize>), CommandError> {
+    let n_args = if value.len() == 3 { 2 } else { 1 };
+    validate_command(&value, &[name], n_args)?;
+    let mut args = extract_args(value, 1)?.into_iter();
+
+    match (args.next(), args.next()) {
+        (Some(key), count) => {
+            let count = match count {
+                Some(count) => Some(bulk_to_string(count)?.parse().map_err(|_| {
+                    CommandError::InvalidCommandArguments(
+                        "value is out of range, must be positive".to_string(),
+                    )
+                })?),
+                None => None,
+            };
+            Ok((bulk_to_string(key)?, count))
+        }
+        _ => Err(CommandError::InvalidCommandArguments(
+            "Invalid key".to_string(),
+        )),
+    }
+}
+
+fn pop_reply(
+    popped: Result<Option<Vec<RespFrame>>, BackendError>,
+    count: Option<usize>,
+) -> RespFrame {
+    match (popped, count) {
+        (Ok(popped), Some(_)) => RespArray::new(popped.unwrap_or_default()).into(),
+        (Ok(popped), None) => popped
+            .and_then(|popped| popped.into_iter().next())
+            .unwrap_or(RespFrame::Null(RespNull)),
+        (Err(e), _) => e.into(),
+    }
+}
+
+impl TryFrom<RespArray> for CommandLPop {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, count) = parse_pop(value, "lpop")?;
+        Ok(CommandLPop { key, count })
+    }
+}
+
+impl CommandExecutor for CommandLPop {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let popped = backend.lpop(&self.key, self.count.unwrap_or(1));
+        pop_reply(popped, self.count)
+    }
+}
+
+impl TryFrom<RespArray> for CommandRPop {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, count) = parse_pop(value, "rpop")?;
+        Ok(CommandRPop { key, count })
+    }
+}
+
+impl CommandExecutor for CommandRPop {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let popped = backend.rpop(&self.key, self.count.unwrap_or(1));
+        pop_reply(popped, self.count)
+    }
+}
+
+impl TryFrom<RespArray> for CommandLLen {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["llen"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match args.next() {
+            Some(key) => Ok(CommandLLen {
+                key: bulk_to_string(key)?,
+            }),
+            _ => Err(CommandError::InvalidCommandArguments(
+                "Invalid key".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for CommandLLen {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.llen(&self.key) {
+            Ok(len) => RespInteger::new(len as i64).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandLRange {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["lrange"], 3)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let parse_index = |frame| -> Result<i64, CommandError> {
+            bulk_to_string(frame)?.parse().map_err(|_| {
+                CommandError::InvalidCommandArguments(
+                    "value is not an integer or out of range".to_string(),
+                )
+            })
+        };
+        match (args.next(), args.next(), args.next()) {
+            (Some(key), Some(start), Some(stop)) => Ok(CommandLRange {
+                key: bulk_to_string(key)?,
+                start: parse_index(start)?,
+                stop: parse_index(stop)?,
+            }),
+            _ => Err(CommandError::InvalidCommandArguments(
+                "Invalid key or range".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for CommandLRange {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.lrange(&self.key, self.start, self.stop) {
+            Ok(values) => RespArray::new(values).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    use crate::{
+        backend::Backend,
+        cmd::{
+            list::{
+                CommandLLen, CommandLPop, CommandLPush, CommandLRange, CommandRPop, CommandRPush,
+            },
+            CommandExecutor, RESP_WRONGTYPE,
+        },
+        RespArray, RespBulkString, RespDecode, RespFrame, RespInteger, RespNull,
+    };
+
+    fn bulks(values: &[&str]) -> Vec<RespFrame> {
+        values
+            .iter()
+            .map(|value| RespBulkString::new(*value).into())
+            .collect()
+    }
+
+    fn lrange(backend: &Backend, start: i64, stop: i64) -> RespFrame {
+        CommandLRange {
+            key: "list".to_string(),
+            start,
+            stop,
+        }
+        .execute(backend)
+    }
+
+    #[test]
+    fn test_push_command_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$5\r\nLPUSH\r\n$4\r\nlist\r\n$1\r\na\r\n$1\r\nb\r\n");
+        let command: CommandLPush = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command.key, "list");
+        assert_eq!(command.values, bulks(&["a", "b"]));
+
+        buf.extend_from_slice(b"*2\r\n$5\r\nrpush\r\n$4\r\nlist\r\n");
+        let ret: Result<CommandRPush, _> = RespArray::decode(&mut buf)?.try_into();
+        assert!(ret.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_on_both_ends() {
+        let backend = Backend::new();
+
+        let command = CommandRPush {
+            key: "list".to_string(),
+            values: bulks(&["c", "d"]),
+        };
+        assert_eq!(command.execute(&backend), RespInteger::new(2).into());
+
+        // LPUSH pushes one value at a time, so they land at the head reversed
+        let command = CommandLPush {
+            key: "list".to_string(),
+            values: bulks(&["b", "a"]),
+        };
+        assert_eq!(command.execute(&backend), RespInteger::new(4).into());
+
+        assert_eq!(
+            lrange(&backend, 0, -1),
+            RespArray::new(bulks(&["a", "b", "c", "d"])).into()
+        );
+        let command = CommandLLen {
+            key: "list".to_string(),
+        };
+        assert_eq!(command.execute(&backend), RespInteger::new(4).into());
+    }
+
+    #[test]
+    fn test_lrange_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.rpush("list", bulks(&["a", "b", "c", "d", "e"]))?;
+
+        assert_eq!(
+            lrange(&backend, 1, 2),
+            RespArray::new(bulks(&["b", "c"])).into()
+        );
+        assert_eq!(
+            lrange(&backend, -2, -1),
+            RespArray::new(bulks(&["d", "e"])).into()
+        );
+        assert_eq!(
+            lrange(&backend, -100, 1),
+            RespArray::new(bulks(&["a", "b"])).into()
+        );
+        assert_eq!(
+            lrange(&backend, 3, 100),
+            RespArray::new(bulks(&["d", "e"])).into()
+        );
+        assert_eq!(lrange(&backend, 4, 2), RespArray::new(vec![]).into());
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$6\r\nlrange\r\n$4\r\nlist\r\n$1\r\n0\r\n$3\r\nabc\r\n");
+        let ret: Result<CommandLRange, _> = RespArray::decode(&mut buf)?.try_into();
+        assert!(ret.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_commands_on_missing_key() {
+        let backend = Backend::new();
+        assert_eq!(lrange(&backend, 0, -1), RespArray::new(vec![]).into());
+        let command = CommandLLen {
+            key: "list".to_string(),
+        };
+        assert_eq!(command.execute(&backend), RespInteger::new(0).into());
+        assert_eq!(backend.key_type("list"), "none");
+    }
+
+    #[test]
+    fn test_list_commands_on_wrong_type() {
+        let backend = Backend::new();
+        backend.set("list", RespBulkString::new("value").into());
+
+        let command = CommandLPush {
+            key: "list".to_string(),
+            values: bulks(&["a"]),
+        };
+        assert_eq!(command.execute(&backend), RESP_WRONGTYPE.clone());
+        assert_eq!(lrange(&backend, 0, -1), RESP_WRONGTYPE.clone());
+    }
+
+    #[test]
+    fn test_pop_command_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$4\r\nlpop\r\n$4\r\nlist\r\n");
+        let command: CommandLPop = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command.count, None);
+
+        buf.extend_from_slice(b"*3\r\n$4\r\nrpop\r\n$4\r\nlist\r\n$1\r\n2\r\n");
+        let command: CommandRPop = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command.count, Some(2));
+
+        buf.extend_from_slice(b"*3\r\n$4\r\nrpop\r\n$4\r\nlist\r\n$2\r\n-1\r\n");
+        let ret: Result<CommandRPop, _> = RespArray::decode(&mut buf)?.try_into();
+        assert!(ret.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pop_single_value() -> Result<()> {
+        let backend = Backend::new();
+        backend.rpush("list", bulks(&["a", "b", "c"]))?;
+
+        let command = CommandLPop {
+            key: "list".to_string(),
+            count: None,
+        };
+        assert_eq!(command.execute(&backend), RespBulkString::new("a").into());
+        let command = CommandRPop {
+            key: "list".to_string(),
+            count: None,
+        };
+        assert_eq!(command.execute(&backend), RespBulkString::new("c").into());
+        assert_eq!(
+            lrange(&backend, 0, -1),
+            RespArray::new(bulks(&["b"])).into()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pop_with_count() -> Result<()> {
+        let backend = Backend::new();
+        backend.rpush("list", bulks(&["a", "b", "c", "d"]))?;
+
+        let command = CommandRPop {
+            key: "list".to_string(),
+            count: Some(2),
+        };
+        assert_eq!(
+            command.execute(&backend),
+            RespArray::new(bulks(&["d", "c"])).into()
+        );
+
+        // asking for more than the list holds pops what is there and drops the key
+        let command = CommandLPop {
+            key: "list".to_string(),
+            count: Some(5),
+        };
+        assert_eq!(
+            command.execute(&backend),
+            RespArray::new(bulks(&["a", "b"])).into()
+        );
+        assert_eq!(backend.key_type("list"), "none");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pop_from_empty_list() {
+        let backend = Backend::new();
+
+        let command = CommandLPop {
+            key: "list".to_string(),
+            count: None,
+        };
+        assert_eq!(command.execute(&backend), RespFrame::Null(RespNull));
+        let command = CommandRPop {
+            key: "list".to_string(),
+            count: Some(3),
+        };
+        assert_eq!(command.execute(&backend), RespArray::new(vec![]).into());
+
+        backend.set("list", RespBulkString::new("value").into());
+        let command = CommandLPop {
+            key: "list".to_string(),
+            count: None,
+        };
+        assert_eq!(command.execute(&backend), RESP_WRONGTYPE.clone());
+    }
+
+    #[test]
+    fn test_lrange_command_deadline() -> Result<()> {
+        let backend = Backend::with_config(crate::config::ServerConfig {
+            command_deadline: Some(std::time::Duration::from_nanos(1)),
+            ..Default::default()
+        });
+        let values: Vec<String> = (0..10_000).map(|i| i.to_string()).collect();
+        let values: Vec<&str> = values.iter().map(String::as_str).collect();
+        backend.rpush("list", bulks(&values))?;
+
+        assert_eq!(
+            lrange(&backend, 0, -1),
+            crate::RespSimpleError::new("ERR command execution timed out").into()
+        );
+        Ok(())
+    }
+}
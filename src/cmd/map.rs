@@ -1,6 +1,20 @@
-use crate::{backend::Backend, RespArray, RespFrame, RespNull};
+use std::{
+    ops::Range,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
-use super::{extract_args, validate_command, CommandError, CommandExecutor, RESP_OK};
+use crate::{
+    backend::{
+        check_deadline, index_range, Backend, BackendError, SetCondition, SetExpiry, SetOptions,
+    },
+    RespArray, RespBulkString, RespFrame, RespInteger, RespMap, RespNull, RespSimpleError,
+    RespSimpleString,
+};
+
+use super::{
+    args_as_strings, bulk_to_bytes, bulk_to_string, extract_args, string_bytes, validate_command,
+    CommandError, CommandExecutor, RESP_OK,
+};
 
 #[derive(Debug, PartialEq)]
 pub struct CommandGet {
@@ -12,25 +26,219 @@ impl CommandGet {
     }
 }
 
+// set key value [NX | XX] [GET] [EX seconds | PX milliseconds | EXAT unix-time-seconds |
+//     PXAT unix-time-milliseconds | KEEPTTL]
 #[derive(Debug, PartialEq)]
 pub struct CommandSet {
     key: String,
     value: RespFrame,
+    options: SetOptions,
+}
+
+// setex key seconds value
+#[derive(Debug, PartialEq)]
+pub struct CommandSetEx {
+    key: String,
+    ttl: Duration,
+    value: RespFrame,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandSetNx {
+    key: String,
+    value: RespFrame,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandStrlen {
+    key: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandAppend {
+    key: String,
+    value: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandGetSet {
+    key: String,
+    value: RespFrame,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandGetDel {
+    key: String,
+}
+
+// also registered as SUBSTR, its deprecated name
+#[derive(Debug, PartialEq)]
+pub struct CommandGetRange {
+    key: String,
+    start: i64,
+    end: i64,
+}
+
+// lcs key1 key2 [LEN] [IDX] [MINMATCHLEN len] [WITHMATCHLEN]
+#[derive(Debug, PartialEq)]
+pub struct CommandLcs {
+    key1: String,
+    key2: String,
+    reply: LcsReply,
+}
+
+#[derive(Debug, PartialEq)]
+enum LcsReply {
+    Subsequence,
+    Len,
+    // the matching ranges, listed from the end of the strings like redis does
+    Idx {
+        min_match_len: usize,
+        with_match_len: bool,
+    },
 }
 
 impl CommandExecutor for CommandGet {
     fn execute(self, backend: &Backend) -> RespFrame {
         match backend.get(&self.key) {
-            Some(value) => value,
-            None => RespFrame::Null(RespNull),
+            Ok(Some(value)) => value,
+            Ok(None) => RespFrame::Null(RespNull),
+            Err(e) => e.into(),
         }
     }
 }
 
 impl CommandExecutor for CommandSet {
     fn execute(self, backend: &Backend) -> RespFrame {
-        backend.set(&self.key, self.value);
-        RESP_OK.clone()
+        match backend.set_with_options(&self.key, self.value, &self.options) {
+            // GET replies with the old value whether or not NX or XX let the SET through
+            Ok((_, Some(old))) => old,
+            Ok((_, None)) if self.options.get => RespFrame::Null(RespNull),
+            Ok((true, None)) => RESP_OK.clone(),
+            Ok((false, None)) => RespFrame::Null(RespNull),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandSet {
+    // the SET the append only file records when the ttl is relative, which includes the
+    // default ttl a plain SET gets
+    pub(crate) fn aof_frame(&self, default_ttl: Option<Duration>) -> Option<RespArray> {
+        let ttl = match (self.options.expiry, default_ttl) {
+            (SetExpiry::After(ttl), _) | (SetExpiry::Clear, Some(ttl)) => ttl,
+            _ => return None,
+        };
+        let mut args = vec!["set".into(), self.key.as_str().into(), self.value.clone()];
+        match self.options.condition {
+            Some(SetCondition::IfMissing) => args.push("nx".into()),
+            Some(SetCondition::IfExists) => args.push("xx".into()),
+            None => {}
+        }
+        if self.options.get {
+            args.push("get".into());
+        }
+        args.push("pxat".into());
+        args.push(unix_millis_after(ttl)?.into());
+        Some(RespArray::new(args))
+    }
+}
+
+impl CommandSetEx {
+    // SETEX is recorded as a SET with an absolute PXAT
+    pub(crate) fn aof_frame(&self) -> Option<RespArray> {
+        Some(RespArray::new(vec![
+            "set".into(),
+            self.key.as_str().into(),
+            self.value.clone(),
+            "pxat".into(),
+            unix_millis_after(self.ttl)?.into(),
+        ]))
+    }
+}
+
+impl CommandSetNx {
+    // with a default ttl, SETNX is recorded as a SET NX with an absolute PXAT
+    pub(crate) fn aof_frame(&self, default_ttl: Option<Duration>) -> Option<RespArray> {
+        Some(RespArray::new(vec![
+            "set".into(),
+            self.key.as_str().into(),
+            self.value.clone(),
+            "nx".into(),
+            "pxat".into(),
+            unix_millis_after(default_ttl?)?.into(),
+        ]))
+    }
+}
+
+impl CommandGetSet {
+    // with a default ttl, GETSET is recorded as a SET with an absolute PXAT
+    pub(crate) fn aof_frame(&self, default_ttl: Option<Duration>) -> Option<RespArray> {
+        Some(RespArray::new(vec![
+            "set".into(),
+            self.key.as_str().into(),
+            self.value.clone(),
+            "pxat".into(),
+            unix_millis_after(default_ttl?)?.into(),
+        ]))
+    }
+}
+
+impl CommandExecutor for CommandSetEx {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.set_ex(&self.key, self.value, self.ttl) {
+            Ok(()) => RESP_OK.clone(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for CommandSetNx {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let inserted = backend.set_nx(&self.key, self.value);
+        RespInteger::new(inserted as i64).into()
+    }
+}
+
+impl CommandExecutor for CommandStrlen {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.get(&self.key) {
+            Ok(Some(value)) => match string_bytes(&value) {
+                Ok(bytes) => RespInteger::new(bytes.len() as i64).into(),
+                Err(e) => e,
+            },
+            Ok(None) => RespInteger::new(0).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for CommandAppend {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.append(&self.key, &self.value) {
+            Ok(len) => RespInteger::new(len as i64).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for CommandGetSet {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.get_set(&self.key, self.value) {
+            Ok(Some(value)) => value,
+            Ok(None) => RespFrame::Null(RespNull),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for CommandGetDel {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.get_del(&self.key) {
+            Ok(Some(value)) => value,
+            Ok(None) => RespFrame::Null(RespNull),
+            Err(e) => e.into(),
+        }
     }
 }
 
@@ -42,9 +250,7 @@ impl TryFrom<RespArray> for CommandGet {
         let mut args = extract_args(value, 1)?.into_iter();
 
         match args.next() {
-            Some(RespFrame::BulkString(key)) => {
-                Ok(CommandGet::new(String::from_utf8_lossy(&key).to_string()))
-            }
+            Some(key) => Ok(CommandGet::new(bulk_to_string(key)?)),
             _ => Err(CommandError::InvalidCommandArguments(
                 "GET command argument must be a bulk string".to_string(),
             )),
@@ -55,12 +261,136 @@ impl TryFrom<RespArray> for CommandGet {
 impl TryFrom<RespArray> for CommandSet {
     type Error = CommandError;
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        validate_command(&value, &["set"], 2)?;
+        let n_args = value.len().saturating_sub(1).max(2);
+        validate_command(&value, &["set"], n_args)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match (args.next(), args.next()) {
+            (Some(key), Some(value)) => Ok(CommandSet {
+                key: bulk_to_string(key)?,
+                value,
+                options: parse_set_options(args_as_strings(args)?)?,
+            }),
+            _ => Err(CommandError::InvalidCommandArguments(
+                "Invalid key or value".to_string(),
+            )),
+        }
+    }
+}
+
+fn parse_set_options(args: Vec<String>) -> Result<SetOptions, CommandError> {
+    let syntax_error = || CommandError::InvalidCommandArguments("syntax error".to_string());
+    let mut options = SetOptions::default();
+    let mut expiry_set = false;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let arg = arg.to_ascii_lowercase();
+        match arg.as_str() {
+            "nx" | "xx" => {
+                if options.condition.is_some() {
+                    return Err(syntax_error());
+                }
+                options.condition = Some(if arg == "nx" {
+                    SetCondition::IfMissing
+                } else {
+                    SetCondition::IfExists
+                });
+            }
+            "get" => options.get = true,
+            "ex" | "px" | "exat" | "pxat" | "keepttl" => {
+                if expiry_set {
+                    return Err(syntax_error());
+                }
+                expiry_set = true;
+                let invalid_expire_time = || {
+                    CommandError::InvalidCommandArguments(
+                        "invalid expire time in 'set' command".to_string(),
+                    )
+                };
+                if arg == "keepttl" {
+                    options.expiry = SetExpiry::Keep;
+                    continue;
+                }
+                let amount = args
+                    .next()
+                    .ok_or_else(syntax_error)?
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|amount| *amount > 0)
+                    .ok_or_else(invalid_expire_time)?;
+                options.expiry = match arg.as_str() {
+                    "ex" => SetExpiry::After(Duration::from_secs(amount)),
+                    "px" => SetExpiry::After(Duration::from_millis(amount)),
+                    "exat" => SetExpiry::At(
+                        instant_at(Duration::from_secs(amount)).ok_or_else(invalid_expire_time)?,
+                    ),
+                    _ => SetExpiry::At(
+                        instant_at(Duration::from_millis(amount))
+                            .ok_or_else(invalid_expire_time)?,
+                    ),
+                };
+            }
+            _ => return Err(syntax_error()),
+        }
+    }
+    Ok(options)
+}
+
+// unix time `ttl` from now in milliseconds, the way PXAT takes it
+fn unix_millis_after(ttl: Duration) -> Option<String> {
+    let at = SystemTime::now().checked_add(ttl)?;
+    Some(at.duration_since(UNIX_EPOCH).ok()?.as_millis().to_string())
+}
+
+// the instant a unix time maps to, a time already past maps to now so the key is stored
+// already expired
+fn instant_at(since_epoch: Duration) -> Option<Instant> {
+    let at = UNIX_EPOCH.checked_add(since_epoch)?;
+    match at.duration_since(SystemTime::now()) {
+        Ok(left) => Instant::now().checked_add(left),
+        Err(_) => Some(Instant::now()),
+    }
+}
+
+impl TryFrom<RespArray> for CommandSetEx {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["setex"], 3)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match (args.next(), args.next(), args.next()) {
+            (Some(key), Some(seconds), Some(value)) => {
+                let seconds = bulk_to_string(seconds)?
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|seconds| *seconds > 0)
+                    .ok_or_else(|| {
+                        CommandError::InvalidCommandArguments(
+                            "invalid expire time in 'setex' command".to_string(),
+                        )
+                    })?;
+                Ok(CommandSetEx {
+                    key: bulk_to_string(key)?,
+                    ttl: Duration::from_secs(seconds),
+                    value,
+                })
+            }
+            _ => Err(CommandError::InvalidCommandArguments(
+                "Invalid key, seconds or value".to_string(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandSetNx {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["setnx"], 2)?;
         let mut args = extract_args(value, 1)?.into_iter();
 
         match (args.next(), args.next()) {
-            (Some(RespFrame::BulkString(key)), Some(value)) => Ok(CommandSet {
-                key: String::from_utf8(key.0)?,
+            (Some(key), Some(value)) => Ok(CommandSetNx {
+                key: bulk_to_string(key)?,
                 value,
             }),
             _ => Err(CommandError::InvalidCommandArguments(
@@ -70,18 +400,337 @@ impl TryFrom<RespArray> for CommandSet {
     }
 }
 
+impl TryFrom<RespArray> for CommandStrlen {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["strlen"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match args.next() {
+            Some(key) => Ok(CommandStrlen {
+                key: bulk_to_string(key)?,
+            }),
+            _ => Err(CommandError::InvalidCommandArguments(
+                "STRLEN command argument must be a bulk string".to_string(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandAppend {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["append"], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match (args.next(), args.next()) {
+            (Some(key), Some(value)) => Ok(CommandAppend {
+                key: bulk_to_string(key)?,
+                value: bulk_to_bytes(value)?,
+            }),
+            _ => Err(CommandError::InvalidCommandArguments(
+                "Invalid key or value".to_string(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandGetSet {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["getset"], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match (args.next(), args.next()) {
+            (Some(key), Some(value)) => Ok(CommandGetSet {
+                key: bulk_to_string(key)?,
+                value,
+            }),
+            _ => Err(CommandError::InvalidCommandArguments(
+                "Invalid key or value".to_string(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandGetDel {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["getdel"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match args.next() {
+            Some(key) => Ok(CommandGetDel {
+                key: bulk_to_string(key)?,
+            }),
+            _ => Err(CommandError::InvalidCommandArguments(
+                "GETDEL command argument must be a bulk string".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for CommandGetRange {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.get(&self.key) {
+            Ok(Some(value)) => match string_bytes(&value) {
+                Ok(bytes) => {
+                    let range = index_range(bytes.len(), self.start, self.end);
+                    RespBulkString::new(&bytes[range]).into()
+                }
+                Err(e) => e,
+            },
+            Ok(None) => RespBulkString::new("").into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandGetRange {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let name = match value.first() {
+            Some(RespFrame::BulkString(name)) if name.eq_ignore_ascii_case(b"substr") => "substr",
+            _ => "getrange",
+        };
+        validate_command(&value, &[name], 3)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let parse_offset = |frame| -> Result<i64, CommandError> {
+            bulk_to_string(frame)?.parse().map_err(|_| {
+                CommandError::InvalidCommandArguments(
+                    "value is not an integer or out of range".to_string(),
+                )
+            })
+        };
+        match (args.next(), args.next(), args.next()) {
+            (Some(key), Some(start), Some(end)) => Ok(CommandGetRange {
+                key: bulk_to_string(key)?,
+                start: parse_offset(start)?,
+                end: parse_offset(end)?,
+            }),
+            _ => Err(CommandError::InvalidCommandArguments(
+                "Invalid key or range".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for CommandLcs {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let deadline = backend.deadline();
+        let (a, b) = match (
+            lcs_operand(backend, &self.key1),
+            lcs_operand(backend, &self.key2),
+        ) {
+            (Ok(a), Ok(b)) => (a, b),
+            (Err(e), _) | (_, Err(e)) => return e,
+        };
+        // the table takes a cell per pair of prefixes, like redis it may not grow past
+        // proto-max-bulk-len
+        let table_size = (a.len() + 1)
+            .checked_mul(b.len() + 1)
+            .and_then(|cells| cells.checked_mul(std::mem::size_of::<u32>()));
+        if !matches!(table_size, Some(size) if size <= backend.config.proto_max_bulk_len) {
+            return RespSimpleError::new(
+                "ERR Insufficient memory, transient memory for LCS exceeds proto-max-bulk-len",
+            )
+            .into();
+        }
+        let (subsequence, matches) = match lcs(&a, &b, deadline) {
+            Ok(lcs) => lcs,
+            Err(e) => return e.into(),
+        };
+
+        match self.reply {
+            LcsReply::Subsequence => RespBulkString::new(subsequence).into(),
+            LcsReply::Len => RespInteger::new(subsequence.len() as i64).into(),
+            LcsReply::Idx {
+                min_match_len,
+                with_match_len,
+            } => {
+                let range = |range: &Range<usize>| -> RespFrame {
+                    RespArray::new(vec![
+                        RespInteger::new(range.start as i64).into(),
+                        RespInteger::new(range.end as i64 - 1).into(),
+                    ])
+                    .into()
+                };
+                let matches = matches
+                    .iter()
+                    .filter(|(a, _)| a.len() >= min_match_len)
+                    .map(|(a, b)| {
+                        let mut item = vec![range(a), range(b)];
+                        if with_match_len {
+                            item.push(RespInteger::new(a.len() as i64).into());
+                        }
+                        RespArray::new(item).into()
+                    })
+                    .collect();
+                let fields: [(&str, RespFrame); 2] = [
+                    ("matches", RespArray::new(matches).into()),
+                    ("len", RespInteger::new(subsequence.len() as i64).into()),
+                ];
+
+                // same layout as HELLO, a flat array unless the connection speaks RESP3
+                if backend.session().protocol() == 2 {
+                    let mut reply = Vec::with_capacity(fields.len() * 2);
+                    for (name, value) in fields {
+                        reply.push(RespBulkString::new(name).into());
+                        reply.push(value);
+                    }
+                    RespArray::new(reply).into()
+                } else {
+                    let mut reply = RespMap::new();
+                    for (name, value) in fields {
+                        reply.insert(RespSimpleString::new(name), value);
+                    }
+                    reply.into()
+                }
+            }
+        }
+    }
+}
+
+// missing keys compare as empty strings
+fn lcs_operand(backend: &Backend, key: &str) -> Result<Vec<u8>, RespFrame> {
+    match backend.get(key) {
+        Ok(Some(value)) => string_bytes(&value).map(|bytes| bytes.into_owned()),
+        Ok(None) => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// a run of the subsequence, as (range in a, range in b)
+type LcsMatch = (Range<usize>, Range<usize>);
+
+// the longest common subsequence of `a` and `b`, plus the runs it is made of,
+// ordered from the end of the strings to the start. gives up once deadline passes
+fn lcs(
+    a: &[u8],
+    b: &[u8],
+    deadline: Option<Instant>,
+) -> Result<(Vec<u8>, Vec<LcsMatch>), BackendError> {
+    // table[i * width + j] is the LCS length of a[..i] and b[..j]
+    let width = b.len() + 1;
+    let mut table = vec![0u32; (a.len() + 1) * width];
+    for i in 1..=a.len() {
+        check_deadline(deadline)?;
+        for j in 1..=b.len() {
+            table[i * width + j] = if a[i - 1] == b[j - 1] {
+                table[(i - 1) * width + j - 1] + 1
+            } else {
+                table[(i - 1) * width + j].max(table[i * width + j - 1])
+            };
+        }
+    }
+
+    // walk back from the end, growing the current run while matches stay adjacent
+    let mut subsequence = Vec::with_capacity(table[table.len() - 1] as usize);
+    let mut matches = Vec::new();
+    let mut current: Option<LcsMatch> = None;
+    let (mut i, mut j) = (a.len(), b.len());
+    while i > 0 && j > 0 {
+        if a[i - 1] == b[j - 1] {
+            subsequence.push(a[i - 1]);
+            match &mut current {
+                Some((ra, rb)) if ra.start == i && rb.start == j => {
+                    ra.start -= 1;
+                    rb.start -= 1;
+                }
+                _ => {
+                    matches.extend(current.take());
+                    current = Some((i - 1..i, j - 1..j));
+                }
+            }
+            i -= 1;
+            j -= 1;
+        } else if table[(i - 1) * width + j] > table[i * width + j - 1] {
+            i -= 1;
+        } else {
+            j -= 1;
+        }
+    }
+    matches.extend(current);
+    subsequence.reverse();
+    Ok((subsequence, matches))
+}
+
+impl TryFrom<RespArray> for CommandLcs {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let n_args = value.len().saturating_sub(1).max(2);
+        validate_command(&value, &["lcs"], n_args)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        let (Some(key1), Some(key2)) = (args.next(), args.next()) else {
+            return Err(CommandError::InvalidCommandArguments(
+                "Invalid key".to_string(),
+            ));
+        };
+
+        let (mut len, mut idx, mut min_match_len, mut with_match_len) = (false, false, 0, false);
+        let mut options = args_as_strings(args)?.into_iter();
+        while let Some(option) = options.next() {
+            match option.to_ascii_lowercase().as_str() {
+                "len" => len = true,
+                "idx" => idx = true,
+                "withmatchlen" => with_match_len = true,
+                "minmatchlen" => {
+                    min_match_len =
+                        options.next().and_then(|n| n.parse().ok()).ok_or_else(|| {
+                            CommandError::InvalidCommandArguments(
+                                "value is not an integer or out of range".to_string(),
+                            )
+                        })?
+                }
+                _ => {
+                    return Err(CommandError::InvalidCommandArguments(format!(
+                        "Unknown LCS option: {}",
+                        option
+                    )))
+                }
+            }
+        }
+
+        let reply = match (len, idx) {
+            (true, true) => {
+                return Err(CommandError::InvalidCommandArguments(
+                    "If you want both the length and indexes, please just use IDX.".to_string(),
+                ))
+            }
+            (true, false) => LcsReply::Len,
+            (false, true) => LcsReply::Idx {
+                min_match_len,
+                with_match_len,
+            },
+            (false, false) => LcsReply::Subsequence,
+        };
+        Ok(CommandLcs {
+            key1: bulk_to_string(key1)?,
+            key2: bulk_to_string(key2)?,
+            reply,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use anyhow::{Ok, Result};
     use bytes::BytesMut;
 
     use crate::{
-        backend::Backend,
+        backend::{index_range, Backend, BackendError, SetOptions},
         cmd::{
-            map::{CommandGet, CommandSet},
-            CommandExecutor, RESP_OK,
+            map::{
+                CommandAppend, CommandGet, CommandGetDel, CommandGetRange, CommandGetSet,
+                CommandLcs, CommandSet, CommandSetEx, CommandSetNx, CommandStrlen, LcsReply,
+            },
+            CommandExecutor, RESP_OK, RESP_WRONGTYPE,
         },
-        RespArray, RespBulkString, RespDecode, RespFrame,
+        config::ServerConfig,
+        RespArray, RespBulkString, RespDecode, RespFrame, RespInteger, RespNull, RespSimpleError,
     };
 
     #[test]
@@ -116,6 +765,7 @@ mod tests {
         let set_command: CommandSet = CommandSet {
             key: "hello".to_string(),
             value: RespFrame::BulkString(RespBulkString::new(b"world".to_vec())),
+            options: SetOptions::default(),
         };
 
         let result = set_command.execute(&backend);
@@ -132,4 +782,680 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_set_with_get_option() -> Result<()> {
+        let backend = Backend::new();
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$3\r\nset\r\n$5\r\nhello\r\n$5\r\nworld\r\n$3\r\nGET\r\n");
+        let command: CommandSet = RespArray::decode(&mut buf)?.try_into()?;
+        assert!(command.options.get);
+        assert_eq!(command.execute(&backend), RespFrame::Null(RespNull));
+
+        let command = CommandSet {
+            key: "hello".to_string(),
+            value: RespBulkString::new("again").into(),
+            options: SetOptions {
+                get: true,
+                ..Default::default()
+            },
+        };
+        assert_eq!(
+            command.execute(&backend),
+            RespBulkString::new("world").into()
+        );
+        assert_eq!(
+            backend.get("hello")?,
+            Some(RespBulkString::new("again").into())
+        );
+
+        // a hash can't be returned by GET, so the key is left alone
+        backend.hset("hashkey", "field", RespBulkString::new("value").into())?;
+        let command = CommandSet {
+            key: "hashkey".to_string(),
+            value: RespBulkString::new("v").into(),
+            options: SetOptions {
+                get: true,
+                ..Default::default()
+            },
+        };
+        assert_eq!(command.execute(&backend), RESP_WRONGTYPE.clone());
+        assert_eq!(backend.key_type("hashkey"), "hash");
+        assert_eq!(
+            backend.hget("hashkey", "field")?,
+            Some(RespBulkString::new("value").into())
+        );
+
+        buf.extend_from_slice(b"*4\r\n$3\r\nset\r\n$5\r\nhello\r\n$5\r\nworld\r\n$3\r\nPUT\r\n");
+        let ret: Result<CommandSet, _> = RespArray::decode(&mut buf)?.try_into();
+        assert!(ret.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_setnx_command() -> Result<()> {
+        let backend = Backend::new();
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$5\r\nsetnx\r\n$5\r\nhello\r\n$5\r\nworld\r\n");
+        let command: CommandSetNx = RespArray::decode(&mut buf)?.try_into()?;
+        let result = command.execute(&backend);
+        assert_eq!(result, RespInteger::new(1).into());
+
+        let command = CommandSetNx {
+            key: "hello".to_string(),
+            value: RespFrame::BulkString(RespBulkString::new(b"again".to_vec())),
+        };
+        let result = command.execute(&backend);
+        assert_eq!(result, RespInteger::new(0).into());
+        assert_eq!(
+            backend.get("hello")?,
+            Some(RespFrame::BulkString(RespBulkString::new(
+                b"world".to_vec()
+            )))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_strlen_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("hello", RespBulkString::new("world").into());
+        backend.set(
+            "list",
+            RespArray::new(vec![RespBulkString::new("world").into()]).into(),
+        );
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$6\r\nstrlen\r\n$5\r\nhello\r\n");
+        let command: CommandStrlen = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command.execute(&backend), RespInteger::new(5).into());
+
+        let command = CommandStrlen {
+            key: "missing".to_string(),
+        };
+        assert_eq!(command.execute(&backend), RespInteger::new(0).into());
+
+        let command = CommandStrlen {
+            key: "list".to_string(),
+        };
+        assert_eq!(command.execute(&backend), RESP_WRONGTYPE.clone());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_command() -> Result<()> {
+        let backend = Backend::new();
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$6\r\nappend\r\n$5\r\nhello\r\n$5\r\nworld\r\n");
+        let command: CommandAppend = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command.execute(&backend), RespInteger::new(5).into());
+        assert_eq!(
+            backend.get("hello")?,
+            Some(RespBulkString::new("world").into())
+        );
+
+        let command = CommandAppend {
+            key: "hello".to_string(),
+            value: b" again".to_vec(),
+        };
+        assert_eq!(command.execute(&backend), RespInteger::new(11).into());
+        assert_eq!(
+            backend.get("hello")?,
+            Some(RespBulkString::new("world again").into())
+        );
+
+        backend.set(
+            "list",
+            RespArray::new(vec![RespBulkString::new("world").into()]).into(),
+        );
+        let command = CommandAppend {
+            key: "list".to_string(),
+            value: b"world".to_vec(),
+        };
+        assert_eq!(command.execute(&backend), RESP_WRONGTYPE.clone());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_getset_command() -> Result<()> {
+        let backend = Backend::new();
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$6\r\ngetset\r\n$5\r\nhello\r\n$5\r\nworld\r\n");
+        let command: CommandGetSet = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command.execute(&backend), RespFrame::Null(RespNull));
+
+        let command = CommandGetSet {
+            key: "hello".to_string(),
+            value: RespBulkString::new("again").into(),
+        };
+        assert_eq!(
+            command.execute(&backend),
+            RespBulkString::new("world").into()
+        );
+        assert_eq!(
+            backend.get("hello")?,
+            Some(RespBulkString::new("again").into())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_getdel_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("hello", RespBulkString::new("world").into());
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$6\r\ngetdel\r\n$5\r\nhello\r\n");
+        let command: CommandGetDel = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(
+            command.execute(&backend),
+            RespBulkString::new("world").into()
+        );
+
+        let command = CommandGet::new("hello".to_string());
+        assert_eq!(command.execute(&backend), RespFrame::Null(RespNull));
+
+        let command = CommandGetDel {
+            key: "hello".to_string(),
+        };
+        assert_eq!(command.execute(&backend), RespFrame::Null(RespNull));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_exceeding_max_bulk_len() -> Result<()> {
+        let backend = Backend::with_config(ServerConfig {
+            proto_max_bulk_len: 8,
+            ..Default::default()
+        });
+
+        let command = CommandAppend {
+            key: "hello".to_string(),
+            value: b"world".to_vec(),
+        };
+        assert_eq!(command.execute(&backend), RespInteger::new(5).into());
+
+        let command = CommandAppend {
+            key: "hello".to_string(),
+            value: b" again".to_vec(),
+        };
+        assert_eq!(
+            command.execute(&backend),
+            RespSimpleError::new("ERR string exceeds maximum allowed size").into()
+        );
+        assert_eq!(
+            backend.get("hello")?,
+            Some(RespBulkString::new("world").into())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_to_counter() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("counter", RespInteger::new(5).into());
+
+        let command = CommandAppend {
+            key: "counter".to_string(),
+            value: b"x".to_vec(),
+        };
+        assert_eq!(command.execute(&backend), RespInteger::new(2).into());
+        assert_eq!(
+            backend.get("counter")?,
+            Some(RespBulkString::new("5x").into())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_commands_on_hash_key() -> Result<()> {
+        let backend = Backend::new();
+        backend.hset("key", "field", RespBulkString::new("value").into())?;
+
+        let command = CommandGet::new("key".to_string());
+        assert_eq!(command.execute(&backend), RESP_WRONGTYPE.clone());
+
+        let command = CommandGetDel {
+            key: "key".to_string(),
+        };
+        assert_eq!(command.execute(&backend), RESP_WRONGTYPE.clone());
+        assert_eq!(backend.key_type("key"), "hash");
+
+        // SET replaces whatever the key held
+        let command = CommandSet {
+            key: "key".to_string(),
+            value: RespBulkString::new("value").into(),
+            options: SetOptions::default(),
+        };
+        assert_eq!(command.execute(&backend), RESP_OK.clone());
+        assert_eq!(backend.key_type("key"), "string");
+        assert_eq!(backend.hget("key", "field"), Err(BackendError::WrongType));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_range() {
+        assert_eq!(index_range(13, 0, 3), 0..4);
+        assert_eq!(index_range(13, -3, -1), 10..13);
+        assert_eq!(index_range(13, 0, -1), 0..13);
+        assert_eq!(index_range(13, 10, 100), 10..13);
+        assert_eq!(index_range(13, -100, 2), 0..3);
+        assert_eq!(index_range(13, 5, 3), 0..0);
+        assert_eq!(index_range(0, 0, -1), 0..0);
+    }
+
+    fn getrange(backend: &Backend, name: &str, start: &str, end: &str) -> Result<RespFrame> {
+        let frame = RespArray::new(vec![
+            RespBulkString::new(name).into(),
+            RespBulkString::new("key").into(),
+            RespBulkString::new(start).into(),
+            RespBulkString::new(end).into(),
+        ]);
+        let command: crate::cmd::Command = frame.try_into()?;
+        Ok(command.execute(backend))
+    }
+
+    #[test]
+    fn test_getrange_and_substr_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("key", RespBulkString::new("This is a string").into());
+
+        for (start, end, expected) in [
+            ("0", "3", "This"),
+            ("-3", "-1", "ing"),
+            ("0", "-1", "This is a string"),
+            ("10", "100", "string"),
+        ] {
+            let expected: RespFrame = RespBulkString::new(expected).into();
+            assert_eq!(getrange(&backend, "getrange", start, end)?, expected);
+            assert_eq!(getrange(&backend, "substr", start, end)?, expected);
+        }
+
+        let command = CommandGetRange {
+            key: "missing".to_string(),
+            start: 0,
+            end: -1,
+        };
+        assert_eq!(command.execute(&backend), RespBulkString::new("").into());
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$6\r\nsubstr\r\n$3\r\nkey\r\n$1\r\na\r\n$1\r\n1\r\n");
+        let ret: Result<CommandGetRange, _> = RespArray::decode(&mut buf)?.try_into();
+        assert!(ret.is_err());
+
+        Ok(())
+    }
+
+    fn lcs(backend: &Backend, args: &[&str]) -> Result<RespFrame> {
+        let mut frames = vec![RespBulkString::new("lcs").into()];
+        frames.extend(args.iter().map(|arg| RespBulkString::new(*arg).into()));
+        let command: CommandLcs = RespArray::new(frames).try_into()?;
+        Ok(command.execute(backend))
+    }
+
+    fn pos(start: i64, end: i64) -> RespFrame {
+        RespArray::new(vec![
+            RespInteger::new(start).into(),
+            RespInteger::new(end).into(),
+        ])
+        .into()
+    }
+
+    #[test]
+    fn test_lcs_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("key1", RespBulkString::new("ohmytext").into());
+        backend.set("key2", RespBulkString::new("mynewtext").into());
+
+        assert_eq!(
+            lcs(&backend, &["key1", "key2"])?,
+            RespBulkString::new("mytext").into()
+        );
+        assert_eq!(
+            lcs(&backend, &["key1", "key2", "LEN"])?,
+            RespInteger::new(6).into()
+        );
+
+        let idx = lcs(
+            &backend,
+            &["key1", "key2", "IDX", "MINMATCHLEN", "4", "WITHMATCHLEN"],
+        )?;
+        let expected = RespArray::new(vec![
+            RespBulkString::new("matches").into(),
+            RespArray::new(vec![RespArray::new(vec![
+                pos(4, 7),
+                pos(5, 8),
+                RespInteger::new(4).into(),
+            ])
+            .into()])
+            .into(),
+            RespBulkString::new("len").into(),
+            RespInteger::new(6).into(),
+        ]);
+        assert_eq!(idx, expected.into());
+
+        let RespFrame::Array(idx) = lcs(&backend, &["key1", "key2", "idx"])? else {
+            panic!("IDX should reply with an array");
+        };
+        assert_eq!(
+            idx[1],
+            RespArray::new(vec![
+                RespArray::new(vec![pos(4, 7), pos(5, 8)]).into(),
+                RespArray::new(vec![pos(2, 3), pos(0, 1)]).into(),
+            ])
+            .into()
+        );
+
+        let ret: Result<CommandLcs, _> = RespArray::new(vec![
+            RespBulkString::new("lcs").into(),
+            RespBulkString::new("key1").into(),
+            RespBulkString::new("key2").into(),
+            RespBulkString::new("len").into(),
+            RespBulkString::new("idx").into(),
+        ])
+        .try_into();
+        assert!(ret.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lcs_table_limited_by_max_bulk_len() -> Result<()> {
+        let backend = Backend::with_config(ServerConfig {
+            proto_max_bulk_len: 1024,
+            ..Default::default()
+        });
+        backend.set("key1", RespBulkString::new("ohmytext").into());
+        backend.set("key2", RespBulkString::new("mynewtext").into());
+        assert_eq!(
+            lcs(&backend, &["key1", "key2"])?,
+            RespBulkString::new("mytext").into()
+        );
+
+        // 21 * 21 cells of 4 bytes don't fit in 1024
+        backend.set("key1", RespBulkString::new("a".repeat(20)).into());
+        backend.set("key2", RespBulkString::new("a".repeat(20)).into());
+        assert_eq!(
+            lcs(&backend, &["key1", "key2"])?,
+            RespSimpleError::new(
+                "ERR Insufficient memory, transient memory for LCS exceeds proto-max-bulk-len"
+            )
+            .into()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_lcs_without_common_subsequence() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("key1", RespBulkString::new("abc").into());
+        backend.set("key2", RespBulkString::new("xyz").into());
+
+        assert_eq!(
+            lcs(&backend, &["key1", "key2"])?,
+            RespBulkString::new("").into()
+        );
+        assert_eq!(
+            lcs(&backend, &["key1", "key2", "len"])?,
+            RespInteger::new(0).into()
+        );
+        // a missing key is an empty string
+        assert_eq!(
+            lcs(&backend, &["key1", "missing", "len"])?,
+            RespInteger::new(0).into()
+        );
+
+        backend.hset("map", "field", RespBulkString::new("abc").into())?;
+        assert_eq!(lcs(&backend, &["key1", "map"])?, RESP_WRONGTYPE.clone());
+
+        let command = CommandLcs {
+            key1: "key1".to_string(),
+            key2: "key1".to_string(),
+            reply: LcsReply::Len,
+        };
+        assert_eq!(command.execute(&backend), RespInteger::new(3).into());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_setex_command() -> Result<()> {
+        let backend = Backend::new();
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$5\r\nsetex\r\n$5\r\nhello\r\n$2\r\n10\r\n$5\r\nworld\r\n");
+        let command: CommandSetEx = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command.execute(&backend), RESP_OK.clone());
+        assert_eq!(
+            backend.get("hello")?,
+            Some(RespBulkString::new("world").into())
+        );
+        let ttl = backend.ttl("hello").expect("SETEX should set a ttl");
+        assert!(ttl <= Duration::from_secs(10) && ttl > Duration::from_secs(9));
+
+        // a plain SET drops the ttl again
+        backend.set("hello", RespBulkString::new("again").into());
+        assert_eq!(backend.ttl("hello"), None);
+
+        // the key is gone once its time is up
+        backend.set_ex(
+            "short",
+            RespBulkString::new("x").into(),
+            Duration::from_millis(10),
+        )?;
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(backend.get("short")?, None);
+        assert_eq!(backend.key_type("short"), "none");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_setex_rejects_invalid_seconds() -> Result<()> {
+        for seconds in ["0", "-5", "ten", "1.5"] {
+            let command = RespArray::new(vec![
+                RespBulkString::new("setex").into(),
+                RespBulkString::new("key").into(),
+                RespBulkString::new(seconds).into(),
+                RespBulkString::new("value").into(),
+            ]);
+            let ret = crate::cmd::dispatch(command, &Backend::new());
+            assert_eq!(
+                ret,
+                RespSimpleError::new(
+                    "ERR Invalid command arguments: invalid expire time in 'setex' command"
+                )
+                .into(),
+                "{}",
+                seconds
+            );
+        }
+        Ok(())
+    }
+
+    fn set(backend: &Backend, args: &[&str]) -> Result<RespFrame> {
+        let mut frames = vec![RespBulkString::new("set").into()];
+        frames.extend(args.iter().map(|arg| RespBulkString::new(*arg).into()));
+        let command: CommandSet = RespArray::new(frames).try_into()?;
+        Ok(command.execute(backend))
+    }
+
+    #[test]
+    fn test_set_nx_and_xx() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("k", RespBulkString::new("old").into());
+
+        // NX on an existing key leaves it alone
+        assert_eq!(set(&backend, &["k", "v", "NX"])?, RespFrame::Null(RespNull));
+        assert_eq!(backend.get("k")?, Some(RespBulkString::new("old").into()));
+
+        // XX on a missing key doesn't create it
+        assert_eq!(
+            set(&backend, &["missing", "v", "XX"])?,
+            RespFrame::Null(RespNull)
+        );
+        assert_eq!(backend.get("missing")?, None);
+
+        assert_eq!(set(&backend, &["k", "new", "xx"])?, RESP_OK.clone());
+        assert_eq!(set(&backend, &["fresh", "v", "nx"])?, RESP_OK.clone());
+        assert_eq!(backend.get("k")?, Some(RespBulkString::new("new").into()));
+
+        // GET still replies with the old value when NX keeps it
+        assert_eq!(
+            set(&backend, &["k", "v", "NX", "GET"])?,
+            RespBulkString::new("new").into()
+        );
+
+        assert!(set(&backend, &["k", "v", "NX", "XX"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_expiry_options() -> Result<()> {
+        let backend = Backend::new();
+
+        assert_eq!(set(&backend, &["k", "v", "EX", "5"])?, RESP_OK.clone());
+        let ttl = backend.ttl("k").expect("EX should set a ttl");
+        assert!(ttl <= Duration::from_secs(5) && ttl > Duration::from_secs(4));
+
+        assert_eq!(set(&backend, &["k", "v2", "KEEPTTL"])?, RESP_OK.clone());
+        assert!(backend.ttl("k").is_some());
+        assert_eq!(set(&backend, &["k", "v3"])?, RESP_OK.clone());
+        assert_eq!(backend.ttl("k"), None);
+
+        assert_eq!(set(&backend, &["k", "v", "px", "1500"])?, RESP_OK.clone());
+        let ttl = backend.ttl("k").expect("PX should set a ttl");
+        assert!(ttl <= Duration::from_millis(1500) && ttl > Duration::from_millis(500));
+
+        for args in [
+            &["k", "v", "EX"][..],
+            &["k", "v", "EX", "0"],
+            &["k", "v", "EX", "-1"],
+            &["k", "v", "EX", "5", "PX", "100"],
+            &["k", "v", "EX", "5", "KEEPTTL"],
+            &["k", "v", "FOO"],
+        ] {
+            assert!(set(&backend, args).is_err(), "{:?}", args);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_absolute_expiry() -> Result<()> {
+        let backend = Backend::new();
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?;
+
+        let at = (now + Duration::from_secs(10)).as_millis().to_string();
+        assert_eq!(set(&backend, &["k", "v", "PXAT", &at])?, RESP_OK.clone());
+        let ttl = backend.ttl("k").expect("PXAT should set a ttl");
+        assert!(ttl <= Duration::from_secs(10) && ttl > Duration::from_secs(8));
+
+        let at = (now + Duration::from_secs(10)).as_secs().to_string();
+        assert_eq!(set(&backend, &["k", "v", "exat", &at])?, RESP_OK.clone());
+        assert!(backend.ttl("k").is_some());
+
+        // a time already past stores the key expired
+        assert_eq!(set(&backend, &["k", "v", "pxat", "1000"])?, RESP_OK.clone());
+        assert_eq!(backend.get("k")?, None);
+
+        assert!(set(&backend, &["k", "v", "PXAT", "0"]).is_err());
+        assert!(set(&backend, &["k", "v", "EX", "5", "PXAT", &at]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_default_ttl() -> Result<()> {
+        let backend = Backend::with_config(crate::config::ServerConfig {
+            default_ttl: Some(Duration::from_secs(60)),
+            ..Default::default()
+        });
+
+        assert_eq!(set(&backend, &["k", "v"])?, RESP_OK.clone());
+        let ttl = backend
+            .ttl("k")
+            .expect("a plain SET should get the default ttl");
+        assert!(ttl <= Duration::from_secs(60) && ttl > Duration::from_secs(58));
+
+        // an explicit expiry wins over the default
+        assert_eq!(set(&backend, &["k", "v", "EX", "5"])?, RESP_OK.clone());
+        let ttl = backend.ttl("k").expect("EX should set a ttl");
+        assert!(ttl <= Duration::from_secs(5));
+
+        // KEEPTTL keeps the ttl the key already has
+        assert_eq!(set(&backend, &["k", "v2", "KEEPTTL"])?, RESP_OK.clone());
+        assert!(backend.ttl("k").expect("KEEPTTL should keep the ttl") <= Duration::from_secs(5));
+
+        // the append only file records the default ttl as an absolute time
+        let cmd = CommandSet::try_from(RespArray::new(vec![
+            RespBulkString::new("set").into(),
+            RespBulkString::new("k").into(),
+            RespBulkString::new("v").into(),
+        ]))?;
+        assert!(cmd.aof_frame(None).is_none());
+        assert!(cmd.aof_frame(backend.config.default_ttl).is_some());
+
+        // SETNX and GETSET store keys without an expiry of their own as well
+        let cmd = CommandSetNx::try_from(crate::cmd::command(&["setnx", "nx", "v"]))?;
+        assert!(cmd.aof_frame(None).is_none());
+        assert!(cmd.aof_frame(backend.config.default_ttl).is_some());
+        assert_eq!(cmd.execute(&backend), RespInteger::new(1).into());
+        let ttl = backend.ttl("nx").expect("SETNX should get the default ttl");
+        assert!(ttl > Duration::from_secs(58));
+
+        assert_eq!(set(&backend, &["k", "v", "EX", "5"])?, RESP_OK.clone());
+        let cmd = CommandGetSet::try_from(crate::cmd::command(&["getset", "k", "v2"]))?;
+        assert!(cmd.aof_frame(backend.config.default_ttl).is_some());
+        assert_eq!(cmd.execute(&backend), RespBulkString::new("v").into());
+        let ttl = backend.ttl("k").expect("GETSET should get the default ttl");
+        assert!(ttl > Duration::from_secs(58));
+        Ok(())
+    }
+
+    #[test]
+    fn test_stored_values_dont_share_the_read_buffer() -> Result<()> {
+        let backend = Backend::new();
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$3\r\nset\r\n$1\r\nk\r\n$5\r\nhello\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let RespFrame::BulkString(sent) = &frame[2] else {
+            panic!("expected a bulk string");
+        };
+        let sent = sent.as_ptr();
+
+        let cmd = CommandSet::try_from(frame)?;
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        let Some(RespFrame::BulkString(stored)) = backend.get("k")? else {
+            panic!("expected the stored bulk string");
+        };
+        assert_eq!(stored, RespBulkString::new("hello"));
+        assert_ne!(stored.as_ptr(), sent);
+        Ok(())
+    }
+
+    #[test]
+    fn test_lcs_command_deadline() -> Result<()> {
+        let backend = Backend::with_config(ServerConfig {
+            command_deadline: Some(Duration::from_nanos(1)),
+            ..Default::default()
+        });
+        backend.set("key1", RespBulkString::new("ab".repeat(100)).into());
+        backend.set("key2", RespBulkString::new("ba".repeat(100)).into());
+
+        assert_eq!(
+            lcs(&backend, &["key1", "key2"])?,
+            RespSimpleError::new("ERR command execution timed out").into()
+        );
+        Ok(())
+    }
 }
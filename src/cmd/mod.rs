@@ -1,26 +1,55 @@
+mod config;
+mod connection;
 mod echo;
 mod hmap;
+mod info;
+mod keyspace;
+mod list;
 mod map;
+mod ping;
+mod pubsub;
+mod server;
+mod set_cmd;
 mod unknow;
 
+use config::CommandConfig;
+use connection::{CommandAuth, CommandHello, CommandSelect};
 use echo::CommandEcho;
 use enum_dispatch::enum_dispatch;
-use hmap::{CommandHGet, CommandHGetAll, CommandHMGet, CommandHSet};
+use hmap::{
+    CommandHDel, CommandHExists, CommandHGet, CommandHGetAll, CommandHIncrBy, CommandHKeys,
+    CommandHLen, CommandHMGet, CommandHSet, CommandHVals,
+};
+use info::CommandInfo;
+use keyspace::{CommandDbSize, CommandFlushDb, CommandKeys, CommandType};
 use lazy_static::lazy_static;
-use map::{CommandGet, CommandSet};
-use std::string::FromUtf8Error;
+use list::{CommandLLen, CommandLPop, CommandLPush, CommandLRange, CommandRPop, CommandRPush};
+use map::{
+    CommandAppend, CommandGet, CommandGetDel, CommandGetRange, CommandGetSet, CommandLcs,
+    CommandSet, CommandSetEx, CommandSetNx, CommandStrlen,
+};
+use ping::CommandPing;
+use pubsub::{CommandPublish, CommandSubscribe, CommandUnsubscribe};
+use server::{CommandCommand, CommandDebug, CommandLolwut, CommandMemory, CommandSave};
+use set_cmd::{
+    CommandSAdd, CommandSCard, CommandSDiff, CommandSInter, CommandSIsMember, CommandSMembers,
+    CommandSRem, CommandSUnion,
+};
+use std::{borrow::Cow, string::FromUtf8Error};
 use thiserror::Error;
 use unknow::CommandUnknown;
 
 use crate::{
-    backend::Backend, RespArray, RespDecodeError, RespFrame, RespSimpleError, RespSimpleString,
+    backend::{Backend, BackendError},
+    config::ServerConfig,
+    RespArray, RespBulkString, RespDecodeError, RespFrame, RespSimpleError, RespSimpleString,
 };
 
 lazy_static! {
     static ref RESP_OK: RespFrame =
         RespFrame::SimpleString(RespSimpleString::new("OK".to_string()));
-    static ref RESP_UNKNOWNN_COMMAND: RespFrame =
-        RespFrame::Error(RespSimpleError::new("Unknown command".to_string()));
+    // the same text the backend uses, so every command rejects the wrong type alike
+    static ref RESP_WRONGTYPE: RespFrame = BackendError::WrongType.into();
 }
 
 #[enum_dispatch]
@@ -43,39 +72,305 @@ pub enum CommandError {
     FromUtf8Error(#[from] FromUtf8Error),
 }
 
+// a malformed command only fails that command, the client gets an error reply and the
+// connection stays usable
+impl From<CommandError> for RespFrame {
+    fn from(err: CommandError) -> Self {
+        RespSimpleError::new(format!("ERR {}", err)).into()
+    }
+}
+
 #[derive(Debug, PartialEq)]
 #[enum_dispatch(CommandExecutor)]
 pub enum Command {
     Get(CommandGet),
     Set(CommandSet),
+    SetNx(CommandSetNx),
+    SetEx(CommandSetEx),
+    Strlen(CommandStrlen),
+    Append(CommandAppend),
+    GetSet(CommandGetSet),
+    GetDel(CommandGetDel),
+    GetRange(CommandGetRange),
+    Lcs(CommandLcs),
     HGet(CommandHGet),
     HSet(CommandHSet),
     HGetAll(CommandHGetAll),
     HMGet(CommandHMGet),
+    HDel(CommandHDel),
+    HExists(CommandHExists),
+    HKeys(CommandHKeys),
+    HVals(CommandHVals),
+    HLen(CommandHLen),
+    HIncrBy(CommandHIncrBy),
+    LPush(CommandLPush),
+    RPush(CommandRPush),
+    LPop(CommandLPop),
+    RPop(CommandRPop),
+    LLen(CommandLLen),
+    LRange(CommandLRange),
+    SAdd(CommandSAdd),
+    SRem(CommandSRem),
+    SIsMember(CommandSIsMember),
+    SMembers(CommandSMembers),
+    SCard(CommandSCard),
+    SInter(CommandSInter),
+    SUnion(CommandSUnion),
+    SDiff(CommandSDiff),
 
     Echo(CommandEcho),
+    Ping(CommandPing),
+    Hello(CommandHello),
+    Select(CommandSelect),
+    Auth(CommandAuth),
+
+    Type(CommandType),
+    Keys(CommandKeys),
+    DbSize(CommandDbSize),
+    FlushDb(CommandFlushDb),
+
+    Config(CommandConfig),
+    Info(CommandInfo),
+    Lolwut(CommandLolwut),
+    Debug(CommandDebug),
+    Memory(CommandMemory),
+    Save(CommandSave),
+    Command(CommandCommand),
+
+    Publish(CommandPublish),
+    Subscribe(CommandSubscribe),
+    Unsubscribe(CommandUnsubscribe),
 
     // unknown commands
     UnknownCommand(CommandUnknown),
 }
 
+type CommandParser = fn(RespArray) -> Result<Command, CommandError>;
+
+// every command name dispatch knows, COMMAND COUNT and COMMAND LIST read it as well
+const COMMAND_TABLE: &[(&str, CommandParser)] = &[
+    ("get", parse::<CommandGet>),
+    ("set", parse::<CommandSet>),
+    ("setnx", parse::<CommandSetNx>),
+    ("setex", parse::<CommandSetEx>),
+    ("strlen", parse::<CommandStrlen>),
+    ("append", parse::<CommandAppend>),
+    ("getset", parse::<CommandGetSet>),
+    ("getdel", parse::<CommandGetDel>),
+    ("getrange", parse::<CommandGetRange>),
+    ("substr", parse::<CommandGetRange>),
+    ("lcs", parse::<CommandLcs>),
+    ("hget", parse::<CommandHGet>),
+    ("hset", parse::<CommandHSet>),
+    ("hgetall", parse::<CommandHGetAll>),
+    ("hmget", parse::<CommandHMGet>),
+    ("hdel", parse::<CommandHDel>),
+    ("hexists", parse::<CommandHExists>),
+    ("hkeys", parse::<CommandHKeys>),
+    ("hvals", parse::<CommandHVals>),
+    ("hlen", parse::<CommandHLen>),
+    ("hincrby", parse::<CommandHIncrBy>),
+    ("lpush", parse::<CommandLPush>),
+    ("rpush", parse::<CommandRPush>),
+    ("lpop", parse::<CommandLPop>),
+    ("rpop", parse::<CommandRPop>),
+    ("llen", parse::<CommandLLen>),
+    ("lrange", parse::<CommandLRange>),
+    ("sadd", parse::<CommandSAdd>),
+    ("srem", parse::<CommandSRem>),
+    ("sismember", parse::<CommandSIsMember>),
+    ("smembers", parse::<CommandSMembers>),
+    ("scard", parse::<CommandSCard>),
+    ("sinter", parse::<CommandSInter>),
+    ("sunion", parse::<CommandSUnion>),
+    ("sdiff", parse::<CommandSDiff>),
+    ("echo", parse::<CommandEcho>),
+    ("ping", parse::<CommandPing>),
+    ("hello", parse::<CommandHello>),
+    ("select", parse::<CommandSelect>),
+    ("auth", parse::<CommandAuth>),
+    ("type", parse::<CommandType>),
+    ("keys", parse::<CommandKeys>),
+    ("dbsize", parse::<CommandDbSize>),
+    ("flushdb", parse::<CommandFlushDb>),
+    ("config", parse::<CommandConfig>),
+    ("info", parse::<CommandInfo>),
+    ("lolwut", parse::<CommandLolwut>),
+    ("debug", parse::<CommandDebug>),
+    ("memory", parse::<CommandMemory>),
+    ("save", parse::<CommandSave>),
+    ("command", parse::<CommandCommand>),
+    ("publish", parse::<CommandPublish>),
+    ("subscribe", parse::<CommandSubscribe>),
+    ("unsubscribe", parse::<CommandUnsubscribe>),
+];
+
+pub(crate) fn command_names() -> impl Iterator<Item = &'static str> {
+    COMMAND_TABLE.iter().map(|(name, _)| *name)
+}
+
+fn parse<T>(value: RespArray) -> Result<Command, CommandError>
+where
+    T: TryFrom<RespArray, Error = CommandError> + Into<Command>,
+{
+    Ok(T::try_from(value)?.into())
+}
+
 impl TryFrom<RespArray> for Command {
     type Error = CommandError;
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
         match value.first() {
-            Some(RespFrame::BulkString(ref command)) => match command.as_ref() {
-                b"get" => Ok(CommandGet::try_from(value)?.into()),
-                b"set" => Ok(CommandSet::try_from(value)?.into()),
-                b"hget" => Ok(CommandHGet::try_from(value)?.into()),
-                b"hset" => Ok(CommandHSet::try_from(value)?.into()),
-                b"hgetall" => Ok(CommandHGetAll::try_from(value)?.into()),
-                b"hmget" => Ok(CommandHMGet::try_from(value)?.into()),
-                b"echo" => Ok(CommandEcho::try_from(value)?.into()),
-                _ => Ok(CommandUnknown.into()),
-            },
-            _ => todo!(),
+            // command names are case insensitive, redis-cli sends them in uppercase
+            Some(RespFrame::BulkString(ref command)) => {
+                match COMMAND_TABLE
+                    .iter()
+                    .find(|(name, _)| name.as_bytes().eq_ignore_ascii_case(command))
+                {
+                    Some((_, parse)) => parse(value),
+                    None => Ok(CommandUnknown::new(&value).into()),
+                }
+            }
+            Some(frame) => Err(CommandError::InvalidCommand(format!(
+                "Invalid command: {:?}",
+                frame
+            ))),
+            None => Err(CommandError::InvalidCommand("Empty command".to_string())),
+        }
+    }
+}
+
+impl Command {
+    // commands that change the keyspace, these are what the append only file records
+    pub fn is_write(&self) -> bool {
+        matches!(
+            self,
+            Command::Set(_)
+                | Command::SetNx(_)
+                | Command::SetEx(_)
+                | Command::Append(_)
+                | Command::GetSet(_)
+                | Command::GetDel(_)
+                | Command::HSet(_)
+                | Command::HDel(_)
+                | Command::HIncrBy(_)
+                | Command::LPush(_)
+                | Command::RPush(_)
+                | Command::LPop(_)
+                | Command::RPop(_)
+                | Command::SAdd(_)
+                | Command::SRem(_)
+                | Command::FlushDb(_)
+        )
+    }
+
+    // EX, PX and the default ttl count from when the command ran, the append only file gets
+    // an absolute PXAT instead so a restart doesn't start the ttl over
+    fn aof_frame(&self, frame: RespArray, config: &ServerConfig) -> RespArray {
+        let rewritten = match self {
+            Command::Set(cmd) => cmd.aof_frame(config.default_ttl),
+            Command::SetEx(cmd) => cmd.aof_frame(),
+            Command::SetNx(cmd) => cmd.aof_frame(config.default_ttl),
+            Command::GetSet(cmd) => cmd.aof_frame(config.default_ttl),
+            _ => None,
+        };
+        rewritten.unwrap_or(frame)
+    }
+}
+
+// puts the original name back on a command sent under its new one, false if the name it
+// was sent under has been renamed away or disabled
+fn restore_original_name(value: &mut RespArray, config: &ServerConfig) -> bool {
+    if config.rename_commands.is_empty() {
+        return true;
+    }
+    let name = match value.first() {
+        Some(RespFrame::BulkString(name)) => String::from_utf8_lossy(name).to_lowercase(),
+        _ => return true,
+    };
+
+    match config.resolve_command(&name) {
+        Some(name) => {
+            value.0[0] = RespBulkString::from(name).into();
+            true
+        }
+        None => false,
+    }
+}
+
+// parses and runs one command against `backend`, the way a connection would, without
+// needing a socket. malformed commands come back as error frames
+pub fn dispatch(mut frame: RespArray, backend: &Backend) -> RespFrame {
+    // the append only file records commands under their original names, so replaying it
+    // doesn't depend on how they are renamed
+    let known = restore_original_name(&mut frame, &backend.config);
+    if let Some(err) = check_subscriber_mode(&frame, backend) {
+        return err.into();
+    }
+    let logged = backend.aof().map(|_| frame.clone());
+    let cmd = if known {
+        match Command::try_from(frame) {
+            Ok(cmd) => cmd,
+            Err(err) => return err.into(),
         }
+    } else {
+        CommandUnknown::new(&frame).into()
+    };
+    if let Some(err) = check_connection_state(&cmd, backend) {
+        return err.into();
+    }
+
+    let (Some(aof), Some(frame), true) = (backend.aof(), logged, cmd.is_write()) else {
+        return cmd.execute(backend);
+    };
+    let frame = cmd.aof_frame(frame, &backend.config);
+    // a write is applied and appended under one lock, so the file has writes in the order
+    // they changed the keyspace
+    let _order = aof.write_order();
+    let reply = cmd.execute(backend);
+    if !matches!(reply, RespFrame::Error(_)) {
+        aof.append(backend.session().db(), frame);
+    }
+    reply
+}
+
+// commands a RESP2 connection can still run while it has subscriptions
+const SUBSCRIBER_COMMANDS: &[&str] = &["subscribe", "unsubscribe", "ping"];
+
+// a RESP2 connection with subscriptions can't tell replies from messages, so like redis
+// only commands managing the subscriptions are allowed until it has none left
+fn check_subscriber_mode(frame: &RespArray, backend: &Backend) -> Option<RespSimpleError> {
+    let session = backend.session();
+    if session.protocol() >= 3 || session.subscription_count() == 0 {
+        return None;
+    }
+    let name = frame
+        .first()
+        .and_then(RespFrame::as_bytes)
+        .map(|name| String::from_utf8_lossy(&name).to_lowercase())
+        .unwrap_or_default();
+    if SUBSCRIBER_COMMANDS.contains(&name.as_str()) {
+        return None;
     }
+    Some(RespSimpleError::new(format!(
+        "ERR Can't execute '{}': only (P|S)SUBSCRIBE / (P|S)UNSUBSCRIBE / PING / QUIT / RESET are allowed in this context",
+        name
+    )))
+}
+
+// AUTH and HELLO are the only commands allowed before a connection is authenticated
+// and, in RESP3-only mode, switched to RESP3
+fn check_connection_state(cmd: &Command, backend: &Backend) -> Option<RespSimpleError> {
+    if matches!(cmd, Command::Auth(_) | Command::Hello(_)) {
+        return None;
+    }
+    let session = backend.session();
+    if backend.config.requirepass.is_some() && !session.is_authenticated() {
+        return Some(RespSimpleError::new("NOAUTH Authentication required."));
+    }
+    if backend.config.resp3_only && session.protocol() < 3 {
+        return Some(RespSimpleError::new("NOPROTO unsupported protocol version"));
+    }
+    None
 }
 
 pub fn validate_command(
@@ -111,6 +406,30 @@ pub fn validate_command(
     Ok(())
 }
 
+pub fn bulk_to_string(frame: RespFrame) -> Result<String, CommandError> {
+    Ok(String::from_utf8(bulk_to_bytes(frame)?)?)
+}
+
+// converts all remaining arguments, failing on the first one that isn't a bulk string
+pub fn args_as_strings(args: impl Iterator<Item = RespFrame>) -> Result<Vec<String>, CommandError> {
+    args.map(bulk_to_string).collect()
+}
+
+pub fn bulk_to_bytes(frame: RespFrame) -> Result<Vec<u8>, CommandError> {
+    match frame {
+        RespFrame::BulkString(value) => Ok(value.0.into()),
+        frame => Err(CommandError::InvalidCommandArguments(format!(
+            "Expected a bulk string argument, got {:?}",
+            frame
+        ))),
+    }
+}
+
+// the content of a value read by a string command, WRONGTYPE if the key holds anything else
+fn string_bytes(value: &RespFrame) -> Result<Cow<'_, [u8]>, RespFrame> {
+    value.as_bytes().ok_or_else(|| RESP_WRONGTYPE.clone())
+}
+
 pub fn extract_args(
     value: RespArray,
     command_length: usize,
@@ -118,15 +437,30 @@ pub fn extract_args(
     Ok(value.0.into_iter().skip(command_length).collect())
 }
 
+// builds a command frame out of its name and arguments, for tests
+#[cfg(test)]
+pub(crate) fn command(args: &[&str]) -> RespArray {
+    RespArray::new(
+        args.iter()
+            .map(|arg| RespBulkString::new(*arg).into())
+            .collect(),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
-        cmd::{map::CommandGet, validate_command},
-        RespArray, RespBulkString, RespFrame,
+        backend::Backend,
+        cmd::{
+            args_as_strings, command, dispatch, map::CommandGet, string_bytes, validate_command,
+            RESP_OK, RESP_WRONGTYPE,
+        },
+        RespArray, RespBulkString, RespDecodeError, RespFrame, RespInteger, RespSimpleError,
+        RespSimpleString,
     };
     use anyhow::Result;
 
-    use super::extract_args;
+    use super::{bulk_to_bytes, bulk_to_string, extract_args, CommandError};
 
     #[test]
     fn test_validate_command() {
@@ -166,4 +500,177 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_command_names_are_case_insensitive() {
+        let backend = Backend::new();
+
+        assert_eq!(
+            dispatch(command(&["PING"]), &backend),
+            RespSimpleString::new("PONG").into()
+        );
+        assert_eq!(
+            dispatch(command(&["SeT", "k", "v"]), &backend),
+            RESP_OK.clone()
+        );
+        assert_eq!(
+            dispatch(command(&["GET", "k"]), &backend),
+            RespBulkString::new("v").into()
+        );
+        assert_eq!(
+            dispatch(command(&["SUBSTR", "k", "0", "0"]), &backend),
+            RespBulkString::new("v").into()
+        );
+        assert!(!matches!(
+            dispatch(command(&["COMMAND", "DOCS"]), &backend),
+            RespFrame::Error(_)
+        ));
+    }
+
+    #[test]
+    fn test_bulk_to_string() {
+        let frame = RespBulkString::new(b"hello".to_vec()).into();
+        assert_eq!(bulk_to_string(frame).unwrap(), "hello");
+
+        let frame = RespBulkString::new(vec![0xff, 0xfe]).into();
+        assert!(matches!(
+            bulk_to_string(frame),
+            Err(CommandError::FromUtf8Error(_))
+        ));
+
+        let frame = RespFrame::Integer(crate::RespInteger::new(1));
+        assert!(matches!(
+            bulk_to_string(frame),
+            Err(CommandError::InvalidCommandArguments(_))
+        ));
+    }
+
+    #[test]
+    fn test_bulk_to_bytes() {
+        let frame = RespBulkString::new(vec![0xff, 0xfe]).into();
+        assert_eq!(bulk_to_bytes(frame).unwrap(), vec![0xff, 0xfe]);
+
+        let frame = RespFrame::Boolean(true);
+        assert!(matches!(
+            bulk_to_bytes(frame),
+            Err(CommandError::InvalidCommandArguments(_))
+        ));
+    }
+
+    #[test]
+    fn test_empty_command_try_from() {
+        let resp_array = RespArray::new(Vec::new());
+        let ret: Result<super::Command, _> = resp_array.try_into();
+        assert!(matches!(ret, Err(CommandError::InvalidCommand(_))));
+    }
+
+    #[test]
+    fn test_dispatch_renamed_commands() {
+        let mut config = crate::config::ServerConfig::default();
+        config
+            .rename_commands
+            .insert("flushdb".to_string(), "".to_string());
+        config
+            .rename_commands
+            .insert("set".to_string(), "put".to_string());
+        let backend = Backend::with_config(config);
+
+        // a disabled command is unknown
+        assert_eq!(
+            dispatch(command(&["flushdb"]), &backend),
+            RespSimpleError::new("ERR unknown command 'flushdb', with args beginning with: ")
+                .into()
+        );
+
+        // a renamed command only answers to its new name
+        assert_eq!(
+            dispatch(command(&["set", "key", "value"]), &backend),
+            RespSimpleError::new(
+                "ERR unknown command 'set', with args beginning with: 'key' 'value' "
+            )
+            .into()
+        );
+        assert_eq!(
+            dispatch(command(&["PUT", "key", "value"]), &backend),
+            RESP_OK.clone()
+        );
+
+        // commands left alone keep working
+        assert_eq!(
+            dispatch(command(&["get", "key"]), &backend),
+            RespBulkString::new("value").into()
+        );
+    }
+
+    #[test]
+    fn test_command_error_becomes_error_reply() {
+        let err = CommandError::InvalidCommandArguments("bad".to_string());
+        let frame: RespFrame = err.into();
+        assert_eq!(
+            frame,
+            RespSimpleError::new("ERR Invalid command arguments: bad").into()
+        );
+
+        assert!(RespDecodeError::NotComplete.is_incomplete());
+        assert!(!RespDecodeError::invalid_frame(0, "bad").is_incomplete());
+    }
+
+    #[test]
+    fn test_dispatch_without_socket() {
+        let backend = Backend::new();
+
+        let ret = dispatch(command(&["set", "key", "value"]), &backend);
+        assert_eq!(ret, RESP_OK.clone());
+        let ret = dispatch(command(&["get", "key"]), &backend);
+        assert_eq!(ret, RespBulkString::new("value").into());
+
+        let ret = dispatch(command(&["get"]), &backend);
+        assert!(matches!(ret, RespFrame::Error(_)));
+    }
+
+    #[test]
+    fn test_args_as_strings() -> Result<()> {
+        let args = vec![
+            RespBulkString::new("a").into(),
+            RespBulkString::new("b").into(),
+        ];
+        assert_eq!(args_as_strings(args.into_iter())?, vec!["a", "b"]);
+
+        let args: Vec<RespFrame> =
+            vec![RespBulkString::new("a").into(), RespInteger::new(1).into()];
+        assert!(matches!(
+            args_as_strings(args.into_iter()),
+            Err(CommandError::InvalidCommandArguments(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_commands_on_hash_key() -> Result<()> {
+        let backend = Backend::new();
+        backend.hset("map", "field", RespBulkString::new("value").into())?;
+
+        let wrongtype: RespFrame = RespSimpleError::new(
+            "WRONGTYPE Operation against a key holding the wrong kind of value",
+        )
+        .into();
+        assert_eq!(*RESP_WRONGTYPE, wrongtype);
+        for args in [
+            &["get", "map"][..],
+            &["strlen", "map"],
+            &["append", "map", "x"],
+            &["getrange", "map", "0", "-1"],
+            &["lpush", "map", "x"],
+            &["sadd", "map", "x"],
+        ] {
+            assert_eq!(dispatch(command(args), &backend), wrongtype, "{:?}", args);
+        }
+        assert_eq!(
+            string_bytes(&RespBulkString::new("abc").into()),
+            Ok("abc".as_bytes().into())
+        );
+
+        Ok(())
+    }
 }
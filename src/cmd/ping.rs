@@ -0,0 +1,85 @@
+use crate::{backend::Backend, RespArray, RespBulkString, RespFrame, RespSimpleString};
+
+use super::{bulk_to_bytes, extract_args, validate_command, CommandError, CommandExecutor};
+
+#[derive(Debug, PartialEq)]
+pub struct CommandPing {
+    message: Option<Vec<u8>>,
+}
+
+impl CommandExecutor for CommandPing {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        match self.message {
+            Some(message) => RespBulkString::new(message).into(),
+            None => RespSimpleString::new("PONG").into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandPing {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // ping [message]
+        let n_args = if value.len() == 2 { 1 } else { 0 };
+        validate_command(&value, &["ping"], n_args)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let message = match args.next() {
+            Some(message) => Some(bulk_to_bytes(message)?),
+            None => None,
+        };
+        Ok(CommandPing { message })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    use crate::{
+        backend::Backend,
+        cmd::{ping::CommandPing, CommandExecutor},
+        RespArray, RespBulkString, RespDecode, RespFrame, RespSimpleString,
+    };
+
+    #[test]
+    fn test_ping_command() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*1\r\n$4\r\nping\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let command = CommandPing::try_from(frame)?;
+        assert_eq!(command.message, None);
+
+        let backend = Backend::new();
+        let ret = command.execute(&backend);
+        assert_eq!(ret, RespFrame::SimpleString(RespSimpleString::new("PONG")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ping_command_with_message() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$4\r\nping\r\n$5\r\nhello\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let command = CommandPing::try_from(frame)?;
+
+        let backend = Backend::new();
+        let ret = command.execute(&backend);
+        assert_eq!(ret, RespBulkString::new(b"hello".to_vec()).into());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ping_command_too_many_args() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$4\r\nping\r\n$1\r\na\r\n$1\r\nb\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(CommandPing::try_from(frame).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,415 @@
+use crate::{backend::Backend, RespArray, RespBulkString, RespFrame, RespInteger, RespNull};
+
+use super::{
+    args_as_strings, bulk_to_bytes, bulk_to_string, extract_args, validate_command, CommandError,
+    CommandExecutor,
+};
+
+#[derive(Debug, PartialEq)]
+pub struct CommandPublish {
+    channel: String,
+    message: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandSubscribe {
+    channels: Vec<String>,
+}
+
+// without channels every subscription of the connection is dropped
+#[derive(Debug, PartialEq)]
+pub struct CommandUnsubscribe {
+    channels: Vec<String>,
+}
+
+impl CommandExecutor for CommandPublish {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let receivers = backend.publish(&self.channel, RespBulkString::new(self.message).into());
+        RespInteger::new(receivers as i64).into()
+    }
+}
+
+impl TryFrom<RespArray> for CommandPublish {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["publish"], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match (args.next(), args.next()) {
+            (Some(channel), Some(message)) => Ok(CommandPublish {
+                channel: bulk_to_string(channel)?,
+                message: bulk_to_bytes(message)?,
+            }),
+            _ => Err(CommandError::InvalidCommandArguments(
+                "Invalid channel or message".to_string(),
+            )),
+        }
+    }
+}
+
+// redis replies once per channel, the first confirmation is the command's reply and
+// the rest are pushed right behind it
+impl CommandExecutor for CommandSubscribe {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let session = backend.session();
+        let mut new_channels = Vec::new();
+        let mut replies = Vec::with_capacity(self.channels.len());
+        for channel in self.channels {
+            let subscribed = session.is_subscribed(&channel)
+                || new_channels.iter().any(|(name, _)| name == &channel);
+            if !subscribed {
+                let rx = backend.subscribe(&channel);
+                new_channels.push((channel.clone(), rx));
+            }
+            let count = session.subscription_count() + new_channels.len();
+            replies.push(session.push_frame(vec![
+                RespBulkString::new("subscribe").into(),
+                RespBulkString::from(channel).into(),
+                RespInteger::new(count as i64).into(),
+            ]));
+        }
+
+        let mut replies = replies.into_iter();
+        let reply = replies.next().expect("SUBSCRIBE has at least one channel");
+        for frame in replies {
+            session.push(frame);
+        }
+        // start forwarding only after the confirmations are queued so messages follow them
+        for (channel, rx) in new_channels {
+            session.forward(channel, rx);
+        }
+        reply
+    }
+}
+
+impl TryFrom<RespArray> for CommandSubscribe {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let n_args = value.len() - 1;
+        validate_command(&value, &["subscribe"], n_args)?;
+        let channels = args_as_strings(extract_args(value, 1)?.into_iter())?;
+        if channels.is_empty() {
+            return Err(CommandError::InvalidCommandArguments(
+                "SUBSCRIBE requires at least one channel".to_string(),
+            ));
+        }
+
+        Ok(CommandSubscribe { channels })
+    }
+}
+
+// like SUBSCRIBE, one confirmation per channel with the first as the reply
+impl CommandExecutor for CommandUnsubscribe {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let session = backend.session();
+        let channels = if self.channels.is_empty() {
+            session.subscribed_channels()
+        } else {
+            self.channels
+        };
+        let mut replies = Vec::with_capacity(channels.len());
+        for channel in channels {
+            session.unsubscribe(&channel);
+            replies.push(session.push_frame(vec![
+                RespBulkString::new("unsubscribe").into(),
+                RespBulkString::from(channel).into(),
+                RespInteger::new(session.subscription_count() as i64).into(),
+            ]));
+        }
+
+        let mut replies = replies.into_iter();
+        // nothing to unsubscribe from still gets one confirmation
+        let reply = replies.next().unwrap_or_else(|| {
+            session.push_frame(vec![
+                RespBulkString::new("unsubscribe").into(),
+                RespFrame::Null(RespNull),
+                RespInteger::new(0).into(),
+            ])
+        });
+        for frame in replies {
+            session.push(frame);
+        }
+        reply
+    }
+}
+
+impl TryFrom<RespArray> for CommandUnsubscribe {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let n_args = value.len() - 1;
+        validate_command(&value, &["unsubscribe"], n_args)?;
+        let channels = args_as_strings(extract_args(value, 1)?.into_iter())?;
+        Ok(CommandUnsubscribe { channels })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    use crate::{
+        backend::Backend,
+        cmd::{
+            command, dispatch,
+            pubsub::{CommandPublish, CommandSubscribe, CommandUnsubscribe},
+            CommandExecutor, RESP_OK,
+        },
+        RespArray, RespBulkString, RespDecode, RespFrame, RespInteger, RespNull, RespPush,
+    };
+
+    fn subscribed(channel: &str, count: i64) -> RespFrame {
+        RespArray::new(vec![
+            RespBulkString::new("subscribe").into(),
+            RespBulkString::new(channel).into(),
+            RespInteger::new(count).into(),
+        ])
+        .into()
+    }
+
+    fn unsubscribed(channel: Option<&str>, count: i64) -> RespFrame {
+        let channel = match channel {
+            Some(channel) => RespBulkString::new(channel).into(),
+            None => RespFrame::Null(RespNull),
+        };
+        RespArray::new(vec![
+            RespBulkString::new("unsubscribe").into(),
+            channel,
+            RespInteger::new(count).into(),
+        ])
+        .into()
+    }
+
+    #[test]
+    fn test_publish_without_subscribers() -> Result<()> {
+        let backend = Backend::new();
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$7\r\npublish\r\n$4\r\nnews\r\n$5\r\nhello\r\n");
+        let command: CommandPublish = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command.execute(&backend), RespInteger::new(0).into());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_and_publish() -> Result<()> {
+        let backend = Backend::new();
+        let subscriber = backend.new_session();
+        let mut pushes = subscriber.session().take_pushes().unwrap();
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n$6\r\nsports\r\n");
+        let command: CommandSubscribe = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command.execute(&subscriber), subscribed("news", 1));
+        assert_eq!(pushes.recv().await, Some(subscribed("sports", 2)));
+
+        // subscribing twice to a channel doesn't add a subscription
+        let command = CommandSubscribe {
+            channels: vec!["news".to_string()],
+        };
+        assert_eq!(command.execute(&subscriber), subscribed("news", 2));
+
+        let command = CommandPublish {
+            channel: "news".to_string(),
+            message: b"hello".to_vec(),
+        };
+        assert_eq!(command.execute(&backend), RespInteger::new(1).into());
+        assert_eq!(
+            pushes.recv().await,
+            Some(
+                RespArray::new(vec![
+                    RespBulkString::new("message").into(),
+                    RespBulkString::new("news").into(),
+                    RespBulkString::new("hello").into(),
+                ])
+                .into()
+            )
+        );
+
+        drop(subscriber);
+        let command = CommandPublish {
+            channel: "news".to_string(),
+            message: b"hello".to_vec(),
+        };
+        // the forwarding task is aborted with the session, give it a moment to go away
+        tokio::task::yield_now().await;
+        assert_eq!(command.execute(&backend), RespInteger::new(0).into());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resp3_subscriber_gets_pushes() -> Result<()> {
+        let backend = Backend::new();
+        let subscriber = backend.new_session();
+        subscriber.session().set_protocol(3);
+        let mut pushes = subscriber.session().take_pushes().unwrap();
+
+        let command = CommandSubscribe {
+            channels: vec!["news".to_string()],
+        };
+        assert_eq!(
+            command.execute(&subscriber),
+            RespPush::new(vec![
+                RespBulkString::new("subscribe").into(),
+                RespBulkString::new("news").into(),
+                RespInteger::new(1).into(),
+            ])
+            .into()
+        );
+
+        backend.publish("news", RespBulkString::new("hello").into());
+        assert_eq!(
+            pushes.recv().await,
+            Some(
+                RespPush::new(vec![
+                    RespBulkString::new("message").into(),
+                    RespBulkString::new("news").into(),
+                    RespBulkString::new("hello").into(),
+                ])
+                .into()
+            )
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unsubscribe() -> Result<()> {
+        let backend = Backend::new();
+        let subscriber = backend.new_session();
+        let mut pushes = subscriber.session().take_pushes().unwrap();
+
+        let command = CommandSubscribe {
+            channels: vec!["news".to_string(), "sports".to_string()],
+        };
+        command.execute(&subscriber);
+        assert_eq!(pushes.recv().await, Some(subscribed("sports", 2)));
+
+        let mut buf = BytesMut::from(&b"*2\r\n$11\r\nunsubscribe\r\n$4\r\nnews\r\n"[..]);
+        let command: CommandUnsubscribe = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command.execute(&subscriber), unsubscribed(Some("news"), 1));
+        tokio::task::yield_now().await;
+        assert_eq!(backend.publish("news", RespBulkString::new("hi").into()), 0);
+
+        // without channels every remaining subscription goes
+        let command = CommandUnsubscribe { channels: vec![] };
+        assert_eq!(
+            command.execute(&subscriber),
+            unsubscribed(Some("sports"), 0)
+        );
+        let command = CommandUnsubscribe { channels: vec![] };
+        assert_eq!(command.execute(&subscriber), unsubscribed(None, 0));
+        assert!(pushes.try_recv().is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resp2_subscriber_mode_only_allows_pubsub_commands() {
+        let backend = Backend::new();
+        let conn = backend.new_session();
+
+        dispatch(command(&["subscribe", "news"]), &conn);
+        let ret = dispatch(command(&["GET", "k"]), &conn);
+        assert!(
+            matches!(&ret, RespFrame::Error(e) if e.starts_with("ERR Can't execute 'get': only")),
+            "{:?}",
+            ret
+        );
+        assert!(!matches!(
+            dispatch(command(&["ping"]), &conn),
+            RespFrame::Error(_)
+        ));
+
+        dispatch(command(&["unsubscribe"]), &conn);
+        assert_eq!(
+            dispatch(command(&["get", "k"]), &conn),
+            RespFrame::Null(RespNull)
+        );
+
+        // RESP3 can tell pushes from replies, so nothing is restricted there
+        conn.session().set_protocol(3);
+        dispatch(command(&["subscribe", "news"]), &conn);
+        assert_eq!(
+            dispatch(command(&["get", "k"]), &conn),
+            RespFrame::Null(RespNull)
+        );
+    }
+
+    fn message(channel: &str, payload: &str) -> RespFrame {
+        RespArray::new(vec![
+            RespBulkString::new("message").into(),
+            RespBulkString::new(channel).into(),
+            RespBulkString::new(payload).into(),
+        ])
+        .into()
+    }
+
+    #[tokio::test]
+    async fn test_keyevent_expired_notification() -> Result<()> {
+        let backend = Backend::new();
+        let subscriber = backend.new_session();
+        let mut pushes = subscriber.session().take_pushes().unwrap();
+        dispatch(
+            command(&["subscribe", "__keyevent@0__:expired"]),
+            &subscriber,
+        );
+
+        // nothing is published until notify-keyspace-events asks for it
+        dispatch(command(&["set", "k", "v", "px", "1"]), &backend);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(dispatch(command(&["get", "k"]), &backend), RespNull.into());
+
+        let ret = dispatch(
+            command(&["config", "set", "notify-keyspace-events", "Ex"]),
+            &backend,
+        );
+        assert_eq!(ret, RESP_OK.clone());
+        dispatch(command(&["set", "k", "v", "px", "1"]), &backend);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(dispatch(command(&["get", "k"]), &backend), RespNull.into());
+
+        let event = tokio::time::timeout(Duration::from_secs(1), pushes.recv()).await?;
+        assert_eq!(event, Some(message("__keyevent@0__:expired", "k")));
+        assert!(pushes.try_recv().is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_keyspace_notifications() -> Result<()> {
+        let backend = Backend::new();
+        let subscriber = backend.new_session();
+        let mut pushes = subscriber.session().take_pushes().unwrap();
+        dispatch(command(&["subscribe", "__keyspace@1__:list"]), &subscriber);
+
+        let ret = dispatch(
+            command(&["config", "set", "notify-keyspace-events", "Kl"]),
+            &backend,
+        );
+        assert_eq!(ret, RESP_OK.clone());
+        let conn = backend.new_session();
+        dispatch(command(&["select", "1"]), &conn);
+        dispatch(command(&["rpush", "list", "a"]), &conn);
+        // string events aren't asked for
+        dispatch(command(&["set", "list2", "a"]), &conn);
+        dispatch(command(&["lpop", "list"]), &conn);
+
+        let event = tokio::time::timeout(Duration::from_secs(1), pushes.recv()).await?;
+        assert_eq!(event, Some(message("__keyspace@1__:list", "rpush")));
+        let event = tokio::time::timeout(Duration::from_secs(1), pushes.recv()).await?;
+        assert_eq!(event, Some(message("__keyspace@1__:list", "lpop")));
+
+        let ret = dispatch(
+            command(&["config", "set", "notify-keyspace-events", "Kq"]),
+            &backend,
+        );
+        assert!(matches!(ret, RespFrame::Error(_)));
+
+        Ok(())
+    }
+}
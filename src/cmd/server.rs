@@ -0,0 +1,396 @@
+use crate::{
+    backend::Backend, RespArray, RespBulkString, RespFrame, RespInteger, RespMap, RespNull,
+    RespSimpleError, RespSimpleString,
+};
+
+use super::{
+    bulk_to_string, command_names, extract_args, validate_command, CommandError, CommandExecutor,
+    RESP_OK,
+};
+
+#[derive(Debug, PartialEq)]
+pub struct CommandLolwut;
+
+#[derive(Debug, PartialEq)]
+pub struct CommandSave;
+
+// just enough of COMMAND for redis-cli, which asks for COUNT and DOCS on connect
+#[derive(Debug, PartialEq)]
+pub enum CommandCommand {
+    Count,
+    Docs,
+    List,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CommandDebug {
+    ChangeReplId,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CommandMemory {
+    Usage(String),
+    Doctor,
+    Stats,
+}
+
+impl CommandExecutor for CommandLolwut {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        // redis draws some art first, only the version line is kept here
+        RespBulkString::from(format!("simple-redis ver. {}\n", env!("CARGO_PKG_VERSION"))).into()
+    }
+}
+
+impl TryFrom<RespArray> for CommandLolwut {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // lolwut [version <n>] is accepted, the art version doesn't change the reply
+        if value.len() != 1 && value.len() != 3 {
+            validate_command(&value, &["lolwut"], 0)?;
+        }
+        Ok(CommandLolwut)
+    }
+}
+
+// dumps the keyspace to `dbfilename` before replying, every other client waits on it
+impl CommandExecutor for CommandSave {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.save_to(&backend.config.dbfilename) {
+            Ok(()) => RESP_OK.clone(),
+            Err(e) => RespSimpleError::new(format!("ERR {}", e)).into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandSave {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["save"], 0)?;
+        Ok(CommandSave)
+    }
+}
+
+impl CommandExecutor for CommandCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match self {
+            CommandCommand::Count => RespInteger::new(command_names().count() as i64).into(),
+            // no docs to give, clients fall back to their own
+            CommandCommand::Docs => {
+                if backend.session().protocol() == 2 {
+                    RespArray::new(Vec::new()).into()
+                } else {
+                    RespMap::new().into()
+                }
+            }
+            CommandCommand::List => RespArray::new(
+                command_names()
+                    .map(|name| RespBulkString::new(name).into())
+                    .collect(),
+            )
+            .into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandCommand {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let subcommand = match value.get(1) {
+            Some(RespFrame::BulkString(subcommand)) => subcommand.to_ascii_lowercase(),
+            _ => {
+                return Err(CommandError::InvalidCommandArguments(
+                    "COMMAND command requires a subcommand".to_string(),
+                ))
+            }
+        };
+
+        match subcommand.as_slice() {
+            b"count" => {
+                validate_command(&value, &["command", "count"], 0)?;
+                Ok(CommandCommand::Count)
+            }
+            // command docs [name ...], every name gets the same empty reply
+            b"docs" => Ok(CommandCommand::Docs),
+            b"list" => {
+                validate_command(&value, &["command", "list"], 0)?;
+                Ok(CommandCommand::List)
+            }
+            _ => Err(CommandError::InvalidCommandArguments(format!(
+                "Unknown COMMAND subcommand: {}",
+                String::from_utf8_lossy(&subcommand)
+            ))),
+        }
+    }
+}
+
+// there is no replication, so changing the replication id has nothing to do
+impl CommandExecutor for CommandDebug {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        match self {
+            CommandDebug::ChangeReplId => RESP_OK.clone(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandDebug {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let subcommand = match value.get(1) {
+            Some(RespFrame::BulkString(subcommand)) => subcommand.to_ascii_lowercase(),
+            _ => {
+                return Err(CommandError::InvalidCommandArguments(
+                    "DEBUG command requires a subcommand".to_string(),
+                ))
+            }
+        };
+
+        match subcommand.as_slice() {
+            b"change-repl-id" => {
+                validate_command(&value, &["debug", "change-repl-id"], 0)?;
+                Ok(CommandDebug::ChangeReplId)
+            }
+            _ => Err(CommandError::InvalidCommandArguments(format!(
+                "Unknown DEBUG subcommand: {}",
+                String::from_utf8_lossy(&subcommand)
+            ))),
+        }
+    }
+}
+
+impl CommandExecutor for CommandMemory {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match self {
+            CommandMemory::Usage(key) => match backend.memory_usage(&key) {
+                Some(bytes) => RespInteger::new(bytes as i64).into(),
+                None => RespFrame::Null(RespNull),
+            },
+            CommandMemory::Doctor => RespBulkString::new("Sam, I have no memory problems").into(),
+            CommandMemory::Stats => {
+                let keys: usize = backend.dbs.iter().map(|db| db.data.len()).sum();
+                let fields: [(&str, RespFrame); 2] = [
+                    ("keys.count", RespInteger::new(keys as i64).into()),
+                    (
+                        "dataset.bytes",
+                        RespInteger::new(backend.dataset_bytes() as i64).into(),
+                    ),
+                ];
+
+                // same layout as HELLO, a flat array unless the connection speaks RESP3
+                if backend.session().protocol() == 2 {
+                    let mut reply = Vec::with_capacity(fields.len() * 2);
+                    for (name, value) in fields {
+                        reply.push(RespBulkString::new(name).into());
+                        reply.push(value);
+                    }
+                    RespArray::new(reply).into()
+                } else {
+                    let mut reply = RespMap::new();
+                    for (name, value) in fields {
+                        reply.insert(RespSimpleString::new(name), value);
+                    }
+                    reply.into()
+                }
+            }
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandMemory {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let subcommand = match value.get(1) {
+            Some(RespFrame::BulkString(subcommand)) => subcommand.to_ascii_lowercase(),
+            _ => {
+                return Err(CommandError::InvalidCommandArguments(
+                    "MEMORY command requires a subcommand".to_string(),
+                ))
+            }
+        };
+
+        match subcommand.as_slice() {
+            b"usage" => {
+                // memory usage key [samples count], every value is measured exactly anyway
+                let n_args = if value.len() == 5 { 3 } else { 1 };
+                validate_command(&value, &["memory", "usage"], n_args)?;
+                let mut args = extract_args(value, 2)?.into_iter();
+                match args.next() {
+                    Some(key) => Ok(CommandMemory::Usage(bulk_to_string(key)?)),
+                    _ => Err(CommandError::InvalidCommandArguments(
+                        "Invalid key".to_string(),
+                    )),
+                }
+            }
+            b"doctor" => {
+                validate_command(&value, &["memory", "doctor"], 0)?;
+                Ok(CommandMemory::Doctor)
+            }
+            b"stats" => {
+                validate_command(&value, &["memory", "stats"], 0)?;
+                Ok(CommandMemory::Stats)
+            }
+            _ => Err(CommandError::InvalidCommandArguments(format!(
+                "Unknown MEMORY subcommand: {}",
+                String::from_utf8_lossy(&subcommand)
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    use crate::{
+        backend::Backend,
+        cmd::{
+            command_names,
+            server::{CommandCommand, CommandDebug, CommandLolwut, CommandMemory, CommandSave},
+            CommandExecutor, RESP_OK,
+        },
+        RespArray, RespBulkString, RespDecode, RespFrame, RespInteger, RespMap, RespNull,
+    };
+
+    #[test]
+    fn test_lolwut_command() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*1\r\n$6\r\nlolwut\r\n");
+        let command: CommandLolwut = RespArray::decode(&mut buf)?.try_into()?;
+
+        let RespFrame::BulkString(reply) = command.execute(&Backend::new()) else {
+            panic!("LOLWUT should reply with a bulk string");
+        };
+        let reply = String::from_utf8_lossy(&reply);
+        assert!(reply.contains(env!("CARGO_PKG_VERSION")));
+
+        buf.extend_from_slice(b"*3\r\n$6\r\nlolwut\r\n$7\r\nversion\r\n$1\r\n5\r\n");
+        let ret: Result<CommandLolwut, _> = RespArray::decode(&mut buf)?.try_into();
+        assert!(ret.is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_command() -> Result<()> {
+        let dbfilename =
+            std::env::temp_dir().join(format!("simple-redis-{}-save.rdb", std::process::id()));
+        let backend = Backend::with_config(crate::config::ServerConfig {
+            dbfilename: dbfilename.clone(),
+            ..Default::default()
+        });
+        backend.set("hello", RespBulkString::new("world").into());
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*1\r\n$4\r\nSAVE\r\n");
+        let command: CommandSave = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command.execute(&backend), RESP_OK.clone());
+
+        let loaded = Backend::new();
+        assert_eq!(loaded.load_from(&dbfilename)?, 1);
+        std::fs::remove_file(&dbfilename)?;
+        assert_eq!(
+            loaded.get("hello")?,
+            Some(RespBulkString::new("world").into())
+        );
+
+        // a directory that doesn't exist fails the command, not the server
+        let backend = Backend::with_config(crate::config::ServerConfig {
+            dbfilename: dbfilename.join("missing").join("dump.rdb"),
+            ..Default::default()
+        });
+        assert!(matches!(CommandSave.execute(&backend), RespFrame::Error(_)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_count() -> Result<()> {
+        let backend = Backend::new();
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$7\r\ncommand\r\n$5\r\nCOUNT\r\n");
+        let command: CommandCommand = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command, CommandCommand::Count);
+
+        let count = command_names().count() as i64;
+        assert!(count > 30);
+        assert_eq!(command.execute(&backend), RespInteger::new(count).into());
+
+        // the list is what dispatch knows, COMMAND included
+        let RespFrame::Array(names) = CommandCommand::List.execute(&backend) else {
+            panic!("COMMAND LIST should reply with an array");
+        };
+        assert_eq!(names.len() as i64, count);
+        assert!(names.contains(&RespBulkString::new("command").into()));
+
+        buf.extend_from_slice(b"*3\r\n$7\r\ncommand\r\n$4\r\ndocs\r\n$3\r\nget\r\n");
+        let command: CommandCommand = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command, CommandCommand::Docs);
+        backend.session().set_protocol(3);
+        assert_eq!(command.execute(&backend), RespMap::new().into());
+
+        buf.extend_from_slice(b"*2\r\n$7\r\ncommand\r\n$7\r\ngetkeys\r\n");
+        let ret: Result<CommandCommand, _> = RespArray::decode(&mut buf)?.try_into();
+        assert!(ret.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_debug_change_repl_id() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$5\r\nDEBUG\r\n$14\r\nCHANGE-REPL-ID\r\n");
+        let command: CommandDebug = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command, CommandDebug::ChangeReplId);
+        assert_eq!(command.execute(&Backend::new()), RESP_OK.clone());
+
+        buf.extend_from_slice(b"*2\r\n$5\r\ndebug\r\n$8\r\nsegfault\r\n");
+        let ret: Result<CommandDebug, _> = RespArray::decode(&mut buf)?.try_into();
+        assert!(ret.is_err());
+
+        Ok(())
+    }
+
+    fn usage(backend: &Backend, key: &str) -> RespFrame {
+        CommandMemory::Usage(key.to_string()).execute(backend)
+    }
+
+    #[test]
+    fn test_memory_usage_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("small", RespBulkString::new("x").into());
+        backend.set("large", RespBulkString::new(vec![b'x'; 10_001]).into());
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$6\r\nMEMORY\r\n$5\r\nUSAGE\r\n$5\r\nsmall\r\n");
+        let command: CommandMemory = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command, CommandMemory::Usage("small".to_string()));
+
+        let RespFrame::Integer(small) = command.execute(&backend) else {
+            panic!("MEMORY USAGE should reply with an integer");
+        };
+        let RespFrame::Integer(large) = usage(&backend, "large") else {
+            panic!("MEMORY USAGE should reply with an integer");
+        };
+        assert!(*small > 0);
+        assert_eq!(*large - *small, 10_000);
+
+        assert_eq!(usage(&backend, "missing"), RespFrame::Null(RespNull));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_stats_and_doctor() {
+        let backend = Backend::new().new_session();
+        backend.set("key", RespBulkString::new("value").into());
+
+        let RespFrame::Array(stats) = CommandMemory::Stats.execute(&backend) else {
+            panic!("MEMORY STATS should reply with an array on RESP2");
+        };
+        assert_eq!(stats[0], RespBulkString::new("keys.count").into());
+        assert_eq!(stats[1], RespInteger::new(1).into());
+
+        let ret = CommandMemory::Doctor.execute(&backend);
+        assert!(matches!(ret, RespFrame::BulkString(_)));
+    }
+}
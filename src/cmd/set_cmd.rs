@@ -0,0 +1,520 @@
+use crate::{
+    backend::{Backend, BackendError},
+    RespArray, RespBulkString, RespFrame, RespInteger, RespSet,
+};
+
+use super::{
+    args_as_strings, bulk_to_string, extract_args, validate_command, CommandError, CommandExecutor,
+};
+
+#[derive(Debug, PartialEq)]
+pub struct CommandSAdd {
+    key: String,
+    members: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandSRem {
+    key: String,
+    members: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandSIsMember {
+    key: String,
+    member: String,
+}
+
+// SMEMBERS replies in the set's iteration order, which is unspecified
+#[derive(Debug, PartialEq)]
+pub struct CommandSMembers {
+    key: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandSCard {
+    key: String,
+}
+
+// SINTER, SUNION and SDIFF reply in no particular order either
+#[derive(Debug, PartialEq)]
+pub struct CommandSInter {
+    keys: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandSUnion {
+    keys: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandSDiff {
+    keys: Vec<String>,
+}
+
+// SADD and SREM take the same arguments: key member [member ...]
+fn parse_members(
+    value: RespArray,
+    name: &'static str,
+) -> Result<(String, Vec<String>), CommandError> {
+    let n_args = value.len() - 1;
+    validate_command(&value, &[name], n_args)?;
+    let mut args = extract_args(value, 1)?.into_iter();
+
+    match args.next() {
+        Some(key) => {
+            let members = args_as_strings(args)?;
+            if members.is_empty() {
+                return Err(CommandError::InvalidCommandArguments(format!(
+                    "Invalid {} member",
+                    name
+                )));
+            }
+            Ok((bulk_to_string(key)?, members))
+        }
+        err => Err(CommandError::InvalidCommandArguments(format!(
+            "Invalid key or member: {:?}",
+            err
+        ))),
+    }
+}
+
+// SMEMBERS and SCARD only take a key
+fn parse_key(value: RespArray, name: &'static str) -> Result<String, CommandError> {
+    validate_command(&value, &[name], 1)?;
+    let mut args = extract_args(value, 1)?.into_iter();
+
+    match args.next() {
+        Some(key) => bulk_to_string(key),
+        _ => Err(CommandError::InvalidCommandArguments(
+            "Invalid key".to_string(),
+        )),
+    }
+}
+
+// SINTER, SUNION and SDIFF take the same arguments: key [key ...]
+fn parse_keys(value: RespArray, name: &'static str) -> Result<Vec<String>, CommandError> {
+    let n_args = value.len() - 1;
+    validate_command(&value, &[name], n_args)?;
+    let keys = args_as_strings(extract_args(value, 1)?.into_iter())?;
+    if keys.is_empty() {
+        return Err(CommandError::InvalidCommandArguments(format!(
+            "{} command must have at least one key",
+            name
+        )));
+    }
+    Ok(keys)
+}
+
+// RESP3 connections get a set, RESP2 ones an array
+fn members_reply(
+    backend: &Backend,
+    members: Result<impl IntoIterator<Item = String>, BackendError>,
+) -> RespFrame {
+    let members: Vec<RespFrame> = match members {
+        Ok(members) => members
+            .into_iter()
+            .map(|member| RespBulkString::from(member).into())
+            .collect(),
+        Err(e) => return e.into(),
+    };
+    if backend.session().protocol() >= 3 {
+        RespSet::from_unique(members).into()
+    } else {
+        RespArray::new(members).into()
+    }
+}
+
+impl TryFrom<RespArray> for CommandSAdd {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, members) = parse_members(value, "sadd")?;
+        Ok(CommandSAdd { key, members })
+    }
+}
+
+impl CommandExecutor for CommandSAdd {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.sadd(&self.key, self.members) {
+            Ok(added) => RespInteger::new(added as i64).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandSRem {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, members) = parse_members(value, "srem")?;
+        Ok(CommandSRem { key, members })
+    }
+}
+
+impl CommandExecutor for CommandSRem {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.srem(&self.key, &self.members) {
+            Ok(removed) => RespInteger::new(removed as i64).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandSIsMember {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["sismember"], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match (args.next(), args.next()) {
+            (Some(key), Some(member)) => Ok(CommandSIsMember {
+                key: bulk_to_string(key)?,
+                member: bulk_to_string(member)?,
+            }),
+            _ => Err(CommandError::InvalidCommandArguments(
+                "Invalid key or member".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for CommandSIsMember {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.sismember(&self.key, &self.member) {
+            Ok(exists) => RespInteger::new(exists as i64).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandSMembers {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(CommandSMembers {
+            key: parse_key(value, "smembers")?,
+        })
+    }
+}
+
+impl CommandExecutor for CommandSMembers {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        members_reply(backend, backend.smembers(&self.key))
+    }
+}
+
+impl TryFrom<RespArray> for CommandSCard {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(CommandSCard {
+            key: parse_key(value, "scard")?,
+        })
+    }
+}
+
+impl CommandExecutor for CommandSCard {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.scard(&self.key) {
+            Ok(len) => RespInteger::new(len as i64).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for CommandSInter {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(CommandSInter {
+            keys: parse_keys(value, "sinter")?,
+        })
+    }
+}
+
+impl CommandExecutor for CommandSInter {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        members_reply(backend, backend.sinter(&self.keys))
+    }
+}
+
+impl TryFrom<RespArray> for CommandSUnion {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(CommandSUnion {
+            keys: parse_keys(value, "sunion")?,
+        })
+    }
+}
+
+impl CommandExecutor for CommandSUnion {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        members_reply(backend, backend.sunion(&self.keys))
+    }
+}
+
+impl TryFrom<RespArray> for CommandSDiff {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(CommandSDiff {
+            keys: parse_keys(value, "sdiff")?,
+        })
+    }
+}
+
+impl CommandExecutor for CommandSDiff {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        members_reply(backend, backend.sdiff(&self.keys))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    use crate::{
+        backend::Backend,
+        cmd::{
+            set_cmd::{
+                CommandSAdd, CommandSCard, CommandSDiff, CommandSInter, CommandSIsMember,
+                CommandSMembers, CommandSRem, CommandSUnion,
+            },
+            CommandExecutor, RESP_WRONGTYPE,
+        },
+        RespArray, RespBulkString, RespDecode, RespEncode, RespFrame, RespInteger,
+    };
+
+    fn members(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    fn sismember(backend: &Backend, member: &str) -> RespFrame {
+        CommandSIsMember {
+            key: "set".to_string(),
+            member: member.to_string(),
+        }
+        .execute(backend)
+    }
+
+    #[test]
+    fn test_sadd_command_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$4\r\nSADD\r\n$3\r\nset\r\n$1\r\na\r\n$1\r\nb\r\n");
+        let command: CommandSAdd = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command.key, "set");
+        assert_eq!(command.members, members(&["a", "b"]));
+
+        buf.extend_from_slice(b"*2\r\n$4\r\nsrem\r\n$3\r\nset\r\n");
+        let ret: Result<CommandSRem, _> = RespArray::decode(&mut buf)?.try_into();
+        assert!(ret.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sadd_counts_new_members() {
+        let backend = Backend::new();
+
+        let command = CommandSAdd {
+            key: "set".to_string(),
+            members: members(&["a", "b", "a"]),
+        };
+        assert_eq!(command.execute(&backend), RespInteger::new(2).into());
+
+        let command = CommandSAdd {
+            key: "set".to_string(),
+            members: members(&["b", "c"]),
+        };
+        assert_eq!(command.execute(&backend), RespInteger::new(1).into());
+
+        let command = CommandSCard {
+            key: "set".to_string(),
+        };
+        assert_eq!(command.execute(&backend), RespInteger::new(3).into());
+
+        let command = CommandSMembers {
+            key: "set".to_string(),
+        };
+        let RespFrame::Array(mut reply) = command.execute(&backend) else {
+            panic!("SMEMBERS should reply with an array");
+        };
+        reply.0.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(
+            reply.0,
+            vec![
+                RespBulkString::new("a").into(),
+                RespBulkString::new("b").into(),
+                RespBulkString::new("c").into(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_membership_checks() -> Result<()> {
+        let backend = Backend::new();
+        backend.sadd("set", members(&["a", "b"]))?;
+
+        assert_eq!(sismember(&backend, "a"), RespInteger::new(1).into());
+        assert_eq!(sismember(&backend, "c"), RespInteger::new(0).into());
+
+        let command = CommandSRem {
+            key: "set".to_string(),
+            members: members(&["a", "c"]),
+        };
+        assert_eq!(command.execute(&backend), RespInteger::new(1).into());
+        assert_eq!(sismember(&backend, "a"), RespInteger::new(0).into());
+
+        // removing the last member drops the key
+        backend.srem("set", &members(&["b"]))?;
+        assert_eq!(backend.key_type("set"), "none");
+        assert_eq!(sismember(&backend, "b"), RespInteger::new(0).into());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_commands_on_wrong_type() {
+        let backend = Backend::new();
+        backend.set("set", RespBulkString::new("value").into());
+
+        let command = CommandSAdd {
+            key: "set".to_string(),
+            members: members(&["a"]),
+        };
+        assert_eq!(command.execute(&backend), RESP_WRONGTYPE.clone());
+        assert_eq!(sismember(&backend, "a"), RESP_WRONGTYPE.clone());
+    }
+
+    fn sorted(reply: RespFrame) -> Vec<RespFrame> {
+        let RespFrame::Array(mut reply) = reply else {
+            panic!("expected an array, got {:?}", reply);
+        };
+        reply.0.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        reply.0
+    }
+
+    fn bulks(values: &[&str]) -> Vec<RespFrame> {
+        values
+            .iter()
+            .map(|value| RespBulkString::new(*value).into())
+            .collect()
+    }
+
+    #[test]
+    fn test_set_algebra_command_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$6\r\nsinter\r\n$1\r\na\r\n$1\r\nb\r\n");
+        let command: CommandSInter = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(command.keys, members(&["a", "b"]));
+
+        buf.extend_from_slice(b"*1\r\n$5\r\nsdiff\r\n");
+        let ret: Result<CommandSDiff, _> = RespArray::decode(&mut buf)?.try_into();
+        assert!(ret.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_algebra_overlapping_sets() -> Result<()> {
+        let backend = Backend::new();
+        backend.sadd("s1", members(&["a", "b", "c"]))?;
+        backend.sadd("s2", members(&["b", "c", "d"]))?;
+        backend.sadd("s3", members(&["c", "e"]))?;
+
+        let keys = members(&["s1", "s2"]);
+        let command = CommandSInter { keys: keys.clone() };
+        assert_eq!(sorted(command.execute(&backend)), bulks(&["b", "c"]));
+        let command = CommandSUnion { keys: keys.clone() };
+        assert_eq!(
+            sorted(command.execute(&backend)),
+            bulks(&["a", "b", "c", "d"])
+        );
+        let command = CommandSDiff { keys };
+        assert_eq!(sorted(command.execute(&backend)), bulks(&["a"]));
+
+        let keys = members(&["s1", "s2", "s3"]);
+        let command = CommandSInter { keys: keys.clone() };
+        assert_eq!(sorted(command.execute(&backend)), bulks(&["c"]));
+        let command = CommandSDiff { keys };
+        assert_eq!(sorted(command.execute(&backend)), bulks(&["a"]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_algebra_disjoint_and_missing_sets() -> Result<()> {
+        let backend = Backend::new();
+        backend.sadd("s1", members(&["a", "b"]))?;
+        backend.sadd("s2", members(&["c"]))?;
+
+        let keys = members(&["s1", "s2"]);
+        let command = CommandSInter { keys: keys.clone() };
+        assert_eq!(sorted(command.execute(&backend)), bulks(&[]));
+        let command = CommandSUnion { keys: keys.clone() };
+        assert_eq!(sorted(command.execute(&backend)), bulks(&["a", "b", "c"]));
+        let command = CommandSDiff { keys };
+        assert_eq!(sorted(command.execute(&backend)), bulks(&["a", "b"]));
+
+        // a missing key is an empty set
+        let keys = members(&["s1", "missing"]);
+        let command = CommandSInter { keys: keys.clone() };
+        assert_eq!(sorted(command.execute(&backend)), bulks(&[]));
+        let command = CommandSUnion { keys: keys.clone() };
+        assert_eq!(sorted(command.execute(&backend)), bulks(&["a", "b"]));
+        let command = CommandSDiff {
+            keys: members(&["missing", "s1"]),
+        };
+        assert_eq!(sorted(command.execute(&backend)), bulks(&[]));
+
+        backend.set("string", RespBulkString::new("value").into());
+        let command = CommandSUnion {
+            keys: members(&["s1", "string"]),
+        };
+        assert_eq!(command.execute(&backend), RESP_WRONGTYPE.clone());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_replies_follow_protocol() -> Result<()> {
+        let backend = Backend::new();
+        backend.sadd("set", members(&["a"]))?;
+        let smembers = || CommandSMembers {
+            key: "set".to_string(),
+        };
+        let sinter = || CommandSInter {
+            keys: members(&["set"]),
+        };
+
+        assert_eq!(smembers().execute(&backend).encode()?, b"*1\r\n$1\r\na\r\n");
+        assert_eq!(sinter().execute(&backend).encode()?, b"*1\r\n$1\r\na\r\n");
+
+        backend.session().set_protocol(3);
+        assert_eq!(smembers().execute(&backend).encode()?, b"~1\r\n$1\r\na\r\n");
+        assert_eq!(sinter().execute(&backend).encode()?, b"~1\r\n$1\r\na\r\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_algebra_command_deadline() -> Result<()> {
+        let backend = Backend::with_config(crate::config::ServerConfig {
+            command_deadline: Some(std::time::Duration::from_nanos(1)),
+            ..Default::default()
+        });
+        for key in ["a", "b"] {
+            let members = (0..10_000).map(|i| format!("{}:{}", key, i)).collect();
+            backend.sadd(key, members)?;
+        }
+
+        let timed_out: RespFrame =
+            crate::RespSimpleError::new("ERR command execution timed out").into();
+        let keys = members(&["a", "b"]);
+        let command = CommandSInter { keys: keys.clone() };
+        assert_eq!(command.execute(&backend), timed_out);
+        let command = CommandSUnion { keys: keys.clone() };
+        assert_eq!(command.execute(&backend), timed_out);
+        let command = CommandSDiff { keys };
+        assert_eq!(command.execute(&backend), timed_out);
+
+        // the sets are left as they were
+        assert_eq!(backend.scard("a")?, 10_000);
+        Ok(())
+    }
+}
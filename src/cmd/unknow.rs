@@ -1,12 +1,113 @@
-use crate::{backend::Backend, RespFrame};
+use crate::{backend::Backend, RespArray, RespFrame, RespSimpleError};
 
-use super::{CommandExecutor, RESP_UNKNOWNN_COMMAND};
+use super::CommandExecutor;
+
+// like redis, the name and every arg are echoed back cut to 128 bytes
+const MAX_ECHOED_LEN: usize = 128;
 
 #[derive(Debug, PartialEq)]
-pub struct CommandUnknown;
+pub struct CommandUnknown {
+    name: String,
+    args: Vec<String>,
+}
+
+impl CommandUnknown {
+    pub fn new(value: &RespArray) -> Self {
+        let mut frames = value.iter().map(|frame| match frame.as_bytes() {
+            Some(bytes) => echoed(&bytes),
+            None => echoed(format!("{:?}", frame).as_bytes()),
+        });
+        let name = frames.next().unwrap_or_default();
+        Self {
+            name,
+            args: frames.collect(),
+        }
+    }
+}
+
+// the text ends up in a simple error, so control bytes are blanked out to keep a CR or LF
+// from ending the reply early
+fn echoed(bytes: &[u8]) -> String {
+    let bytes = &bytes[..bytes.len().min(MAX_ECHOED_LEN)];
+    String::from_utf8_lossy(bytes)
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect()
+}
 
 impl CommandExecutor for CommandUnknown {
     fn execute(self, _backend: &Backend) -> RespFrame {
-        RESP_UNKNOWNN_COMMAND.to_owned()
+        // same wording as redis: ERR unknown command 'foo', with args beginning with: 'a' 'b'
+        let args: String = self.args.iter().map(|arg| format!("'{}' ", arg)).collect();
+        RespSimpleError::new(format!(
+            "ERR unknown command '{}', with args beginning with: {}",
+            self.name, args
+        ))
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        backend::Backend,
+        cmd::{unknow::CommandUnknown, Command, CommandExecutor},
+        RespArray, RespBulkString, RespEncode, RespFrame, RespSimpleError,
+    };
+    use anyhow::Result;
+
+    #[test]
+    fn test_unknown_command_reports_name() -> Result<()> {
+        let resp_array = RespArray::new(vec![
+            RespBulkString::new(b"foobar".to_vec()).into(),
+            RespBulkString::new(b"key".to_vec()).into(),
+        ]);
+        let command: Command = resp_array.try_into()?;
+        assert!(matches!(command, Command::UnknownCommand(_)));
+
+        let backend = Backend::new();
+        let ret = command.execute(&backend);
+        assert_eq!(
+            ret,
+            RespFrame::Error(RespSimpleError::new(
+                "ERR unknown command 'foobar', with args beginning with: 'key' "
+            ))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_command_without_args() {
+        let resp_array = RespArray::new(vec![RespBulkString::new(b"foobar".to_vec()).into()]);
+        let command = CommandUnknown::new(&resp_array);
+        assert_eq!(command.name, "foobar");
+        assert!(command.args.is_empty());
+
+        let ret = command.execute(&Backend::new());
+        assert_eq!(
+            ret,
+            RespFrame::Error(RespSimpleError::new(
+                "ERR unknown command 'foobar', with args beginning with: "
+            ))
+        );
+    }
+
+    #[test]
+    fn test_unknown_command_name_cannot_break_the_reply() -> Result<()> {
+        let resp_array = RespArray::new(vec![
+            RespBulkString::new(b"x\r\n+INJ".to_vec()).into(),
+            RespBulkString::new(vec![b'a'; 1000]).into(),
+        ]);
+        let ret = CommandUnknown::new(&resp_array).execute(&Backend::new());
+        let expected = format!(
+            "ERR unknown command 'x  +INJ', with args beginning with: '{}' ",
+            "a".repeat(128)
+        );
+        assert_eq!(ret, RespFrame::Error(RespSimpleError::new(expected)));
+
+        let encoded = ret.encode()?;
+        assert_eq!(encoded.iter().filter(|b| **b == b'\n').count(), 1);
+        Ok(())
     }
 }
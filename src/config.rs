@@ -0,0 +1,454 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    thread,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::FrameLimits;
+
+const DEFAULT_WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_DATABASES: usize = 16;
+const DEFAULT_PIPELINE_DEPTH: usize = 1024;
+const DEFAULT_BIND: IpAddr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+const DEFAULT_PORT: u16 = 6379;
+const DEFAULT_DBFILENAME: &str = "dump.rdb";
+const DEFAULT_APPENDFILENAME: &str = "appendonly.aof";
+
+// how often the append only file is synced to disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AppendFsync {
+    // every write command, before it replies
+    Always,
+    // once a second, from the writer task
+    #[default]
+    EverySec,
+    // whenever the OS gets to it
+    No,
+}
+
+impl AppendFsync {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AppendFsync::Always => "always",
+            AppendFsync::EverySec => "everysec",
+            AppendFsync::No => "no",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    // address and port the server listens on
+    pub bind: IpAddr,
+    pub port: u16,
+    // a reply that can't be flushed to the client within this window aborts the connection
+    pub write_timeout: Duration,
+    // upper bound for a string value grown in place by commands like APPEND, and for the
+    // table LCS works in
+    pub proto_max_bulk_len: usize,
+    // most elements a client may declare for one aggregate frame
+    pub proto_max_multibulk_len: usize,
+    // original command name -> new name, an empty new name disables the command
+    pub rename_commands: HashMap<String, String>,
+    // connections have to switch to RESP3 with HELLO 3 before running any other command
+    pub resp3_only: bool,
+    // number of logical databases reachable with SELECT
+    pub databases: usize,
+    // clients have to AUTH with this password before running other commands
+    pub requirepass: Option<String>,
+    // replies to pipelined commands are batched, but flushed at least every this many commands
+    pub pipeline_depth: usize,
+    // shards of each database's map, a power of two above 1 like dashmap requires
+    pub shard_amount: usize,
+    // commands whose work grows with the data, KEYS, LRANGE, SINTER, SUNION, SDIFF and
+    // LCS, give up once they run longer than this
+    pub command_deadline: Option<Duration>,
+    // ttl given to keys SET stores without EX, PX, EXAT, PXAT or KEEPTTL, and to keys SETNX
+    // and GETSET store, so the server works as a bounded cache. redis has no such setting, its keys never expire by default
+    pub default_ttl: Option<Duration>,
+    // where SAVE writes the keyspace, and where it is loaded from on startup
+    pub dbfilename: PathBuf,
+    // record write commands to `appendfilename` and replay them on startup
+    pub appendonly: bool,
+    pub appendfilename: PathBuf,
+    pub appendfsync: AppendFsync,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: DEFAULT_BIND,
+            port: DEFAULT_PORT,
+            write_timeout: DEFAULT_WRITE_TIMEOUT,
+            proto_max_bulk_len: FrameLimits::default().max_bulk_len,
+            proto_max_multibulk_len: FrameLimits::default().max_aggregate_len,
+            rename_commands: HashMap::new(),
+            resp3_only: false,
+            databases: DEFAULT_DATABASES,
+            requirepass: None,
+            pipeline_depth: DEFAULT_PIPELINE_DEPTH,
+            shard_amount: default_shard_amount(),
+            command_deadline: None,
+            default_ttl: None,
+            dbfilename: PathBuf::from(DEFAULT_DBFILENAME),
+            appendonly: false,
+            appendfilename: PathBuf::from(DEFAULT_APPENDFILENAME),
+            appendfsync: AppendFsync::default(),
+        }
+    }
+}
+
+// the same default dashmap picks for itself
+fn default_shard_amount() -> usize {
+    let parallelism = thread::available_parallelism().map_or(1, usize::from);
+    (parallelism * 4).next_power_of_two()
+}
+
+impl ServerConfig {
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut config = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--bind" => match args.next() {
+                    Some(addr) => {
+                        config.bind = addr
+                            .parse()
+                            .map_err(|_| anyhow!("--bind: invalid IP address {:?}", addr))?
+                    }
+                    None => return Err(anyhow!("--bind requires an IP address")),
+                },
+                "--port" => match args.next() {
+                    Some(port) => {
+                        config.port = port
+                            .parse()
+                            .map_err(|_| anyhow!("--port: invalid port {:?}", port))?
+                    }
+                    None => return Err(anyhow!("--port requires a port number")),
+                },
+                "--rename-command" => match (args.next(), args.next()) {
+                    (Some(name), Some(new_name)) => {
+                        config
+                            .rename_commands
+                            .insert(name.to_lowercase(), new_name.to_lowercase());
+                    }
+                    _ => return Err(anyhow!("--rename-command requires <command> <new-name>")),
+                },
+                "--resp3-only" => config.resp3_only = true,
+                "--requirepass" => match args.next() {
+                    Some(password) => config.requirepass = Some(password),
+                    None => return Err(anyhow!("--requirepass requires a password")),
+                },
+                "--databases" => match args.next().and_then(|n| n.parse().ok()) {
+                    Some(n) if n > 0 => config.databases = n,
+                    _ => return Err(anyhow!("--databases requires a positive number")),
+                },
+                "--pipeline-depth" => match args.next().and_then(|n| n.parse().ok()) {
+                    Some(n) if n > 0 => config.pipeline_depth = n,
+                    _ => return Err(anyhow!("--pipeline-depth requires a positive number")),
+                },
+                "--command-deadline" => match args.next().and_then(|ms| ms.parse().ok()) {
+                    Some(ms) if ms > 0 => config.command_deadline = Some(Duration::from_millis(ms)),
+                    _ => {
+                        return Err(anyhow!(
+                            "--command-deadline requires a positive number of milliseconds"
+                        ))
+                    }
+                },
+                "--default-ttl" => match args.next().and_then(|secs| secs.parse().ok()) {
+                    Some(secs) if secs > 0 => config.default_ttl = Some(Duration::from_secs(secs)),
+                    _ => {
+                        return Err(anyhow!(
+                            "--default-ttl requires a positive number of seconds"
+                        ))
+                    }
+                },
+                "--dbfilename" => match args.next() {
+                    Some(path) => config.dbfilename = PathBuf::from(path),
+                    None => return Err(anyhow!("--dbfilename requires a path")),
+                },
+                "--appendonly" => config.appendonly = true,
+                "--appendfilename" => match args.next() {
+                    Some(path) => config.appendfilename = PathBuf::from(path),
+                    None => return Err(anyhow!("--appendfilename requires a path")),
+                },
+                "--appendfsync" => match args.next().as_deref() {
+                    Some("always") => config.appendfsync = AppendFsync::Always,
+                    Some("everysec") => config.appendfsync = AppendFsync::EverySec,
+                    Some("no") => config.appendfsync = AppendFsync::No,
+                    _ => return Err(anyhow!("--appendfsync requires always, everysec or no")),
+                },
+                "--proto-max-bulk-len" => match args.next().and_then(|n| n.parse().ok()) {
+                    Some(n) if n > 0 => config.proto_max_bulk_len = n,
+                    _ => return Err(anyhow!("--proto-max-bulk-len requires a positive number")),
+                },
+                "--proto-max-multibulk-len" => match args.next().and_then(|n| n.parse().ok()) {
+                    Some(n) if n > 0 => config.proto_max_multibulk_len = n,
+                    _ => {
+                        return Err(anyhow!(
+                            "--proto-max-multibulk-len requires a positive number"
+                        ))
+                    }
+                },
+                "--shards" => match args.next().and_then(|n| n.parse::<usize>().ok()) {
+                    Some(n) if n > 1 && n.is_power_of_two() => config.shard_amount = n,
+                    _ => return Err(anyhow!("--shards requires a power of two above 1")),
+                },
+                _ => return Err(anyhow!("Unknown argument: {}", arg)),
+            }
+        }
+
+        Ok(config)
+    }
+
+    // what the decoder accepts from clients
+    pub fn frame_limits(&self) -> FrameLimits {
+        FrameLimits {
+            max_bulk_len: self.proto_max_bulk_len,
+            max_aggregate_len: self.proto_max_multibulk_len,
+        }
+    }
+
+    pub fn listen_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.bind, self.port)
+    }
+
+    // maps the command name a client sent to the command that should run,
+    // None if that name was renamed away or disabled
+    pub fn resolve_command(&self, name: &str) -> Option<String> {
+        if self.rename_commands.contains_key(name) {
+            return None;
+        }
+        match self
+            .rename_commands
+            .iter()
+            .find(|(_, new_name)| !new_name.is_empty() && new_name.as_str() == name)
+        {
+            Some((original, _)) => Some(original.to_owned()),
+            None => Some(name.to_owned()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rename_command_from_args() -> Result<()> {
+        let args = [
+            "--rename-command",
+            "FLUSHDB",
+            "",
+            "--rename-command",
+            "keys",
+            "k",
+        ];
+        let config = ServerConfig::from_args(args.into_iter().map(String::from))?;
+
+        assert_eq!(config.resolve_command("flushdb"), None);
+        assert_eq!(config.resolve_command("keys"), None);
+        assert_eq!(config.resolve_command("k"), Some("keys".to_string()));
+        assert_eq!(config.resolve_command("get"), Some("get".to_string()));
+
+        let args = ["--rename-command", "flushdb"];
+        assert!(ServerConfig::from_args(args.into_iter().map(String::from)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resp3_only_from_args() -> Result<()> {
+        assert!(!ServerConfig::from_args(Vec::new())?.resp3_only);
+
+        let config = ServerConfig::from_args(["--resp3-only".to_string()])?;
+        assert!(config.resp3_only);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_databases_from_args() -> Result<()> {
+        assert_eq!(ServerConfig::from_args(Vec::new())?.databases, 16);
+
+        let args = ["--databases", "4"].map(String::from);
+        assert_eq!(ServerConfig::from_args(args)?.databases, 4);
+
+        let args = ["--databases", "0"].map(String::from);
+        assert!(ServerConfig::from_args(args).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_requirepass_from_args() -> Result<()> {
+        assert_eq!(ServerConfig::from_args(Vec::new())?.requirepass, None);
+
+        let args = ["--requirepass", "secret"].map(String::from);
+        let config = ServerConfig::from_args(args)?;
+        assert_eq!(config.requirepass.as_deref(), Some("secret"));
+
+        assert!(ServerConfig::from_args(["--requirepass".to_string()]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pipeline_depth_from_args() -> Result<()> {
+        assert_eq!(ServerConfig::from_args(Vec::new())?.pipeline_depth, 1024);
+
+        let args = ["--pipeline-depth", "8"].map(String::from);
+        assert_eq!(ServerConfig::from_args(args)?.pipeline_depth, 8);
+
+        let args = ["--pipeline-depth", "none"].map(String::from);
+        assert!(ServerConfig::from_args(args).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_listen_addr_from_args() -> Result<()> {
+        let config = ServerConfig::from_args(Vec::new())?;
+        assert_eq!(config.listen_addr().to_string(), "0.0.0.0:6379");
+
+        let args = ["--bind", "127.0.0.1", "--port", "7000"].map(String::from);
+        let config = ServerConfig::from_args(args)?;
+        assert_eq!(config.listen_addr().to_string(), "127.0.0.1:7000");
+
+        let args = ["--bind", "::1"].map(String::from);
+        assert_eq!(
+            ServerConfig::from_args(args)?.listen_addr().to_string(),
+            "[::1]:6379"
+        );
+
+        for args in [
+            ["--bind", "localhost:1"],
+            ["--port", "70000"],
+            ["--port", "-1"],
+        ] {
+            let err = ServerConfig::from_args(args.map(String::from)).unwrap_err();
+            assert!(err.to_string().starts_with(args[0]));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_proto_limits_from_args() -> Result<()> {
+        let config = ServerConfig::from_args(Vec::new())?;
+        assert_eq!(config.frame_limits(), FrameLimits::default());
+
+        let args = [
+            "--proto-max-bulk-len",
+            "1048576",
+            "--proto-max-multibulk-len",
+            "1000",
+        ]
+        .map(String::from);
+        let config = ServerConfig::from_args(args)?;
+        assert_eq!(
+            config.frame_limits(),
+            FrameLimits {
+                max_bulk_len: 1024 * 1024,
+                max_aggregate_len: 1000,
+            }
+        );
+
+        for args in [
+            ["--proto-max-bulk-len", "0"],
+            ["--proto-max-multibulk-len", "lots"],
+        ] {
+            assert!(ServerConfig::from_args(args.map(String::from)).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_deadline_from_args() -> Result<()> {
+        assert_eq!(ServerConfig::from_args(Vec::new())?.command_deadline, None);
+
+        let args = ["--command-deadline", "250"].map(String::from);
+        assert_eq!(
+            ServerConfig::from_args(args)?.command_deadline,
+            Some(Duration::from_millis(250))
+        );
+
+        let args = ["--command-deadline", "0"].map(String::from);
+        assert!(ServerConfig::from_args(args).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_ttl_from_args() -> Result<()> {
+        assert_eq!(ServerConfig::from_args(Vec::new())?.default_ttl, None);
+
+        let args = ["--default-ttl", "60"].map(String::from);
+        assert_eq!(
+            ServerConfig::from_args(args)?.default_ttl,
+            Some(Duration::from_secs(60))
+        );
+
+        for args in [["--default-ttl", "0"], ["--default-ttl", "soon"]] {
+            assert!(ServerConfig::from_args(args.map(String::from)).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_dbfilename_from_args() -> Result<()> {
+        let config = ServerConfig::from_args(Vec::new())?;
+        assert_eq!(config.dbfilename, PathBuf::from("dump.rdb"));
+
+        let args = ["--dbfilename", "/var/lib/redis/data.rdb"].map(String::from);
+        let config = ServerConfig::from_args(args)?;
+        assert_eq!(config.dbfilename, PathBuf::from("/var/lib/redis/data.rdb"));
+
+        assert!(ServerConfig::from_args(["--dbfilename".to_string()]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_appendonly_from_args() -> Result<()> {
+        let config = ServerConfig::from_args(Vec::new())?;
+        assert!(!config.appendonly);
+        assert_eq!(config.appendfilename, PathBuf::from("appendonly.aof"));
+
+        assert_eq!(config.appendfsync, AppendFsync::EverySec);
+
+        let args = ["--appendonly", "--appendfilename", "/tmp/writes.aof"].map(String::from);
+        let config = ServerConfig::from_args(args)?;
+        assert!(config.appendonly);
+        assert_eq!(config.appendfilename, PathBuf::from("/tmp/writes.aof"));
+
+        let args = ["--appendfsync", "always"].map(String::from);
+        assert_eq!(
+            ServerConfig::from_args(args)?.appendfsync,
+            AppendFsync::Always
+        );
+        let args = ["--appendfsync", "sometimes"].map(String::from);
+        assert!(ServerConfig::from_args(args).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_shards_from_args() -> Result<()> {
+        let default = ServerConfig::from_args(Vec::new())?.shard_amount;
+        assert!(default > 1 && default.is_power_of_two());
+
+        let args = ["--shards", "64"].map(String::from);
+        assert_eq!(ServerConfig::from_args(args)?.shard_amount, 64);
+
+        for n in ["0", "1", "48"] {
+            let args = ["--shards", n].map(String::from);
+            assert!(ServerConfig::from_args(args).is_err());
+        }
+
+        Ok(())
+    }
+}
@@ -1,5 +1,8 @@
+pub mod aof;
 pub mod backend;
+pub mod client;
 pub mod cmd;
+pub mod config;
 pub mod network;
 mod resp;
 
@@ -1,29 +1,84 @@
+use std::time::Duration;
+
 use anyhow::Result;
-use simple_redis::{backend::Backend, network::stream_handler};
-use tokio::net::TcpListener;
+use simple_redis::{
+    aof::{self, Aof},
+    backend::Backend,
+    config::ServerConfig,
+    network::stream_handler,
+};
+use tokio::{net::TcpListener, signal, task::JoinSet, time::timeout};
 use tracing::{info, warn};
 
+// on Ctrl-C the server stops accepting and gives open connections this long to finish,
+// clients still connected after that are dropped. idle clients never finish on their own,
+// so a shutdown with clients attached always takes the full window
+const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
 
-    let addr = "0.0.0.0:6379";
+    let config = ServerConfig::from_args(std::env::args().skip(1))?;
+
+    let addr = config.listen_addr();
     info!("Simple-Redis-Server listening on {}", addr);
     let listener = TcpListener::bind(addr).await?;
 
-    let backend = Backend::new();
+    let dbfilename = config.dbfilename.clone();
+    let appendfilename = config.appendonly.then(|| config.appendfilename.clone());
+    let backend = Backend::with_config(config);
+    // like redis, the append only file wins over the dump when it is turned on. it is
+    // replayed before being attached, so replaying doesn't record the commands again
+    if let Some(path) = appendfilename {
+        if path.exists() {
+            let commands = aof::replay(&backend, &path)?;
+            info!("Replayed {} commands from {}", commands, path.display());
+        }
+        backend.enable_aof(Aof::open(&path, backend.config.appendfsync).await?);
+    } else if dbfilename.exists() {
+        let records = backend.load_from(&dbfilename)?;
+        info!("Loaded {} records from {}", records, dbfilename.display());
+    }
+
+    let mut connections = JoinSet::new();
+    let shutdown = signal::ctrl_c();
+    tokio::pin!(shutdown);
 
     loop {
-        let (stream, raddr) = listener.accept().await?;
-        info!("New connection from {}", raddr);
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, raddr) = accepted?;
+                info!("New connection from {}", raddr);
 
-        let backend_cloned = backend.clone();
+                let backend_cloned = backend.clone();
 
-        tokio::spawn(async move {
-            match stream_handler(stream, backend_cloned).await {
-                Ok(_) => info!("Connection from {} exited", raddr),
-                Err(e) => warn!("Connection closed with error: {}", e),
+                connections.spawn(async move {
+                    match stream_handler(stream, backend_cloned).await {
+                        Ok(_) => info!("Connection from {} exited", raddr),
+                        Err(e) => warn!("Connection closed with error: {}", e),
+                    }
+                });
             }
-        });
+            // reap finished connections so the set doesn't grow with every client served
+            Some(_) = connections.join_next(), if !connections.is_empty() => {}
+            ret = &mut shutdown => {
+                ret?;
+                info!("Shutting down, no longer accepting connections");
+                break;
+            }
+        }
+    }
+    drop(listener);
+
+    let drain = async { while connections.join_next().await.is_some() {} };
+    if timeout(SHUTDOWN_DRAIN_TIMEOUT, drain).await.is_err() {
+        warn!("Closing {} connections still open", connections.len());
+        connections.shutdown().await;
+    }
+    if let Some(aof) = backend.aof() {
+        aof.flush().await;
     }
+    info!("Simple-Redis-Server stopped");
+    Ok(())
 }
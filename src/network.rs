@@ -1,31 +1,38 @@
-use anyhow::{Ok, Result};
+use anyhow::{anyhow, Ok, Result};
 use futures::SinkExt;
-use tokio::net::TcpStream;
+use tokio::{net::TcpStream, time::timeout};
 use tokio_stream::StreamExt;
 use tracing::info;
 
 use crate::resp::RespEncode;
 use crate::{
-    backend::Backend,
-    cmd::{Command, CommandExecutor},
-    RespArray, RespDecode, RespFrame,
+    backend::Backend, cmd::dispatch, frame_len, FrameLimits, RespArray, RespDecode,
+    RespDecodeError, RespFrame, RespSimpleError,
 };
 use tokio_util::codec::{Decoder, Encoder, Framed};
 
-struct RespFrameCodec;
+// encodes and decodes whole frames, shared by the server and `Client`
+#[derive(Debug, Default)]
+pub struct RespFrameCodec {
+    limits: FrameLimits,
+}
+
+impl RespFrameCodec {
+    pub fn new(limits: FrameLimits) -> Self {
+        Self { limits }
+    }
+}
 
 impl Encoder<RespFrame> for RespFrameCodec {
     type Error = anyhow::Error;
 
     fn encode(&mut self, item: RespFrame, dst: &mut bytes::BytesMut) -> Result<()> {
-        let encoded = item.encode()?;
-        dst.extend_from_slice(&encoded);
-        Ok(())
+        item.encode_to(dst)
     }
 }
 
 impl Decoder for RespFrameCodec {
-    type Item = RespArray;
+    type Item = RespFrame;
     type Error = anyhow::Error;
 
     fn decode(&mut self, src: &mut bytes::BytesMut) -> Result<Option<Self::Item>> {
@@ -33,14 +40,45 @@ impl Decoder for RespFrameCodec {
             "RespFrameCodec decode command buf: {}",
             String::from_utf8_lossy(src)
         );
-        if src.is_empty() {
-            return Ok(None);
+        // a partial frame stays in `src` untouched until the rest of it arrives. the limits
+        // are only checked here, decoding itself doesn't know about them
+        match frame_len(src, &self.limits) {
+            Err(err) if err.is_incomplete() => return Ok(None),
+            Err(err @ RespDecodeError::InvalidFrameLength(_)) => return Err(err.into()),
+            _ => {}
         }
-        let frame = RespArray::decode(src)?;
+        let frame = RespFrame::decode(src)?;
         Ok(Some(frame))
     }
 }
 
+// counts replies written but not flushed yet, replies are flushed once no complete
+// command is buffered or once `max_depth` of them are pending
+#[derive(Debug)]
+struct Pipeline {
+    pending: usize,
+    max_depth: usize,
+}
+
+impl Pipeline {
+    fn new(max_depth: usize) -> Self {
+        Self {
+            pending: 0,
+            max_depth,
+        }
+    }
+
+    // records one more reply and tells whether the batch has to be flushed now
+    fn push(&mut self, more_buffered: bool) -> bool {
+        self.pending += 1;
+        if !more_buffered || self.pending >= self.max_depth {
+            self.pending = 0;
+            return true;
+        }
+        false
+    }
+}
+
 #[derive(Debug)]
 struct RedisRequest {
     frame: RespArray,
@@ -53,28 +91,651 @@ struct RedisResponse {
 }
 
 pub async fn stream_handler(stream: TcpStream, backend: Backend) -> Result<()> {
-    let mut framed = Framed::new(stream, RespFrameCodec);
+    let backend = backend.new_session();
+    let _client = backend.client_connected();
+    let mut pushes = backend
+        .session()
+        .take_pushes()
+        .ok_or_else(|| anyhow!("session push queue already taken"))?;
+    let mut framed = Framed::new(stream, RespFrameCodec::new(backend.config.frame_limits()));
+    let mut pipeline = Pipeline::new(backend.config.pipeline_depth);
 
     loop {
-        match framed.next().await {
-            Some(std::result::Result::Ok(frame)) => {
-                let request = RedisRequest {
-                    frame,
-                    backend: backend.clone(),
-                };
-                info!("request: {:?}", request);
-                let response = request_handler(request).await?;
-                framed.send(response.response).await?;
-            }
-            Some(Err(err)) => return Err(err),
-            None => return Ok(()),
+        tokio::select! {
+            frame = framed.next() => match frame {
+                // an empty array carries no command, ignore it like redis does. replies
+                // queued by the commands before it still have to go out
+                Some(std::result::Result::Ok(RespFrame::Array(frame))) if frame.is_empty() => {
+                    if !has_complete_frame(&framed) {
+                        flush(&mut framed, &backend).await?;
+                    }
+                }
+                Some(std::result::Result::Ok(RespFrame::Array(frame))) => {
+                    let request = RedisRequest {
+                        frame,
+                        backend: backend.clone(),
+                    };
+                    info!("request: {:?}", request);
+                    let response = request_handler(request).await;
+                    feed_frame(&mut framed, response.response, &backend).await?;
+                    // frames a command queued behind its reply
+                    while let std::result::Result::Ok(frame) = pushes.try_recv() {
+                        feed_frame(&mut framed, frame, &backend).await?;
+                    }
+                    if pipeline.push(has_complete_frame(&framed)) {
+                        flush(&mut framed, &backend).await?;
+                    }
+                }
+                Some(std::result::Result::Ok(frame)) => {
+                    let err = RespDecodeError::unexpected_frame("a command array", &frame).into();
+                    let reply = RespSimpleError::new(format!("ERR Protocol error: {}", err));
+                    let _ = send_frame(&mut framed, reply.into(), &backend).await;
+                    return Err(err);
+                }
+                Some(Err(err)) => {
+                    // tell the client why the connection is dropped, like redis does
+                    let reply = RespSimpleError::new(format!("ERR Protocol error: {}", err));
+                    let _ = send_frame(&mut framed, reply.into(), &backend).await;
+                    return Err(err);
+                }
+                None => return Ok(()),
+            },
+            Some(frame) = pushes.recv() => send_frame(&mut framed, frame, &backend).await?,
+            _ = backend.session().overflowed() => return Err(anyhow!("push queue overflowed")),
         }
     }
 }
 
-async fn request_handler(request: RedisRequest) -> Result<RedisResponse> {
-    let (frame, backend) = (request.frame, request.backend);
-    let cmd = Command::try_from(frame)?;
-    let ret = cmd.execute(&backend);
-    Ok(RedisResponse { response: ret })
+// only a whole frame is decoded without waiting on the socket, a partial one must not hold
+// back the replies already written
+fn has_complete_frame(framed: &Framed<TcpStream, RespFrameCodec>) -> bool {
+    frame_len(framed.read_buffer(), &framed.codec().limits).is_ok()
+}
+
+async fn send_frame(
+    framed: &mut Framed<TcpStream, RespFrameCodec>,
+    frame: RespFrame,
+    backend: &Backend,
+) -> Result<()> {
+    match timeout(backend.config.write_timeout, framed.send(frame)).await {
+        std::result::Result::Ok(ret) => ret,
+        Err(_) => Err(anyhow!("write timed out")),
+    }
+}
+
+// queues a frame without flushing, the sink still writes out once its buffer fills up
+async fn feed_frame(
+    framed: &mut Framed<TcpStream, RespFrameCodec>,
+    frame: RespFrame,
+    backend: &Backend,
+) -> Result<()> {
+    match timeout(backend.config.write_timeout, framed.feed(frame)).await {
+        std::result::Result::Ok(ret) => ret,
+        Err(_) => Err(anyhow!("write timed out")),
+    }
+}
+
+async fn flush(framed: &mut Framed<TcpStream, RespFrameCodec>, backend: &Backend) -> Result<()> {
+    match timeout(backend.config.write_timeout, framed.flush()).await {
+        std::result::Result::Ok(ret) => ret,
+        Err(_) => Err(anyhow!("write timed out")),
+    }
+}
+
+// never fails, bad commands become error replies and only I/O or codec errors close
+// the connection
+async fn request_handler(request: RedisRequest) -> RedisResponse {
+    let ret = dispatch(request.frame, &request.backend);
+    RedisResponse { response: ret }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use anyhow::Result;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+
+    use bytes::BytesMut;
+    use tokio_util::codec::Decoder;
+
+    use crate::{
+        backend::Backend, config::ServerConfig, FrameLimits, RespArray, RespBulkString,
+        RespDecodeError,
+    };
+
+    use super::{stream_handler, Pipeline, RespFrameCodec};
+
+    #[tokio::test]
+    async fn test_write_timeout_on_stalled_client() -> Result<()> {
+        let backend = Backend::with_config(ServerConfig {
+            write_timeout: Duration::from_millis(100),
+            ..Default::default()
+        });
+        backend.set("big", RespBulkString::new(vec![b'a'; 1024 * 1024]).into());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+
+        // the client pipelines requests for large replies and never reads them
+        let mut client = TcpStream::connect(addr).await?;
+        client
+            .write_all(&b"*2\r\n$3\r\nget\r\n$3\r\nbig\r\n".repeat(64))
+            .await?;
+
+        let (stream, _) = listener.accept().await?;
+        let ret = tokio::time::timeout(Duration::from_secs(10), stream_handler(stream, backend))
+            .await
+            .expect("connection task should terminate after the write timeout");
+        assert_eq!(ret.unwrap_err().to_string(), "write timed out");
+
+        drop(client);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_empty_command_is_ignored() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await?;
+            stream_handler(stream, Backend::new()).await
+        });
+
+        let mut client = TcpStream::connect(addr).await?;
+        client.write_all(b"*0\r\n").await?;
+        client
+            .write_all(b"*2\r\n$4\r\necho\r\n$5\r\nhello\r\n")
+            .await?;
+
+        let mut buf = [0u8; 11];
+        client.read_exact(&mut buf).await?;
+        assert_eq!(&buf, b"$5\r\nhello\r\n");
+
+        drop(client);
+        server.await??;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_protocol_error_reports_offset() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await?;
+            stream_handler(stream, Backend::new()).await
+        });
+
+        let mut client = TcpStream::connect(addr).await?;
+        client.write_all(b"*1\r\n?oops\r\n").await?;
+
+        let mut reply = String::new();
+        client.read_to_string(&mut reply).await?;
+        assert!(reply.starts_with("-ERR Protocol error: Invalid frame at offset 4"));
+
+        assert!(server.await?.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resp3_only_rejects_resp2_commands() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await?;
+            let backend = Backend::with_config(ServerConfig {
+                resp3_only: true,
+                ..Default::default()
+            });
+            stream_handler(stream, backend).await
+        });
+
+        let mut client = TcpStream::connect(addr).await?;
+        client.write_all(b"*1\r\n$4\r\nping\r\n").await?;
+        let expected = b"-NOPROTO unsupported protocol version\r\n";
+        let mut buf = vec![0u8; expected.len()];
+        client.read_exact(&mut buf).await?;
+        assert_eq!(buf, expected);
+
+        client
+            .write_all(b"*2\r\n$5\r\nhello\r\n$1\r\n3\r\n")
+            .await?;
+        let mut buf = [0u8; 1];
+        client.read_exact(&mut buf).await?;
+        assert_eq!(&buf, b"%");
+
+        // skip the rest of the HELLO reply, then PING should go through
+        client.write_all(b"*1\r\n$4\r\nping\r\n").await?;
+        let mut reply = Vec::new();
+        while !reply.ends_with(b"+PONG\r\n") {
+            let mut buf = [0u8; 256];
+            let n = client.read(&mut buf).await?;
+            assert!(n > 0, "connection closed before PONG");
+            reply.extend_from_slice(&buf[..n]);
+        }
+
+        drop(client);
+        server.await??;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_requirepass_rejects_commands_before_auth() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await?;
+            let backend = Backend::with_config(ServerConfig {
+                requirepass: Some("secret".to_string()),
+                ..Default::default()
+            });
+            stream_handler(stream, backend).await
+        });
+
+        let mut client = TcpStream::connect(addr).await?;
+        client.write_all(b"*1\r\n$4\r\nping\r\n").await?;
+        let expected = b"-NOAUTH Authentication required.\r\n";
+        let mut buf = vec![0u8; expected.len()];
+        client.read_exact(&mut buf).await?;
+        assert_eq!(buf, expected);
+
+        client
+            .write_all(b"*2\r\n$4\r\nauth\r\n$6\r\nsecret\r\n*1\r\n$4\r\nping\r\n")
+            .await?;
+        let mut buf = [0u8; 12];
+        client.read_exact(&mut buf).await?;
+        assert_eq!(&buf, b"+OK\r\n+PONG\r\n");
+
+        drop(client);
+        server.await??;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_publish_reaches_subscriber() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            let backend = Backend::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(stream_handler(stream, backend.clone()));
+            }
+        });
+
+        let mut subscriber = TcpStream::connect(addr).await?;
+        subscriber
+            .write_all(b"*2\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n")
+            .await?;
+        let expected = b"*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n";
+        let mut buf = vec![0u8; expected.len()];
+        subscriber.read_exact(&mut buf).await?;
+        assert_eq!(buf, expected);
+
+        let mut publisher = TcpStream::connect(addr).await?;
+        publisher
+            .write_all(b"*3\r\n$7\r\npublish\r\n$4\r\nnews\r\n$5\r\nhello\r\n")
+            .await?;
+        let mut buf = [0u8; 4];
+        publisher.read_exact(&mut buf).await?;
+        assert_eq!(&buf, b":1\r\n");
+
+        let expected = b"*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n";
+        let mut buf = vec![0u8; expected.len()];
+        tokio::time::timeout(Duration::from_secs(5), subscriber.read_exact(&mut buf)).await??;
+        assert_eq!(buf, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pipeline_flushes_in_batches() {
+        let mut pipeline = Pipeline::new(3);
+        let flushes: Vec<bool> = (0..7).map(|_| pipeline.push(true)).collect();
+        assert_eq!(flushes, vec![false, false, true, false, false, true, false]);
+
+        // nothing else buffered, the pending replies go out right away
+        assert!(pipeline.push(false));
+        assert!(!pipeline.push(true));
+    }
+
+    #[tokio::test]
+    async fn test_pipelined_replies_beyond_depth_arrive_in_order() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await?;
+            let backend = Backend::with_config(ServerConfig {
+                pipeline_depth: 4,
+                ..Default::default()
+            });
+            stream_handler(stream, backend).await
+        });
+
+        let mut request = Vec::new();
+        let mut expected = Vec::new();
+        for i in 0..50 {
+            let message = format!("{:02}", i);
+            request
+                .extend_from_slice(format!("*2\r\n$4\r\necho\r\n$2\r\n{}\r\n", message).as_bytes());
+            expected.extend_from_slice(format!("$2\r\n{}\r\n", message).as_bytes());
+        }
+
+        let mut client = TcpStream::connect(addr).await?;
+        client.write_all(&request).await?;
+        let mut buf = vec![0u8; expected.len()];
+        tokio::time::timeout(Duration::from_secs(5), client.read_exact(&mut buf)).await??;
+        assert_eq!(buf, expected);
+
+        drop(client);
+        server.await??;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_command_error_keeps_connection_open() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await?;
+            stream_handler(stream, Backend::new()).await
+        });
+
+        let mut client = TcpStream::connect(addr).await?;
+        client
+            .write_all(b"*3\r\n$3\r\nget\r\n$1\r\na\r\n$1\r\nb\r\n*1\r\n$4\r\nping\r\n")
+            .await?;
+        client.shutdown().await?;
+
+        let mut reply = String::new();
+        client.read_to_string(&mut reply).await?;
+        assert!(reply.starts_with("-ERR Invalid command arguments: "));
+        assert!(reply.ends_with("\r\n+PONG\r\n"));
+
+        server.await??;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_non_array_frame_closes_connection() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await?;
+            stream_handler(stream, Backend::new()).await
+        });
+
+        let mut client = TcpStream::connect(addr).await?;
+        client.write_all(b"+ping\r\n*1\r\n$4\r\nping\r\n").await?;
+
+        let mut reply = String::new();
+        client.read_to_string(&mut reply).await?;
+        assert!(
+            reply.starts_with("-ERR Protocol error: Unexpected frame: expected a command array")
+        );
+        assert!(!reply.contains("PONG"));
+
+        assert!(server.await?.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pipelined_auth_applies_to_following_commands() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await?;
+            let backend = Backend::with_config(ServerConfig {
+                requirepass: Some("secret".to_string()),
+                ..Default::default()
+            });
+            backend.set("k", RespBulkString::new("v").into());
+            stream_handler(stream, backend).await
+        });
+
+        // every command in one batch is checked against the state left by the one before it
+        let mut client = TcpStream::connect(addr).await?;
+        client
+            .write_all(
+                b"*2\r\n$4\r\nauth\r\n$5\r\nwrong\r\n*2\r\n$3\r\nget\r\n$1\r\nk\r\n\
+                  *2\r\n$4\r\nauth\r\n$6\r\nsecret\r\n*2\r\n$3\r\nget\r\n$1\r\nk\r\n",
+            )
+            .await?;
+        client.shutdown().await?;
+
+        let mut reply = String::new();
+        client.read_to_string(&mut reply).await?;
+        let replies: Vec<&str> = reply.split_terminator("\r\n").collect();
+        assert!(replies[0].starts_with("-WRONGPASS"));
+        assert_eq!(
+            replies[1..],
+            ["-NOAUTH Authentication required.", "+OK", "$1", "v"]
+        );
+
+        server.await??;
+        Ok(())
+    }
+
+    #[test]
+    fn test_codec_waits_for_split_frame() -> Result<()> {
+        let mut codec = RespFrameCodec::default();
+        let mut buf = BytesMut::from(&b"*2\r\n$3\r\nget\r\n$5\r\nhel"[..]);
+        assert_eq!(codec.decode(&mut buf)?, None);
+        // nothing consumed, the next segment completes the same frame
+        assert_eq!(&buf[..], b"*2\r\n$3\r\nget\r\n$5\r\nhel");
+
+        buf.extend_from_slice(b"lo\r\n*1\r\n$4\r\nping");
+        let frame = codec.decode(&mut buf)?;
+        assert_eq!(
+            frame,
+            Some(
+                RespArray::new(vec![
+                    RespBulkString::new("get").into(),
+                    RespBulkString::new("hello").into(),
+                ])
+                .into()
+            )
+        );
+        assert_eq!(codec.decode(&mut buf)?, None);
+        assert_eq!(&buf[..], b"*1\r\n$4\r\nping");
+
+        let mut buf = BytesMut::from(&b"*1\r\n?oops\r\n"[..]);
+        assert!(codec.decode(&mut buf).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pings_in_one_write_get_one_reply_each() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await?;
+            stream_handler(stream, Backend::new()).await
+        });
+
+        let mut client = TcpStream::connect(addr).await?;
+        client.write_all(&b"*1\r\n$4\r\nping\r\n".repeat(3)).await?;
+        let mut buf = [0u8; 21];
+        tokio::time::timeout(Duration::from_secs(5), client.read_exact(&mut buf)).await??;
+        assert_eq!(&buf, b"+PONG\r\n+PONG\r\n+PONG\r\n");
+
+        drop(client);
+        server.await??;
+        Ok(())
+    }
+
+    #[test]
+    fn test_codec_returns_one_frame_per_call() -> Result<()> {
+        let mut codec = RespFrameCodec::default();
+        let mut buf = BytesMut::from(&b"*1\r\n$4\r\nping\r\n".repeat(3)[..]);
+        for remaining in [2, 1, 0] {
+            assert!(codec.decode(&mut buf)?.is_some());
+            assert_eq!(buf.len(), remaining * 14);
+        }
+        assert_eq!(codec.decode(&mut buf)?, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_set_split_over_three_segments_runs_once() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await?;
+            stream_handler(stream, Backend::new()).await
+        });
+
+        let mut client = TcpStream::connect(addr).await?;
+        for segment in [
+            &b"*3\r\n"[..],
+            b"$3\r\nset\r\n$5\r\nhello\r\n",
+            b"$5\r\nworld\r\n",
+        ] {
+            client.write_all(segment).await?;
+            client.flush().await?;
+            // give the server time to read each segment on its own
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        client
+            .write_all(b"*2\r\n$3\r\nget\r\n$5\r\nhello\r\n")
+            .await?;
+
+        // exactly one OK, then the value, with no error or second reply in between
+        let expected = b"+OK\r\n$5\r\nworld\r\n";
+        let mut buf = [0u8; 16];
+        tokio::time::timeout(Duration::from_secs(5), client.read_exact(&mut buf)).await??;
+        assert_eq!(&buf, expected);
+
+        client.shutdown().await?;
+        let mut rest = Vec::new();
+        client.read_to_end(&mut rest).await?;
+        assert!(rest.is_empty(), "unexpected replies: {:?}", rest);
+        server.await??;
+        Ok(())
+    }
+
+    // the count is updated by the server side of the connection, so give it a moment
+    async fn wait_for_clients(backend: &Backend, expected: usize) {
+        let wait = async {
+            while backend.connected_clients() != expected {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        if tokio::time::timeout(Duration::from_secs(5), wait)
+            .await
+            .is_err()
+        {
+            panic!(
+                "expected {} connected clients, got {}",
+                expected,
+                backend.connected_clients()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connected_clients_count() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let backend = Backend::new();
+        let server_backend = backend.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(stream_handler(stream, server_backend.clone()));
+            }
+        });
+
+        let first = TcpStream::connect(addr).await?;
+        let mut second = TcpStream::connect(addr).await?;
+        wait_for_clients(&backend, 2).await;
+
+        drop(first);
+        wait_for_clients(&backend, 1).await;
+
+        // a connection closed by a protocol error is uncounted all the same
+        second.write_all(b"+ping\r\n").await?;
+        let mut reply = Vec::new();
+        second.read_to_end(&mut reply).await?;
+        wait_for_clients(&backend, 0).await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reply_flushed_before_trailing_empty_array() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await?;
+            stream_handler(stream, Backend::new()).await
+        });
+
+        let mut client = TcpStream::connect(addr).await?;
+        client
+            .write_all(b"*2\r\n$4\r\necho\r\n$5\r\nhello\r\n*0\r\n")
+            .await?;
+        let mut buf = [0u8; 11];
+        tokio::time::timeout(Duration::from_secs(2), client.read_exact(&mut buf)).await??;
+        assert_eq!(&buf, b"$5\r\nhello\r\n");
+
+        drop(client);
+        server.await??;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reply_flushed_before_partial_frame() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await?;
+            stream_handler(stream, Backend::new()).await
+        });
+
+        // the second command only arrives after the first reply was read
+        let mut client = TcpStream::connect(addr).await?;
+        client
+            .write_all(b"*2\r\n$4\r\necho\r\n$5\r\nhello\r\n*2\r\n$4\r\necho\r\n$5\r\nwor")
+            .await?;
+        let mut buf = [0u8; 11];
+        tokio::time::timeout(Duration::from_secs(2), client.read_exact(&mut buf)).await??;
+        assert_eq!(&buf, b"$5\r\nhello\r\n");
+
+        client.write_all(b"ld\r\n").await?;
+        tokio::time::timeout(Duration::from_secs(2), client.read_exact(&mut buf)).await??;
+        assert_eq!(&buf, b"$5\r\nworld\r\n");
+
+        drop(client);
+        server.await??;
+        Ok(())
+    }
+
+    #[test]
+    fn test_codec_rejects_lengths_over_its_limits() -> Result<()> {
+        let config = ServerConfig {
+            proto_max_bulk_len: 8,
+            proto_max_multibulk_len: 2,
+            ..Default::default()
+        };
+        let mut codec = RespFrameCodec::new(config.frame_limits());
+
+        let mut buf = BytesMut::from(&b"*2\r\n$3\r\nset\r\n$8\r\nabc"[..]);
+        assert_eq!(codec.decode(&mut buf)?, None);
+        for input in [&b"*2\r\n$3\r\nset\r\n$9\r\n"[..], b"*3\r\n"] {
+            let mut buf = BytesMut::from(input);
+            let err = codec.decode(&mut buf).unwrap_err();
+            assert!(matches!(
+                err.downcast_ref(),
+                Some(RespDecodeError::InvalidFrameLength(_))
+            ));
+        }
+
+        // the default codec takes what redis takes
+        let mut codec = RespFrameCodec::new(FrameLimits::default());
+        let mut buf = BytesMut::from(&b"*3\r\n$9\r\n"[..]);
+        assert_eq!(codec.decode(&mut buf)?, None);
+        Ok(())
+    }
 }
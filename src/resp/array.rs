@@ -12,6 +12,17 @@ impl RespArray {
     pub fn new(frame_vec: Vec<RespFrame>) -> Self {
         Self(frame_vec)
     }
+
+    // `RespArray::of(["get", "key"])`, strings become bulk strings
+    pub fn of(items: impl IntoIterator<Item = impl Into<RespFrame>>) -> Self {
+        items.into_iter().map(Into::into).collect()
+    }
+}
+
+impl FromIterator<RespFrame> for RespArray {
+    fn from_iter<I: IntoIterator<Item = RespFrame>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
 }
 impl Deref for RespArray {
     type Target = Vec<RespFrame>;
@@ -26,39 +37,46 @@ impl Deref for RespArray {
 const ARRAY_CAP: usize = 4096;
 impl RespEncode for RespArray {
     fn encode(self) -> Result<Vec<u8>> {
-        if self.0.is_empty() {
-            return Ok(b"*-1\r\n".to_vec());
-        }
-        let mut buf = Vec::with_capacity(ARRAY_CAP);
-        buf.extend_from_slice(&format!("*{}\r\n", self.0.len()).into_bytes());
+        let mut buf = BytesMut::with_capacity(ARRAY_CAP);
+        self.encode_to(&mut buf)?;
+        Ok(buf.to_vec())
+    }
 
+    fn encode_to(self, dst: &mut BytesMut) -> Result<()> {
+        dst.extend_from_slice(format!("*{}\r\n", self.0.len()).as_bytes());
         for frame in self.0 {
-            buf.extend_from_slice(&frame.encode().unwrap());
+            frame.encode_to(dst)?;
         }
-
-        Ok(buf)
+        Ok(())
     }
 }
 
 // - array: "*<number-of-elements>\r\n<element-1>...<element-n>"
 //    - "*2\r\n$3\r\nget\r\n$5\r\nhello\r\n"
-// - null array: "*-1\r\n"
 impl RespDecode for RespArray {
     const FIRST_BYTE: [u8; 1] = [b'*'];
 
     fn decode(buf: &mut BytesMut) -> Result<Self, RespDecodeError> {
+        Self::decode_nested(buf, 0)
+    }
+}
+
+impl RespArray {
+    pub(crate) fn decode_nested(buf: &mut BytesMut, depth: usize) -> Result<Self, RespDecodeError> {
+        let frame_len = buf.len();
         let (length_end_pos, length) =
             parse_length(buf, &String::from_utf8_lossy(&Self::FIRST_BYTE))?;
-        if length == -1 {
-            buf.advance(5);
-            return Ok(Self::new(Vec::new()));
-        }
-        let length: usize = length as usize;
+        // "*-1" is a `RespNullArray`, not an array without elements
+        let length: usize = length.try_into().map_err(|_| {
+            RespDecodeError::invalid_frame(1, format!("invalid array length {}", length))
+        })?;
         buf.advance(length_end_pos + CRLF_LEN);
 
         let mut frames = Vec::new();
         for _ in 0..length {
-            let value = RespFrame::decode(buf)?;
+            let offset = frame_len - buf.len();
+            let value =
+                RespFrame::decode_nested(buf, depth + 1).map_err(|e| e.at_offset(offset))?;
             frames.push(value);
         }
         Ok(Self::new(frames))
@@ -72,6 +90,7 @@ mod tests {
     use bytes::BytesMut;
 
     use crate::resp::{bulk_string::RespBulkString, simple_string::RespSimpleString};
+    use crate::{RespInteger, RespMap, RespNullArray, RespNullBulkString, RespSet};
 
     use super::*;
 
@@ -83,15 +102,19 @@ mod tests {
         ];
         let resp_array = RespArray::new(frame_vec);
         let result = resp_array.encode()?;
-        assert_eq!(result, b"*2\r\n$-1\r\n$5\r\nhello\r\n");
+        assert_eq!(result, b"*2\r\n$0\r\n\r\n$5\r\nhello\r\n");
         Ok(())
     }
 
     #[test]
     fn test_null_array_encode() -> Result<()> {
-        let resp_null_array: RespFrame = RespArray::new(Vec::new()).into();
+        let resp_null_array: RespFrame = RespNullArray.into();
         let result = resp_null_array.encode()?;
         assert_eq!(result, b"*-1\r\n");
+
+        let resp_empty_array: RespFrame = RespArray::new(Vec::new()).into();
+        let result = resp_empty_array.encode()?;
+        assert_eq!(result, b"*0\r\n");
         Ok(())
     }
 
@@ -123,8 +146,104 @@ mod tests {
     #[test]
     fn test_null_array_decode() {
         let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*-1\r\n*0\r\n");
+        let frame = RespFrame::decode(&mut buf).unwrap();
+        assert_eq!(frame, RespNullArray.into());
+        let frame = RespFrame::decode(&mut buf).unwrap();
+        assert_eq!(frame, RespArray::new(Vec::new()).into());
+        assert!(buf.is_empty());
+
+        // an array can't be null
         buf.extend_from_slice(b"*-1\r\n");
-        let frame = RespArray::decode(&mut buf).unwrap();
-        assert_eq!(frame, RespArray::new(Vec::new()));
+        assert!(RespArray::decode(&mut buf).is_err());
+    }
+
+    #[test]
+    fn test_array_decode_error_offset() {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$3\r\nget\r\n$3\r\nkeyx\r\n");
+        let err = RespArray::decode(&mut buf).unwrap_err();
+        assert!(matches!(
+            err,
+            RespDecodeError::InvalidFrame { offset: 20, .. }
+        ));
+        assert!(err.to_string().contains("offset 20"));
+
+        buf.clear();
+        buf.extend_from_slice(b"*1\r\n?oops\r\n");
+        let err = RespArray::decode(&mut buf).unwrap_err();
+        assert!(matches!(
+            err,
+            RespDecodeError::InvalidFrame { offset: 4, .. }
+        ));
+    }
+
+    #[test]
+    fn test_encode_to_matches_encode() -> Result<()> {
+        let mut map = RespMap::new();
+        map.insert(
+            RespSimpleString::new("key"),
+            RespBulkString::new("value").into(),
+        );
+        let frame: RespFrame = RespArray::new(vec![
+            RespBulkString::new("hello").into(),
+            RespBulkString::new("").into(),
+            RespArray::new(vec![RespInteger::new(1).into(), map.into()]).into(),
+            RespSet::new(vec![RespSimpleString::new("OK").into()]).into(),
+        ])
+        .into();
+
+        let mut buf = BytesMut::from(&b"+prefix\r\n"[..]);
+        frame.clone().encode_to(&mut buf)?;
+        let mut expected = b"+prefix\r\n".to_vec();
+        expected.extend_from_slice(&frame.encode()?);
+        assert_eq!(&buf[..], &expected[..]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_of() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$3\r\nget\r\n$5\r\nhello\r\n");
+        let decoded = RespArray::decode(&mut buf)?;
+        assert_eq!(RespArray::of(["get", "hello"]), decoded);
+        assert_eq!(
+            RespArray::of(["get", "hello"]).encode()?,
+            b"*2\r\n$3\r\nget\r\n$5\r\nhello\r\n"
+        );
+
+        let mixed: RespArray = [RespFrame::from(1), RespFrame::from("a")]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            mixed,
+            RespArray::new(vec![
+                RespInteger::new(1).into(),
+                RespBulkString::new("a").into()
+            ])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_and_null_round_trip() -> Result<()> {
+        let frames: Vec<RespFrame> = vec![
+            RespArray::new(Vec::new()).into(),
+            RespNullArray.into(),
+            RespBulkString::new("").into(),
+            RespNullBulkString.into(),
+        ];
+        let mut buf = BytesMut::new();
+        for frame in frames.clone() {
+            frame.encode_to(&mut buf)?;
+        }
+        assert_eq!(&buf[..], b"*0\r\n*-1\r\n$0\r\n\r\n$-1\r\n");
+        for frame in frames {
+            assert_eq!(RespFrame::decode(&mut buf)?, frame);
+        }
+        assert!(buf.is_empty());
+
+        Ok(())
     }
 }
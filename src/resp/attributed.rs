@@ -0,0 +1,94 @@
+use anyhow::Result;
+use bytes::BytesMut;
+
+use crate::{RespDecode, RespDecodeError, RespEncode, RespFrame, RespMap, BUF_CAP};
+
+// a RESP3 reply carrying extra information about it, which clients are free to ignore.
+// only ever sent to RESP3 connections, see `Session::attributed`
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct RespAttributed {
+    pub attrs: RespMap,
+    pub frame: Box<RespFrame>,
+}
+impl RespAttributed {
+    pub fn new(attrs: RespMap, frame: impl Into<RespFrame>) -> Self {
+        Self {
+            attrs,
+            frame: Box::new(frame.into()),
+        }
+    }
+}
+
+// - attribute: "|<number-of-entries>\r\n<key-1><value-1>...<key-n><value-n>" then the frame
+impl RespEncode for RespAttributed {
+    fn encode(self) -> Result<Vec<u8>> {
+        let mut buf = BytesMut::with_capacity(BUF_CAP);
+        self.encode_to(&mut buf)?;
+        Ok(buf.to_vec())
+    }
+
+    fn encode_to(self, dst: &mut BytesMut) -> Result<()> {
+        self.attrs.encode_entries(Self::FIRST_BYTE, dst)?;
+        self.frame.encode_to(dst)
+    }
+}
+
+// - attribute: "|<number-of-entries>\r\n<key-1><value-1>...<key-n><value-n>" then the frame
+impl RespDecode for RespAttributed {
+    const FIRST_BYTE: [u8; 1] = [b'|'];
+
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespDecodeError> {
+        Self::decode_nested(buf, 0)
+    }
+}
+
+impl RespAttributed {
+    pub(crate) fn decode_nested(buf: &mut BytesMut, depth: usize) -> Result<Self, RespDecodeError> {
+        let frame_len = buf.len();
+        let attrs = RespMap::decode_entries(buf, Self::FIRST_BYTE, depth)?;
+        let offset = frame_len - buf.len();
+        let frame = RespFrame::decode_nested(buf, depth + 1).map_err(|e| e.at_offset(offset))?;
+        Ok(Self::new(attrs, frame))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{
+        frame_len, FrameLimits, RespDecode, RespEncode, RespFrame, RespInteger, RespMap,
+        RespSimpleString,
+    };
+
+    use super::RespAttributed;
+
+    fn attrs() -> RespMap {
+        let mut attrs = RespMap::new();
+        attrs.insert(RespSimpleString::new("ttl"), RespInteger::new(3600).into());
+        attrs
+    }
+
+    #[test]
+    fn test_attributed_encode() -> anyhow::Result<()> {
+        let frame: RespFrame = RespAttributed::new(attrs(), RespInteger::new(42)).into();
+        assert_eq!(frame.encode()?, b"|1\r\n+ttl\r\n:3600\r\n:42\r\n");
+        assert_eq!(
+            frame_len(b"|1\r\n+ttl\r\n:3600\r\n:42\r\n", &FrameLimits::default()),
+            Ok(22)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_attributed_round_trip() -> anyhow::Result<()> {
+        let attributed = RespAttributed::new(attrs(), RespInteger::new(42));
+        let mut buf = BytesMut::from(&attributed.clone().encode()?[..]);
+        buf.extend_from_slice(b"+OK\r\n");
+
+        let frame = RespFrame::decode(&mut buf)?;
+        assert_eq!(frame, attributed.into());
+        assert_eq!(&buf[..], b"+OK\r\n");
+        Ok(())
+    }
+}
@@ -27,8 +27,9 @@ impl RespDecode for bool {
                 buf.advance(4);
                 Ok(false)
             }
-            _ => Err(RespDecodeError::InvalidFrame(
-                "RespBoolean requires to be t or f".to_string(),
+            _ => Err(RespDecodeError::invalid_frame(
+                1,
+                "RespBoolean requires to be t or f",
             )),
         }
     }
@@ -1,10 +1,10 @@
 use anyhow::Result;
-use bytes::{Buf, BytesMut};
+use bytes::BytesMut;
 use std::ops::Deref;
 
 use crate::RespDecodeError;
 
-use crate::{parse_length, RespDecode, RespEncode, CRLF, CRLF_LEN};
+use crate::{decode_length_prefixed, RespDecode, RespEncode};
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
 pub struct RespBulkError(Vec<u8>);
@@ -37,23 +37,8 @@ impl RespDecode for RespBulkError {
     const FIRST_BYTE: [u8; 1] = [b'!'];
 
     fn decode(buf: &mut BytesMut) -> Result<Self, RespDecodeError> {
-        let (length_end_pos, length) =
-            parse_length(buf, &String::from_utf8_lossy(&Self::FIRST_BYTE))?;
-        if length == -1 {
-            buf.advance(5);
-            return Ok(Self::new(Vec::new()));
-        }
-        let length: usize = length as usize;
-        buf.advance(length_end_pos + CRLF_LEN);
-        let error = buf.split_to(length + CRLF_LEN);
-        if &error[length..] == CRLF.as_bytes() {
-            Ok(RespBulkError::new(&error[0..length]))
-        } else {
-            Err(RespDecodeError::InvalidFrame(format!(
-                "RespBulkError didn't end with {} or length not match",
-                CRLF
-            )))
-        }
+        let data = decode_length_prefixed(buf, Self::FIRST_BYTE)?;
+        Ok(Self::new(data.unwrap_or_default()))
     }
 }
 
@@ -92,8 +77,9 @@ mod tests {
         let result = RespFrame::decode(&mut buf).unwrap_err();
         assert_eq!(
             result,
-            RespDecodeError::InvalidFrame(
-                "RespBulkError didn't end with \r\n or length not match".to_string()
+            RespDecodeError::invalid_frame(
+                16,
+                "'!' frame didn't end with CRLF or length not match"
             )
         );
     }
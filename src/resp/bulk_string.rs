@@ -1,21 +1,22 @@
 use anyhow::Result;
 use std::ops::Deref;
 
-use bytes::{Buf, BytesMut};
+use bytes::{Bytes, BytesMut};
 
 use crate::RespDecodeError;
 
-use crate::{parse_length, RespDecode, RespEncode, CRLF, CRLF_LEN};
+use crate::{decode_length_prefixed, RespDecode, RespEncode, RespNullBulkString};
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
-pub struct RespBulkString(pub Vec<u8>);
+// backed by `Bytes` so clones share the data and decoding slices the read buffer
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RespBulkString(pub Bytes);
 impl RespBulkString {
     pub fn new(string: impl Into<Vec<u8>>) -> Self {
-        Self(string.into())
+        Self(Bytes::from(string.into()))
     }
 }
 impl Deref for RespBulkString {
-    type Target = Vec<u8>;
+    type Target = [u8];
     fn deref(&self) -> &Self::Target {
         &self.0
     }
@@ -28,49 +29,44 @@ impl AsRef<[u8]> for RespBulkString {
 
 impl From<String> for RespBulkString {
     fn from(value: String) -> Self {
-        RespBulkString(value.into_bytes())
+        RespBulkString(Bytes::from(value))
+    }
+}
+
+impl From<Bytes> for RespBulkString {
+    fn from(value: Bytes) -> Self {
+        RespBulkString(value)
     }
 }
 
 // - bulk string: "$<length>\r\n<data>\r\n"
 impl RespEncode for RespBulkString {
     fn encode(self) -> Result<Vec<u8>> {
-        if self.0.is_empty() {
-            return Ok(b"$-1\r\n".to_vec());
-        }
-        Ok(format!(
-            "${}\r\n{}\r\n",
-            self.0.len(),
-            String::from_utf8(self.0).unwrap()
-        )
-        .into())
+        let mut buf = BytesMut::with_capacity(self.0.len() + 16);
+        self.encode_to(&mut buf)?;
+        Ok(buf.to_vec())
+    }
+
+    fn encode_to(self, dst: &mut BytesMut) -> Result<()> {
+        dst.extend_from_slice(format!("${}\r\n", self.0.len()).as_bytes());
+        dst.extend_from_slice(&self.0);
+        dst.extend_from_slice(b"\r\n");
+        Ok(())
     }
 }
 
 // - bulk string: "$<length>\r\n<data>\r\n"
-// - null bulk string: "$-1\r\n"
+//   "$-1\r\n" is a `RespNullBulkString`, it doesn't read as an empty string
 impl RespDecode for RespBulkString {
     const FIRST_BYTE: [u8; 1] = [b'$'];
 
     fn decode(buf: &mut BytesMut) -> std::result::Result<Self, RespDecodeError> {
-        let (length_end_pos, length) =
-            parse_length(buf, &String::from_utf8_lossy(&Self::FIRST_BYTE))?;
-
-        if length == -1 {
-            buf.advance(5);
-            return Ok(Self::new(Vec::new()));
-        }
-        let length: usize = length as usize;
-
-        buf.advance(length_end_pos + CRLF_LEN);
-        let bulk_string = buf.split_to(length + CRLF_LEN);
-        if &bulk_string[length..] == CRLF.as_bytes() {
-            Ok(RespBulkString::new(&bulk_string[0..length]))
-        } else {
-            Err(RespDecodeError::InvalidFrame(format!(
-                "RespBulkString didn't end with {} or length not match",
-                CRLF
-            )))
+        match decode_length_prefixed(buf, Self::FIRST_BYTE)? {
+            Some(data) => Ok(Self(data)),
+            None => Err(RespDecodeError::unexpected_frame(
+                "a bulk string",
+                &RespNullBulkString.into(),
+            )),
         }
     }
 }
@@ -94,7 +90,7 @@ mod tests {
 
     #[test]
     fn test_null_bulk_string_encode() -> Result<()> {
-        let resp_null_bulk_string: RespFrame = RespBulkString::new("").into();
+        let resp_null_bulk_string: RespFrame = crate::RespNullBulkString.into();
         let result = resp_null_bulk_string.encode()?;
         assert_eq!(result, b"$-1\r\n");
         Ok(())
@@ -117,8 +113,9 @@ mod tests {
         let result = RespBulkString::decode(&mut buf).unwrap_err();
         assert_eq!(
             result,
-            RespDecodeError::InvalidFrame(
-                "RespBulkString didn't end with \r\n or length not match".to_string()
+            RespDecodeError::invalid_frame(
+                18,
+                "'$' frame didn't end with CRLF or length not match"
             )
         );
     }
@@ -127,7 +124,64 @@ mod tests {
     fn test_null_bulk_string_decode() {
         let mut buf = BytesMut::new();
         buf.extend_from_slice(b"$-1\r\n");
+        let result = RespBulkString::decode(&mut buf).unwrap_err();
+        assert!(matches!(result, RespDecodeError::UnexpectedFrame { .. }));
+
+        buf.clear();
+        buf.extend_from_slice(b"$-1\r\n");
+        let frame = RespFrame::decode(&mut buf).unwrap();
+        assert_eq!(frame, RespNullBulkString.into());
+    }
+
+    #[test]
+    fn test_empty_bulk_string_decode() {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"$0\r\n");
+        let result = RespBulkString::decode(&mut buf).unwrap_err();
+        assert_eq!(result, RespDecodeError::NotComplete);
+
+        buf.extend_from_slice(b"\r\n");
         let frame = RespBulkString::decode(&mut buf).unwrap();
         assert_eq!(frame, RespBulkString::new(Vec::new()));
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_partial_bulk_string_decode() {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"$5\r\nhel");
+        let result = RespBulkString::decode(&mut buf).unwrap_err();
+        assert_eq!(result, RespDecodeError::NotComplete);
+    }
+
+    #[test]
+    fn test_bulk_string_clone_shares_data() {
+        let frame = RespBulkString::new(vec![b'x'; 1024]);
+        let cloned = frame.clone();
+        assert_eq!(frame.as_ptr(), cloned.as_ptr());
+    }
+
+    #[test]
+    fn test_bulk_string_decode_slices_buffer() {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"$5\r\nhello\r\n");
+        let start = buf.as_ptr() as usize;
+        let frame = RespBulkString::decode(&mut buf).unwrap();
+        // the payload still lives in the read buffer's allocation, right after "$5\r\n"
+        assert_eq!(frame.as_ptr() as usize, start + 4);
+    }
+
+    #[test]
+    fn test_empty_bulk_string_round_trip() -> Result<()> {
+        let encoded = RespBulkString::new("").encode()?;
+        assert_eq!(encoded, b"$0\r\n\r\n");
+
+        let mut buf = BytesMut::from(&encoded[..]);
+        let frame = RespBulkString::decode(&mut buf)?;
+        assert_eq!(frame, RespBulkString::new(""));
+        assert!(frame.is_empty());
+        assert!(buf.is_empty());
+
+        Ok(())
     }
 }
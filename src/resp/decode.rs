@@ -1,20 +1,57 @@
 use anyhow::Result;
-use bytes::BytesMut;
+use bytes::{Buf, Bytes, BytesMut};
 use tracing::info;
 
 use crate::RespDecodeError;
 
 use super::{
-    array::RespArray, bulk_error::RespBulkError, bulk_string::RespBulkString, frame::RespFrame,
-    integer::RespInteger, map::RespMap, null::RespNull, set::RespSet,
-    simple_error::RespSimpleError, simple_string::RespSimpleString,
+    array::RespArray,
+    attributed::RespAttributed,
+    bulk_error::RespBulkError,
+    bulk_string::RespBulkString,
+    frame::RespFrame,
+    integer::RespInteger,
+    map::RespMap,
+    null::{RespNull, RespNullArray, RespNullBulkString},
+    push::RespPush,
+    set::RespSet,
+    simple_error::RespSimpleError,
+    simple_string::RespSimpleString,
 };
 
 pub const CRLF_LEN: usize = 2;
 pub const CRLF: &str = "\r\n";
 
-pub trait RespFrameFirstByte {
-    const FIRST_BYTE: [u8; 1];
+pub const MAX_NESTING_DEPTH: usize = 128;
+// a line frame or length header that hasn't ended within this many bytes is rejected
+// instead of buffered, like redis does for the lines it reads
+pub const MAX_LINE_LEN: usize = 64 * 1024;
+
+// upper bounds for declared lengths, `frame_len` checks them before anything is read for
+// the frame. the server takes them from its config
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameLimits {
+    // bytes of bulk data, like redis' proto-max-bulk-len
+    pub max_bulk_len: usize,
+    // elements of an aggregate, or entries of a map
+    pub max_aggregate_len: usize,
+}
+
+impl FrameLimits {
+    // for checks that only care whether a frame is complete
+    pub const UNLIMITED: Self = Self {
+        max_bulk_len: usize::MAX,
+        max_aggregate_len: usize::MAX,
+    };
+}
+
+impl Default for FrameLimits {
+    fn default() -> Self {
+        Self {
+            max_bulk_len: 512 * 1024 * 1024,
+            max_aggregate_len: 1024 * 1024,
+        }
+    }
 }
 
 pub trait RespDecode: Sized {
@@ -25,31 +62,57 @@ pub trait RespDecode: Sized {
 impl RespDecode for RespFrame {
     const FIRST_BYTE: [u8; 1] = [b'?'];
     fn decode(buf: &mut BytesMut) -> Result<Self, super::RespDecodeError> {
+        Self::decode_nested(buf, 0)
+    }
+}
+
+impl RespFrame {
+    // `depth` counts the aggregates enclosing this frame, so hostile input can't nest
+    // deep enough to overflow the stack
+    pub(crate) fn decode_nested(buf: &mut BytesMut, depth: usize) -> Result<Self, RespDecodeError> {
         if buf.len() < 3 {
             return Err(crate::RespDecodeError::NotComplete);
         }
         let mut iter = buf.iter().peekable();
+        if matches!(iter.peek(), Some(b'*' | b'%' | b'~' | b'>' | b'|'))
+            && depth >= MAX_NESTING_DEPTH
+        {
+            return Err(RespDecodeError::invalid_frame(
+                0,
+                format!("frames nested deeper than {}", MAX_NESTING_DEPTH),
+            ));
+        }
         match iter.peek() {
             Some(b'+') => Ok(RespSimpleString::decode(buf)?.into()),
             Some(b'-') => Ok(RespSimpleError::decode(buf)?.into()),
             Some(b'!') => Ok(RespBulkError::decode(buf)?.into()),
             Some(b':') => Ok(RespInteger::decode(buf)?.into()),
+            // a -1 length is a null, not an empty value
+            Some(b'$') if buf.starts_with(b"$-1\r\n") => {
+                Ok(RespNullBulkString::decode(buf)?.into())
+            }
             Some(b'$') => Ok(RespBulkString::decode(buf)?.into()),
-            Some(b'*') => Ok(RespArray::decode(buf)?.into()),
-            Some(b'%') => Ok(RespMap::decode(buf)?.into()),
-            Some(b'~') => Ok(RespSet::decode(buf)?.into()),
+            Some(b'*') if buf.starts_with(b"*-1\r\n") => Ok(RespNullArray::decode(buf)?.into()),
+            Some(b'*') => Ok(RespArray::decode_nested(buf, depth)?.into()),
+            Some(b'%') => Ok(RespMap::decode_nested(buf, depth)?.into()),
+            Some(b'~') => Ok(RespSet::decode_nested(buf, depth)?.into()),
+            Some(b'>') => Ok(RespPush::decode_nested(buf, depth)?.into()),
+            Some(b'|') => Ok(RespAttributed::decode_nested(buf, depth)?.into()),
             Some(b'_') => Ok(RespNull::decode(buf)?.into()),
             Some(b'#') => Ok(bool::decode(buf)?.into()),
             Some(b',') => Ok(f64::decode(buf)?.into()),
             None => Err(RespDecodeError::NotComplete),
-            _ => Err(RespDecodeError::InvalidFrame("Invalid frame".to_string())),
+            Some(byte) => Err(RespDecodeError::invalid_frame(
+                0,
+                format!("unknown frame type {:?}", **byte as char),
+            )),
         }
     }
 }
 
 pub fn find_nth_crlf(buf: &[u8], nth: usize) -> Option<usize> {
     let mut count = 0;
-    for i in 0..buf.len() - 1 {
+    for i in 0..buf.len().saturating_sub(1) {
         if buf[i] == b'\r' && buf[i + 1] == b'\n' {
             count += 1;
             if count == nth {
@@ -60,6 +123,20 @@ pub fn find_nth_crlf(buf: &[u8], nth: usize) -> Option<usize> {
     None
 }
 
+// position of the CRLF ending the line at the start of `buf`, only the first
+// `MAX_LINE_LEN` bytes are searched
+fn find_line_end(buf: &[u8]) -> Result<usize, RespDecodeError> {
+    let searched = &buf[..buf.len().min(MAX_LINE_LEN + CRLF_LEN)];
+    match find_nth_crlf(searched, 1) {
+        Some(pos) => Ok(pos),
+        None if buf.len() < MAX_LINE_LEN + CRLF_LEN => Err(RespDecodeError::NotComplete),
+        None => Err(RespDecodeError::invalid_frame(
+            0,
+            format!("line longer than {} bytes", MAX_LINE_LEN),
+        )),
+    }
+}
+
 pub fn extract_simple_frame_data(
     buf: &mut BytesMut,
     prefix: [u8; 1],
@@ -72,14 +149,348 @@ pub fn extract_simple_frame_data(
         )));
     }
 
-    match find_nth_crlf(buf, 1) {
-        Some(pos) => Ok(pos),
-        None => Err(RespDecodeError::NotComplete),
+    let pos = find_line_end(buf)?;
+    // a line frame ends at its first CRLF, a lone CR or LF before that can't be part of it
+    if let Some(stray) = buf[..pos].iter().position(|b| *b == b'\r' || *b == b'\n') {
+        return Err(RespDecodeError::invalid_frame(
+            stray,
+            "unexpected CR or LF inside a simple frame",
+        ));
     }
+    Ok(pos)
 }
 
+// declared lengths are bounded by `frame_len`, which measures a frame before it is decoded.
+// decoding itself never allocates ahead of the data that actually arrived
 pub fn parse_length(buf: &mut BytesMut, prefix: &str) -> Result<(usize, isize), RespDecodeError> {
     let length_end_pos = extract_simple_frame_data(buf, [prefix.as_bytes()[0]])?;
-    let length = String::from_utf8_lossy(&buf[prefix.len()..length_end_pos]);
-    Ok((length_end_pos, length.parse()?))
+    let length = parse_length_digits(&buf[prefix.len()..length_end_pos], prefix.len())?;
+    Ok((length_end_pos, length))
+}
+
+// lengths are "0" or an optional "-" and digits without a leading zero, like redis reads
+// them. `offset` is where the digits start in the frame, for the error
+fn parse_length_digits(digits: &[u8], offset: usize) -> Result<isize, RespDecodeError> {
+    let unsigned = digits.strip_prefix(b"-").unwrap_or(digits);
+    let canonical = match unsigned {
+        [b'0'] => unsigned.len() == digits.len(),
+        [b'1'..=b'9', rest @ ..] => rest.iter().all(u8::is_ascii_digit),
+        _ => false,
+    };
+    if !canonical {
+        return Err(RespDecodeError::invalid_frame(
+            offset,
+            format!("invalid length {:?}", String::from_utf8_lossy(digits)),
+        ));
+    }
+    Ok(String::from_utf8_lossy(digits).parse()?)
+}
+
+// total size of the frame at the start of `buf` without consuming anything, so callers can
+// wait for more bytes instead of decoding half a frame. lengths over `limits` are rejected
+// as soon as their header is there. only incompleteness and limits are reported reliably,
+// other problems are left for `RespFrame::decode` to describe
+pub fn frame_len(buf: &[u8], limits: &FrameLimits) -> Result<usize, RespDecodeError> {
+    frame_len_nested(buf, limits, 0)
+}
+
+fn frame_len_nested(
+    buf: &[u8],
+    limits: &FrameLimits,
+    depth: usize,
+) -> Result<usize, RespDecodeError> {
+    let prefix = *buf.first().ok_or(RespDecodeError::NotComplete)?;
+    let line_end = find_line_end(buf)?;
+    let header_len = line_end + CRLF_LEN;
+    let length = || parse_length_digits(&buf[1..line_end], 1);
+
+    match prefix {
+        b'+' | b'-' | b':' | b'#' | b',' | b'_' => Ok(header_len),
+        b'$' | b'!' => {
+            let Ok(length) = usize::try_from(length()?) else {
+                return Ok(header_len);
+            };
+            if length > limits.max_bulk_len {
+                return Err(RespDecodeError::InvalidFrameLength(length));
+            }
+            let total = header_len + length + CRLF_LEN;
+            if buf.len() < total {
+                return Err(RespDecodeError::NotComplete);
+            }
+            Ok(total)
+        }
+        b'*' | b'~' | b'>' | b'%' | b'|' => {
+            if depth >= MAX_NESTING_DEPTH {
+                return Err(RespDecodeError::invalid_frame(0, "frames nested too deep"));
+            }
+            let Ok(length) = usize::try_from(length()?) else {
+                return Ok(header_len);
+            };
+            if length > limits.max_aggregate_len {
+                return Err(RespDecodeError::InvalidFrameLength(length));
+            }
+            // attributes are followed by the frame they describe
+            let elements = match prefix {
+                b'%' => length * 2,
+                b'|' => length * 2 + 1,
+                _ => length,
+            };
+            let mut total = header_len;
+            for _ in 0..elements {
+                total += frame_len_nested(&buf[total..], limits, depth + 1)?;
+            }
+            Ok(total)
+        }
+        byte => Err(RespDecodeError::invalid_frame(
+            0,
+            format!("unknown frame type {:?}", byte as char),
+        )),
+    }
+}
+
+// shared by the "<prefix><length>\r\n<data>\r\n" frames. returns None for a "-1" length and
+// only consumes `buf` once the whole frame is there
+pub fn decode_length_prefixed(
+    buf: &mut BytesMut,
+    prefix: [u8; 1],
+) -> Result<Option<Bytes>, RespDecodeError> {
+    let (length_end_pos, length) = parse_length(buf, &String::from_utf8_lossy(&prefix))?;
+    let data_start = length_end_pos + CRLF_LEN;
+    if length == -1 {
+        buf.advance(data_start);
+        return Ok(None);
+    }
+    let length: usize = length.try_into().map_err(|_| {
+        RespDecodeError::invalid_frame(prefix.len(), format!("invalid length {}", length))
+    })?;
+
+    let data_end = data_start + length;
+    if buf.len() < data_end + CRLF_LEN {
+        return Err(RespDecodeError::NotComplete);
+    }
+    if &buf[data_end..data_end + CRLF_LEN] != CRLF.as_bytes() {
+        return Err(RespDecodeError::invalid_frame(
+            data_end,
+            format!(
+                "{:?} frame didn't end with CRLF or length not match",
+                prefix[0] as char
+            ),
+        ));
+    }
+
+    buf.advance(data_start);
+    let data = buf.split_to(length).freeze();
+    buf.advance(CRLF_LEN);
+    Ok(Some(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{RespArray, RespDecode, RespDecodeError, RespFrame};
+
+    use super::{decode_length_prefixed, frame_len, FrameLimits, MAX_LINE_LEN, MAX_NESTING_DEPTH};
+
+    #[test]
+    fn test_decode_length_prefixed() {
+        let mut buf = BytesMut::from(&b"$5\r\nhello\r\n+OK\r\n"[..]);
+        let data = decode_length_prefixed(&mut buf, [b'$']).unwrap();
+        assert_eq!(data.as_deref(), Some(&b"hello"[..]));
+        assert_eq!(&buf[..], b"+OK\r\n");
+
+        let mut buf = BytesMut::from(&b"!-1\r\n"[..]);
+        let data = decode_length_prefixed(&mut buf, [b'!']).unwrap();
+        assert_eq!(data, None);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_decode_length_prefixed_truncated() {
+        for input in [&b"$5\r\nhel"[..], b"$5\r\nhello", b"$5\r\nhello\r", b"$5"] {
+            let mut buf = BytesMut::from(input);
+            let err = decode_length_prefixed(&mut buf, [b'$']).unwrap_err();
+            assert_eq!(err, RespDecodeError::NotComplete);
+            // nothing is consumed, the next read appends to the same frame
+            assert_eq!(&buf[..], input);
+        }
+    }
+
+    #[test]
+    fn test_length_must_be_canonical() {
+        for input in [&b"$7\r\nmessage\r\n"[..], b"$0\r\n\r\n", b"$-1\r\n"] {
+            let mut buf = BytesMut::from(input);
+            assert!(
+                decode_length_prefixed(&mut buf, [b'$']).is_ok(),
+                "{:?}",
+                input
+            );
+        }
+
+        for input in [
+            &b"$007\r\nmessage\r\n"[..],
+            b"$ 7\r\nmessage\r\n",
+            b"$+7\r\nmessage\r\n",
+            b"$7a\r\nmessage\r\n",
+            b"$-0\r\n",
+            b"$--1\r\n",
+            b"$\r\n",
+        ] {
+            let mut buf = BytesMut::from(input);
+            let err = decode_length_prefixed(&mut buf, [b'$']).unwrap_err();
+            assert!(
+                matches!(err, RespDecodeError::InvalidFrame { offset: 1, .. }),
+                "{:?}",
+                input
+            );
+            assert!(frame_len(input, &FrameLimits::default()).is_err());
+        }
+
+        let mut buf = BytesMut::from(&b"*02\r\n+a\r\n+b\r\n"[..]);
+        assert!(RespArray::decode(&mut buf).is_err());
+    }
+
+    #[test]
+    fn test_frame_len() {
+        let frame = b"*2\r\n$3\r\nget\r\n%1\r\n+k\r\n:1\r\n+OK\r\n";
+        assert_eq!(
+            frame_len(frame, &FrameLimits::default()),
+            Ok(frame.len() - 5)
+        );
+        for end in 0..frame.len() - 5 {
+            assert_eq!(
+                frame_len(&frame[..end], &FrameLimits::default()),
+                Err(RespDecodeError::NotComplete)
+            );
+        }
+
+        assert_eq!(frame_len(b"$-1\r\n+OK\r\n", &FrameLimits::default()), Ok(5));
+        assert!(matches!(
+            frame_len(b"?oops\r\n", &FrameLimits::default()),
+            Err(RespDecodeError::InvalidFrame { .. })
+        ));
+    }
+
+    #[test]
+    fn test_declared_length_limits() {
+        let limits = FrameLimits {
+            max_bulk_len: 1024,
+            max_aggregate_len: 16,
+        };
+        for (input, length) in [
+            (&b"$2000000000\r\n"[..], 2_000_000_000),
+            (b"$1025\r\n", 1025),
+            (b"*17\r\n", 17),
+            (b"%999999999\r\n", 999_999_999),
+            // nested frames are held to the same limits
+            (b"*1\r\n~17\r\n", 17),
+            (b"*2\r\n$3\r\nset\r\n$1025\r\n", 1025),
+        ] {
+            assert_eq!(
+                frame_len(input, &limits),
+                Err(RespDecodeError::InvalidFrameLength(length)),
+                "{:?}",
+                input
+            );
+        }
+
+        // at the limit the header is fine, the decoder just waits for the rest
+        for input in [&b"$1024\r\n"[..], b"*16\r\n", b"%16\r\n"] {
+            assert_eq!(frame_len(input, &limits), Err(RespDecodeError::NotComplete));
+        }
+
+        // decoding on its own allocates nothing for a declared length it hasn't seen data for
+        let mut buf = BytesMut::from(&b"*999999999\r\n"[..]);
+        assert_eq!(
+            RespArray::decode(&mut buf),
+            Err(RespDecodeError::NotComplete)
+        );
+        let mut buf = BytesMut::from(&b"$2000000000\r\n"[..]);
+        assert_eq!(
+            decode_length_prefixed(&mut buf, [b'$']),
+            Err(RespDecodeError::NotComplete)
+        );
+    }
+
+    #[test]
+    fn test_nesting_depth_limit() {
+        let nested = |depth: usize| {
+            let mut input = "*1\r\n".repeat(depth);
+            input.push_str(":1\r\n");
+            BytesMut::from(input.as_bytes())
+        };
+
+        let mut buf = nested(MAX_NESTING_DEPTH);
+        assert!(RespFrame::decode(&mut buf).is_ok());
+
+        let mut buf = nested(MAX_NESTING_DEPTH + 1);
+        let err = RespFrame::decode(&mut buf).unwrap_err();
+        assert!(matches!(
+            err,
+            RespDecodeError::InvalidFrame { offset, .. } if offset == MAX_NESTING_DEPTH * 4
+        ));
+
+        // way past the limit is rejected the same way instead of overflowing the stack
+        let mut buf = nested(100_000);
+        assert!(RespArray::decode(&mut buf).is_err());
+
+        let mut buf = BytesMut::from("~1\r\n%1\r\n+k\r\n".repeat(100).as_bytes());
+        assert!(matches!(
+            RespFrame::decode(&mut buf),
+            Err(RespDecodeError::InvalidFrame { .. })
+        ));
+    }
+
+    #[test]
+    fn test_decode_length_prefixed_bad_terminator() {
+        let mut buf = BytesMut::from(&b"$5\r\nhelloxx\r\n"[..]);
+        let err = decode_length_prefixed(&mut buf, [b'$']).unwrap_err();
+        assert!(matches!(
+            err,
+            RespDecodeError::InvalidFrame { offset: 9, .. }
+        ));
+
+        let mut buf = BytesMut::from(&b"$-2\r\nhello\r\n"[..]);
+        let err = decode_length_prefixed(&mut buf, [b'$']).unwrap_err();
+        assert!(matches!(
+            err,
+            RespDecodeError::InvalidFrame { offset: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_line_length_limit() {
+        // a line at the limit is fine, the decoder waits for its CRLF until then
+        let mut line = b"+".to_vec();
+        line.resize(MAX_LINE_LEN, b'a');
+        assert_eq!(
+            frame_len(&line, &FrameLimits::default()),
+            Err(RespDecodeError::NotComplete)
+        );
+        line.extend_from_slice(b"\r\n");
+        assert_eq!(
+            frame_len(&line, &FrameLimits::default()),
+            Ok(MAX_LINE_LEN + 2)
+        );
+
+        let mut input = b"*1\r\n+".to_vec();
+        input.resize(MAX_LINE_LEN * 2, b'a');
+        assert!(matches!(
+            frame_len(&input, &FrameLimits::default()),
+            Err(RespDecodeError::InvalidFrame { .. })
+        ));
+        let mut buf = BytesMut::from(&input[..]);
+        assert!(matches!(
+            RespFrame::decode(&mut buf),
+            Err(RespDecodeError::InvalidFrame { offset: 4, .. })
+        ));
+
+        // a length header gets no more room than any other line
+        let mut input = b"$".to_vec();
+        input.resize(MAX_LINE_LEN * 2, b'1');
+        assert!(matches!(
+            frame_len(&input, &FrameLimits::default()),
+            Err(RespDecodeError::InvalidFrame { .. })
+        ));
+    }
 }
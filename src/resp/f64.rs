@@ -5,9 +5,22 @@ use bytes::BytesMut;
 use crate::{extract_simple_frame_data, RespDecode, RespEncode, CRLF_LEN};
 
 // - double: ",[<+|->]<integral>[.<fractional>][<E|e>[sign]<exponent>]\r\n"
+//   written like redis does: whole numbers without a fraction, no leading '+', and
+//   "inf", "-inf" and "nan" for the special values
 impl RespEncode for f64 {
     fn encode(self) -> Result<Vec<u8>> {
-        Ok(format!(",{:+e}\r\n", self).into())
+        let s = if self.is_nan() {
+            "nan".to_string()
+        } else if self.is_infinite() {
+            if self > 0.0 { "inf" } else { "-inf" }.to_string()
+        } else if self == 0.0 || (1e-5..1e17).contains(&self.abs()) {
+            // Display gives the shortest text that reads back the same value
+            format!("{}", self)
+        } else {
+            // without an exponent these would be hundreds of digits long
+            format!("{:e}", self)
+        };
+        Ok(format!(",{}\r\n", s).into())
     }
 }
 
@@ -35,20 +48,40 @@ mod tests {
     fn test_double_encode() -> Result<()> {
         let resp_double: RespFrame = 123.4567.into();
         let result = resp_double.encode()?;
-        assert_eq!(result, b",+1.234567e2\r\n");
+        assert_eq!(result, b",123.4567\r\n");
 
         let resp_double: RespFrame = (-1.0).into();
         let result = resp_double.encode()?;
-        assert_eq!(result, b",-1e0\r\n");
+        assert_eq!(result, b",-1\r\n");
+
+        let resp_double: RespFrame = 3.0.into();
+        let result = resp_double.encode()?;
+        assert_eq!(result, b",3\r\n");
 
         let resp_double: RespFrame = 1.23456e+8.into();
         let result = resp_double.encode()?;
-        assert_eq!(result, b",+1.23456e8\r\n");
+        assert_eq!(result, b",123456000\r\n");
 
         let resp_double: RespFrame = (-1.23456e-8).into();
         let result = resp_double.encode()?;
         assert_eq!(result, b",-1.23456e-8\r\n");
 
+        let resp_double: RespFrame = 1e300.into();
+        let result = resp_double.encode()?;
+        assert_eq!(result, b",1e300\r\n");
+
+        let resp_double: RespFrame = f64::INFINITY.into();
+        let result = resp_double.encode()?;
+        assert_eq!(result, b",inf\r\n");
+
+        let resp_double: RespFrame = f64::NEG_INFINITY.into();
+        let result = resp_double.encode()?;
+        assert_eq!(result, b",-inf\r\n");
+
+        let resp_double: RespFrame = f64::NAN.into();
+        let result = resp_double.encode()?;
+        assert_eq!(result, b",nan\r\n");
+
         Ok(())
     }
 
@@ -64,4 +97,34 @@ mod tests {
         let frame = f64::decode(&mut buf).unwrap();
         assert_eq!(frame, -1.23456e-9);
     }
+
+    #[test]
+    fn test_double_decode_special_values() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b",inf\r\n,-inf\r\n,nan\r\n");
+        assert_eq!(f64::decode(&mut buf)?, f64::INFINITY);
+        assert_eq!(f64::decode(&mut buf)?, f64::NEG_INFINITY);
+        assert!(f64::decode(&mut buf)?.is_nan());
+        assert!(buf.is_empty());
+
+        // what the encoder writes reads back as the same value
+        for value in [
+            3.0,
+            -0.5,
+            1e300,
+            -1.23456e-8,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+        ] {
+            let mut buf = BytesMut::from(&value.encode()?[..]);
+            assert_eq!(f64::decode(&mut buf)?, value);
+        }
+        let mut buf = BytesMut::from(&f64::NAN.encode()?[..]);
+        assert!(f64::decode(&mut buf)?.is_nan());
+
+        buf.extend_from_slice(b",infinite\r\n");
+        assert!(f64::decode(&mut buf).is_err());
+
+        Ok(())
+    }
 }
@@ -1,7 +1,10 @@
+use std::{borrow::Cow, mem::size_of};
+
 use enum_dispatch::enum_dispatch;
 
 use crate::{
-    RespArray, RespBulkError, RespBulkString, RespInteger, RespMap, RespNull, RespSimpleError,
+    RespArray, RespAttributed, RespBulkError, RespBulkString, RespDecodeError, RespInteger,
+    RespMap, RespMapKey, RespNull, RespNullArray, RespNullBulkString, RespPush, RespSimpleError,
     RespSimpleString,
 };
 
@@ -17,8 +20,213 @@ pub enum RespFrame {
     BulkString(RespBulkString),
     Array(RespArray),
     Null(RespNull),
+    NullBulkString(RespNullBulkString),
+    NullArray(RespNullArray),
     Boolean(bool),
     Double(f64),
     Map(RespMap),
     Set(RespSet),
+    Push(RespPush),
+    Attributed(RespAttributed),
+}
+
+impl RespFrame {
+    // the byte content of string-typed frames; integers render as their decimal text
+    pub fn as_bytes(&self) -> Option<Cow<'_, [u8]>> {
+        match self {
+            RespFrame::BulkString(s) => Some(Cow::Borrowed(s.as_ref())),
+            RespFrame::SimpleString(s) => Some(Cow::Borrowed(s.as_bytes())),
+            RespFrame::Integer(i) => Some(Cow::Owned(i.to_string().into_bytes())),
+            _ => None,
+        }
+    }
+
+    // rough number of bytes this frame occupies, the frame itself plus what it owns on the heap
+    pub fn memory_usage(&self) -> usize {
+        let owned = match self {
+            RespFrame::SimpleString(s) => s.len(),
+            RespFrame::Error(e) => e.len(),
+            RespFrame::BulkError(e) => e.len(),
+            RespFrame::BulkString(s) => s.len(),
+            RespFrame::Array(frames) => frames.iter().map(Self::memory_usage).sum(),
+            RespFrame::Set(frames) => frames.iter().map(Self::memory_usage).sum(),
+            RespFrame::Push(frames) => frames.iter().map(Self::memory_usage).sum(),
+            RespFrame::Map(map) => map_memory_usage(map),
+            RespFrame::Attributed(attributed) => {
+                map_memory_usage(&attributed.attrs) + attributed.frame.memory_usage()
+            }
+            RespFrame::Integer(_)
+            | RespFrame::Null(_)
+            | RespFrame::NullBulkString(_)
+            | RespFrame::NullArray(_)
+            | RespFrame::Boolean(_)
+            | RespFrame::Double(_) => 0,
+        };
+        size_of::<RespFrame>() + owned
+    }
+}
+
+// shorthands for building replies, the variants' own types convert through enum_dispatch
+impl From<i64> for RespFrame {
+    fn from(value: i64) -> Self {
+        RespInteger::new(value).into()
+    }
+}
+
+impl From<&str> for RespFrame {
+    fn from(value: &str) -> Self {
+        RespBulkString::new(value).into()
+    }
+}
+
+impl From<String> for RespFrame {
+    fn from(value: String) -> Self {
+        RespBulkString::from(value).into()
+    }
+}
+
+impl From<Vec<RespFrame>> for RespFrame {
+    fn from(value: Vec<RespFrame>) -> Self {
+        RespArray::new(value).into()
+    }
+}
+
+// a missing value is a null reply
+impl<T: Into<RespFrame>> From<Option<T>> for RespFrame {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => RespNull.into(),
+        }
+    }
+}
+
+// the other way round, for reading replies: `let n: i64 = client.command(..).await?.try_into()?`.
+// bool and f64 are variants themselves, enum_dispatch already gives them a `TryInto`
+// whose error is a plain &str
+impl TryFrom<RespFrame> for i64 {
+    type Error = RespDecodeError;
+    fn try_from(frame: RespFrame) -> Result<Self, Self::Error> {
+        match frame {
+            RespFrame::Integer(i) => Ok(*i),
+            frame => Err(RespDecodeError::unexpected_frame("an integer", &frame)),
+        }
+    }
+}
+
+impl TryFrom<RespFrame> for String {
+    type Error = RespDecodeError;
+    fn try_from(frame: RespFrame) -> Result<Self, Self::Error> {
+        match frame {
+            RespFrame::SimpleString(s) => Ok(s.to_string()),
+            RespFrame::BulkString(s) => String::from_utf8(s.to_vec())
+                .map_err(|_| RespDecodeError::invalid_frame(0, "bulk string is not valid utf-8")),
+            frame => Err(RespDecodeError::unexpected_frame("a string", &frame)),
+        }
+    }
+}
+
+impl TryFrom<RespFrame> for Vec<RespFrame> {
+    type Error = RespDecodeError;
+    fn try_from(frame: RespFrame) -> Result<Self, Self::Error> {
+        match frame {
+            RespFrame::Array(array) => Ok(array.0),
+            frame => Err(RespDecodeError::unexpected_frame("an array", &frame)),
+        }
+    }
+}
+
+fn map_memory_usage(map: &RespMap) -> usize {
+    map.iter()
+        .map(|(key, value)| size_of::<RespMapKey>() + key.as_bytes().len() + value.memory_usage())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_as_bytes() {
+        let frame: RespFrame = RespBulkString::new("hello").into();
+        assert_eq!(frame.as_bytes().as_deref(), Some(&b"hello"[..]));
+
+        let frame: RespFrame = RespSimpleString::new("OK").into();
+        assert_eq!(frame.as_bytes().as_deref(), Some(&b"OK"[..]));
+
+        let frame: RespFrame = RespInteger::new(-42).into();
+        assert_eq!(frame.as_bytes().as_deref(), Some(&b"-42"[..]));
+
+        let frame: RespFrame = RespArray::new(Vec::new()).into();
+        assert_eq!(frame.as_bytes(), None);
+    }
+
+    #[test]
+    fn test_frame_memory_usage() {
+        let small: RespFrame = RespBulkString::new("hello").into();
+        let large: RespFrame = RespBulkString::new(vec![b'x'; 1000]).into();
+        assert_eq!(large.memory_usage() - small.memory_usage(), 995);
+
+        let array: RespFrame = RespArray::new(vec![small.clone(), large.clone()]).into();
+        assert_eq!(
+            array.memory_usage(),
+            std::mem::size_of::<RespFrame>() + small.memory_usage() + large.memory_usage()
+        );
+    }
+
+    #[test]
+    fn test_frame_from_rust_types() {
+        assert_eq!(RespFrame::from(42), RespInteger::new(42).into());
+        assert_eq!(
+            RespFrame::from("hello"),
+            RespBulkString::new("hello").into()
+        );
+        assert_eq!(
+            RespFrame::from("hello".to_string()),
+            RespBulkString::new("hello").into()
+        );
+        assert_eq!(RespFrame::from(true), RespFrame::Boolean(true));
+        assert_eq!(
+            RespFrame::from(vec![RespFrame::from(1), RespFrame::from("a")]),
+            RespArray::new(vec![
+                RespInteger::new(1).into(),
+                RespBulkString::new("a").into()
+            ])
+            .into()
+        );
+        assert_eq!(RespFrame::from(Some("a")), RespBulkString::new("a").into());
+        assert_eq!(RespFrame::from(None::<i64>), RespFrame::Null(RespNull));
+    }
+
+    #[test]
+    fn test_frame_into_rust_types() -> anyhow::Result<()> {
+        let n: i64 = RespFrame::from(42).try_into()?;
+        assert_eq!(n, 42);
+        let s: String = RespFrame::from(RespSimpleString::new("OK")).try_into()?;
+        assert_eq!(s, "OK");
+        let s: String = RespFrame::from("hello").try_into()?;
+        assert_eq!(s, "hello");
+        let b: Result<bool, _> = RespFrame::Boolean(true).try_into();
+        assert_eq!(b, Ok(true));
+        let items: Vec<RespFrame> = RespFrame::from(vec![RespFrame::from(1)]).try_into()?;
+        assert_eq!(items, vec![RespFrame::from(1)]);
+
+        let ret: Result<i64, _> = RespFrame::from("42").try_into();
+        assert!(matches!(ret, Err(RespDecodeError::UnexpectedFrame { .. })));
+        let ret: Result<String, _> = RespFrame::from(42).try_into();
+        assert!(matches!(ret, Err(RespDecodeError::UnexpectedFrame { .. })));
+        let ret: Result<String, _> = RespFrame::from(RespBulkString::new(vec![0xff])).try_into();
+        assert!(ret.is_err());
+        let ret: Result<i64, _> = RespFrame::Null(RespNull).try_into();
+        assert_eq!(
+            ret.unwrap_err().to_string(),
+            "Unexpected frame: expected an integer, got Null(RespNull)"
+        );
+        let ret: Result<bool, _> = RespFrame::Null(RespNull).try_into();
+        assert!(ret.is_err());
+        let ret: Result<Vec<RespFrame>, _> = RespFrame::from(1).try_into();
+        assert!(ret.is_err());
+
+        Ok(())
+    }
 }
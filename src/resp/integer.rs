@@ -1,5 +1,5 @@
 use anyhow::Result;
-use bytes::BytesMut;
+use bytes::{Buf, BytesMut};
 use std::ops::Deref;
 
 use crate::RespDecodeError;
@@ -33,11 +33,35 @@ impl RespDecode for RespInteger {
 
     fn decode(buf: &mut BytesMut) -> Result<Self, RespDecodeError> {
         let end_content_pos = extract_simple_frame_data(buf, Self::FIRST_BYTE)?;
+        let value = parse_integer(&buf[1..end_content_pos])?;
+        buf.advance(end_content_pos + CRLF_LEN);
+        Ok(RespInteger::new(value))
+    }
+}
 
-        let data = buf.split_to(end_content_pos + CRLF_LEN);
-        let s = String::from_utf8_lossy(&data[1..end_content_pos]);
-        Ok(RespInteger::new(s.trim().parse()?))
+// an optional sign and at least one digit, nothing else. the offset in errors is where the
+// value starts in the frame
+fn parse_integer(data: &[u8]) -> Result<i64, RespDecodeError> {
+    let digits = data
+        .strip_prefix(b"+")
+        .or(data.strip_prefix(b"-"))
+        .unwrap_or(data);
+    if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+        return Err(RespDecodeError::invalid_frame(
+            1,
+            format!("invalid integer {:?}", String::from_utf8_lossy(data)),
+        ));
     }
+    // the bytes are ascii by now
+    std::str::from_utf8(data)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| {
+            RespDecodeError::invalid_frame(
+                1,
+                format!("integer {} out of range", String::from_utf8_lossy(data)),
+            )
+        })
 }
 
 #[cfg(test)]
@@ -75,4 +99,36 @@ mod tests {
         let frame = RespInteger::decode(&mut buf).unwrap();
         assert_eq!(frame, RespInteger::new(-123));
     }
+
+    #[test]
+    fn test_integer_decode_rejects_bad_values() {
+        for (input, reason) in [
+            (
+                &b":99999999999999999999\r\n"[..],
+                "integer 99999999999999999999 out of range",
+            ),
+            (
+                b":-9223372036854775809\r\n",
+                "integer -9223372036854775809 out of range",
+            ),
+            (b":\r\n", "invalid integer \"\""),
+            (b":+\r\n", "invalid integer \"+\""),
+            (b": 12\r\n", "invalid integer \" 12\""),
+            (b":12abc\r\n", "invalid integer \"12abc\""),
+        ] {
+            let mut buf = BytesMut::from(input);
+            assert_eq!(
+                RespInteger::decode(&mut buf),
+                Err(RespDecodeError::invalid_frame(1, reason)),
+                "{:?}",
+                input
+            );
+        }
+
+        let mut buf = BytesMut::from(&b":-9223372036854775808\r\n"[..]);
+        assert_eq!(
+            RespInteger::decode(&mut buf).unwrap(),
+            RespInteger::new(i64::MIN)
+        );
+    }
 }
@@ -6,28 +6,85 @@ use std::{
 
 use bytes::{Buf, BytesMut};
 
-use crate::{RespDecode, RespDecodeError, RespEncode, RespFrame, RespSimpleString, BUF_CAP};
+use crate::{
+    RespBulkString, RespDecode, RespDecodeError, RespEncode, RespFrame, RespSimpleString, BUF_CAP,
+};
 
-use super::decode::{parse_length, CRLF_LEN};
+use super::decode::{frame_len, parse_length, FrameLimits, CRLF_LEN};
 
+// entries live in a BTreeMap, so a map always encodes in ascending key order
+// no matter the order they were inserted in, keeping the wire output deterministic
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
-pub struct RespMap(BTreeMap<RespSimpleString, RespFrame>);
+pub struct RespMap(BTreeMap<RespMapKey, RespFrame>);
 impl RespMap {
     pub fn new() -> Self {
         Self(BTreeMap::new())
     }
+
+    pub fn insert(&mut self, key: impl Into<RespMapKey>, value: RespFrame) -> Option<RespFrame> {
+        self.0.insert(key.into(), value)
+    }
+
+    pub fn get(&self, key: impl Into<RespMapKey>) -> Option<&RespFrame> {
+        self.0.get(&key.into())
+    }
+}
+
+// a map key keeps the string type it was sent as, so a map encodes back the way it was
+// decoded. simple string keys sort before bulk string ones
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RespMapKey {
+    Simple(RespSimpleString),
+    Bulk(RespBulkString),
+}
+
+impl RespMapKey {
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            RespMapKey::Simple(key) => key.as_bytes(),
+            RespMapKey::Bulk(key) => key,
+        }
+    }
+}
+
+impl From<RespSimpleString> for RespMapKey {
+    fn from(key: RespSimpleString) -> Self {
+        RespMapKey::Simple(key)
+    }
+}
+
+impl From<RespBulkString> for RespMapKey {
+    fn from(key: RespBulkString) -> Self {
+        RespMapKey::Bulk(key)
+    }
+}
+
+impl RespEncode for RespMapKey {
+    fn encode(self) -> Result<Vec<u8>> {
+        match self {
+            RespMapKey::Simple(key) => key.encode(),
+            RespMapKey::Bulk(key) => key.encode(),
+        }
+    }
+
+    fn encode_to(self, dst: &mut BytesMut) -> Result<()> {
+        match self {
+            RespMapKey::Simple(key) => key.encode_to(dst),
+            RespMapKey::Bulk(key) => key.encode_to(dst),
+        }
+    }
 }
 
 // - map: "%<number-of-entries>\r\n<key-1><value-1>...<key-n><value-n>"
 impl RespEncode for RespMap {
     fn encode(self) -> Result<Vec<u8>> {
-        let mut buf = Vec::with_capacity(BUF_CAP);
-        buf.extend_from_slice(&format!("%{}\r\n", self.0.len()).into_bytes());
-        for frame in self.0 {
-            buf.extend_from_slice(&frame.0.encode().unwrap());
-            buf.extend_from_slice(&frame.1.encode().unwrap());
-        }
-        Ok(buf)
+        let mut buf = BytesMut::with_capacity(BUF_CAP);
+        self.encode_to(&mut buf)?;
+        Ok(buf.to_vec())
+    }
+
+    fn encode_to(self, dst: &mut BytesMut) -> Result<()> {
+        self.encode_entries(Self::FIRST_BYTE, dst)
     }
 }
 
@@ -36,27 +93,74 @@ impl RespDecode for RespMap {
     const FIRST_BYTE: [u8; 1] = [b'%'];
 
     fn decode(buf: &mut BytesMut) -> Result<Self, RespDecodeError> {
+        Self::decode_nested(buf, 0)
+    }
+}
+
+impl RespMap {
+    pub(crate) fn decode_nested(buf: &mut BytesMut, depth: usize) -> Result<Self, RespDecodeError> {
+        Self::decode_entries(buf, Self::FIRST_BYTE, depth)
+    }
+
+    // attributes share the map layout under a different prefix
+    pub(crate) fn encode_entries(self, prefix: [u8; 1], dst: &mut BytesMut) -> Result<()> {
+        dst.extend_from_slice(&prefix);
+        dst.extend_from_slice(format!("{}\r\n", self.0.len()).as_bytes());
+        for (key, value) in self.0 {
+            key.encode_to(dst)?;
+            value.encode_to(dst)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn decode_entries(
+        buf: &mut BytesMut,
+        prefix: [u8; 1],
+        depth: usize,
+    ) -> Result<Self, RespDecodeError> {
+        // a partial map must not be half consumed, so an outermost one is measured before
+        // anything is read. nested maps are covered by the check of their outermost frame
+        if depth == 0 {
+            frame_len(buf, &FrameLimits::UNLIMITED)?;
+        }
         let mut frames = Self::new();
-        let (length_end_pos, length) =
-            parse_length(buf, &String::from_utf8_lossy(&Self::FIRST_BYTE))?;
+        let frame_len = buf.len();
+        let (length_end_pos, length) = parse_length(buf, &String::from_utf8_lossy(&prefix))?;
         buf.advance(length_end_pos + CRLF_LEN);
 
         for _ in 0..length {
-            let key = RespSimpleString::decode(buf)?;
-            let value = RespFrame::decode(buf)?;
+            let offset = frame_len - buf.len();
+            let key = Self::decode_key(buf, depth).map_err(|e| e.at_offset(offset))?;
+            let offset = frame_len - buf.len();
+            let value =
+                RespFrame::decode_nested(buf, depth + 1).map_err(|e| e.at_offset(offset))?;
             frames.insert(key, value);
         }
         Ok(frames)
     }
 }
 
+impl RespMap {
+    // keys are simple or bulk strings, binary bulk keys included
+    fn decode_key(buf: &mut BytesMut, depth: usize) -> Result<RespMapKey, RespDecodeError> {
+        match RespFrame::decode_nested(buf, depth + 1)? {
+            RespFrame::SimpleString(key) => Ok(key.into()),
+            RespFrame::BulkString(key) => Ok(key.into()),
+            frame => Err(RespDecodeError::unexpected_frame(
+                "a string map key",
+                &frame,
+            )),
+        }
+    }
+}
+
 impl Default for RespMap {
     fn default() -> Self {
         Self::new()
     }
 }
 impl Deref for RespMap {
-    type Target = BTreeMap<RespSimpleString, RespFrame>;
+    type Target = BTreeMap<RespMapKey, RespFrame>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -108,4 +212,79 @@ mod tests {
         );
         assert_eq!(frame, resp_map);
     }
+
+    #[test]
+    fn test_map_encode_order_is_deterministic() -> Result<()> {
+        let entries = [("b", 2), ("c", 3), ("a", 1)];
+
+        let mut map = RespMap::new();
+        for (k, v) in entries {
+            map.insert(RespSimpleString::new(k), crate::RespInteger::new(v).into());
+        }
+        let mut reversed = RespMap::new();
+        for (k, v) in entries.into_iter().rev() {
+            reversed.insert(RespSimpleString::new(k), crate::RespInteger::new(v).into());
+        }
+
+        let encoded = map.encode()?;
+        assert_eq!(encoded, reversed.encode()?);
+        assert_eq!(
+            encoded,
+            b"%3\r\n+a\r\n:1\r\n+b\r\n:2\r\n+c\r\n:3\r\n".to_vec()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_decode_incomplete() -> Result<()> {
+        let full = b"%2\r\n+hello\r\n$5\r\nworld\r\n+foo\r\n$3\r\nbar\r\n";
+        // the header and the first entry only, then cut inside the second entry
+        for end in [23, 27, full.len() - 1] {
+            let mut buf = BytesMut::from(&full[..end]);
+            assert_eq!(RespMap::decode(&mut buf), Err(RespDecodeError::NotComplete));
+            assert_eq!(
+                RespFrame::decode(&mut buf),
+                Err(RespDecodeError::NotComplete)
+            );
+            assert_eq!(&buf[..], &full[..end]);
+        }
+
+        let mut buf = BytesMut::from(&full[..]);
+        let map = RespMap::decode(&mut buf)?;
+        assert_eq!(map.len(), 2);
+        assert!(buf.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_decode_bulk_string_keys() -> Result<()> {
+        // bulk keys, binary ones and ones holding CRLF included, round trip as they came
+        let input = b"%3\r\n$3\r\nkey\r\n$3\r\nval\r\n$4\r\nk\r\ny\r\n:1\r\n$1\r\n\xff\r\n:2\r\n";
+        let mut buf = BytesMut::from(&input[..]);
+        let map = RespMap::decode(&mut buf)?;
+        assert_eq!(
+            map.get(RespBulkString::new("key")),
+            Some(&RespBulkString::new("val").into())
+        );
+        assert_eq!(map.get(RespSimpleString::new("key")), None);
+        assert_eq!(
+            map.get(RespBulkString::new(b"k\r\ny".to_vec())),
+            Some(&crate::RespInteger::new(1).into())
+        );
+        assert_eq!(
+            map.encode()?,
+            b"%3\r\n$4\r\nk\r\ny\r\n:1\r\n$3\r\nkey\r\n$3\r\nval\r\n$1\r\n\xff\r\n:2\r\n".to_vec()
+        );
+
+        // a key that isn't a string is reported where it starts
+        let mut buf = BytesMut::from(&b"%2\r\n+a\r\n:1\r\n:1\r\n$3\r\nval\r\n"[..]);
+        assert!(matches!(
+            RespMap::decode(&mut buf),
+            Err(RespDecodeError::UnexpectedFrame { .. })
+        ));
+
+        Ok(())
+    }
 }
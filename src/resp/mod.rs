@@ -1,4 +1,5 @@
 mod array;
+mod attributed;
 mod bool;
 mod bulk_error;
 mod bulk_string;
@@ -8,37 +9,48 @@ mod frame;
 mod integer;
 mod map;
 mod null;
+mod push;
 mod set;
 mod simple_error;
 mod simple_string;
 
 pub use self::{
     array::RespArray,
+    attributed::RespAttributed,
     bulk_error::RespBulkError,
     bulk_string::RespBulkString,
-    decode::{extract_simple_frame_data, parse_length, RespDecode, CRLF, CRLF_LEN},
+    decode::{
+        decode_length_prefixed, extract_simple_frame_data, frame_len, parse_length, FrameLimits,
+        RespDecode, CRLF, CRLF_LEN, MAX_LINE_LEN, MAX_NESTING_DEPTH,
+    },
     frame::RespFrame,
     integer::RespInteger,
-    map::RespMap,
-    null::RespNull,
+    map::{RespMap, RespMapKey},
+    null::{RespNull, RespNullArray, RespNullBulkString},
+    push::RespPush,
     set::RespSet,
     simple_error::RespSimpleError,
     simple_string::RespSimpleString,
 };
 
 use anyhow::Result;
+use bytes::BytesMut;
 use enum_dispatch::enum_dispatch;
 use std::num::{ParseFloatError, ParseIntError};
 use thiserror::Error;
 
 #[derive(Error, Debug, Clone, PartialEq)]
 pub enum RespDecodeError {
-    #[error("Invalid frame: {0}")]
-    InvalidFrame(String),
+    #[error("Invalid frame at offset {offset}: {reason}")]
+    InvalidFrame { offset: usize, reason: String },
 
     #[error("Invalid frame type: {0}")]
     InvalidFrameType(String),
 
+    // a well formed frame that is not allowed where it showed up
+    #[error("Unexpected frame: expected {expected}, got {found}")]
+    UnexpectedFrame { expected: String, found: String },
+
     #[error("Invalid frame length: {0}")]
     InvalidFrameLength(usize),
 
@@ -52,11 +64,54 @@ pub enum RespDecodeError {
     ParseFloatError(#[from] ParseFloatError),
 }
 
+impl RespDecodeError {
+    pub fn invalid_frame(offset: usize, reason: impl Into<String>) -> Self {
+        RespDecodeError::InvalidFrame {
+            offset,
+            reason: reason.into(),
+        }
+    }
+
+    pub fn unexpected_frame(expected: impl Into<String>, found: &RespFrame) -> Self {
+        RespDecodeError::UnexpectedFrame {
+            expected: expected.into(),
+            found: format!("{:?}", found),
+        }
+    }
+
+    // `NotComplete` only means more bytes are needed, every other error is final
+    pub fn is_incomplete(&self) -> bool {
+        matches!(self, RespDecodeError::NotComplete)
+    }
+
+    // nested frames report offsets relative to their own start, shift them so they
+    // point into the enclosing frame
+    pub(crate) fn at_offset(self, base: usize) -> Self {
+        match self {
+            RespDecodeError::InvalidFrame { offset, reason } => RespDecodeError::InvalidFrame {
+                offset: base + offset,
+                reason,
+            },
+            err => err,
+        }
+    }
+}
+
 pub const BUF_CAP: usize = 1024;
 
 #[enum_dispatch]
 pub trait RespEncode {
     fn encode(self) -> Result<Vec<u8>>;
+
+    // appends the encoded frame to `dst`, aggregates override it so their elements are
+    // written straight into the same buffer
+    fn encode_to(self, dst: &mut BytesMut) -> Result<()>
+    where
+        Self: Sized,
+    {
+        dst.extend_from_slice(&self.encode()?);
+        Ok(())
+    }
 }
 
 // implementation of Redis serialization protocol
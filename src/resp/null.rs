@@ -7,6 +7,13 @@ use super::decode::RespDecode;
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
 pub struct RespNull;
 
+// the RESP2 nulls, kept apart from an empty bulk string or array which are values
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
+pub struct RespNullBulkString;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
+pub struct RespNullArray;
+
 // - null: "_\r\n"
 impl RespEncode for RespNull {
     fn encode(self) -> Result<Vec<u8>> {
@@ -19,14 +26,59 @@ impl RespDecode for RespNull {
     const FIRST_BYTE: [u8; 1] = [b'_'];
 
     fn decode(buf: &mut BytesMut) -> Result<Self, RespDecodeError> {
-        if buf == "_\r\n" {
-            buf.advance(3);
-            Ok(Self)
-        } else {
-            Err(RespDecodeError::InvalidFrame(
-                "RespNull requires to start with _".to_string(),
-            ))
-        }
+        decode_literal(buf, b"_\r\n", "RespNull requires to be _\r\n")?;
+        Ok(Self)
+    }
+}
+
+// - null bulk string: "$-1\r\n"
+impl RespEncode for RespNullBulkString {
+    fn encode(self) -> Result<Vec<u8>> {
+        Ok(b"$-1\r\n".to_vec())
+    }
+}
+
+// - null bulk string: "$-1\r\n"
+impl RespDecode for RespNullBulkString {
+    const FIRST_BYTE: [u8; 1] = [b'$'];
+
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespDecodeError> {
+        decode_literal(buf, b"$-1\r\n", "RespNullBulkString requires to be $-1\r\n")?;
+        Ok(Self)
+    }
+}
+
+// - null array: "*-1\r\n"
+impl RespEncode for RespNullArray {
+    fn encode(self) -> Result<Vec<u8>> {
+        Ok(b"*-1\r\n".to_vec())
+    }
+}
+
+// - null array: "*-1\r\n"
+impl RespDecode for RespNullArray {
+    const FIRST_BYTE: [u8; 1] = [b'*'];
+
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespDecodeError> {
+        decode_literal(buf, b"*-1\r\n", "RespNullArray requires to be *-1\r\n")?;
+        Ok(Self)
+    }
+}
+
+// consumes `literal` from the front of `buf`, anything after it belongs to the next frame
+fn decode_literal(buf: &mut BytesMut, literal: &[u8], reason: &str) -> Result<(), RespDecodeError> {
+    if buf.starts_with(literal) {
+        buf.advance(literal.len());
+        Ok(())
+    } else if literal.starts_with(buf) {
+        Err(RespDecodeError::NotComplete)
+    } else {
+        let offset = buf
+            .iter()
+            .zip(literal)
+            .position(|(a, b)| a != b)
+            .unwrap_or(buf.len().min(literal.len()));
+        Err(RespDecodeError::invalid_frame(offset, reason))
     }
 }
 
@@ -54,4 +106,36 @@ mod tests {
         let frame = RespNull::decode(&mut buf).unwrap();
         assert_eq!(frame, RespNull);
     }
+
+    #[test]
+    fn test_null_decode_error_offset() {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"_x\n");
+        let err = RespNull::decode(&mut buf).unwrap_err();
+        assert!(matches!(
+            err,
+            RespDecodeError::InvalidFrame { offset: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_null_decode_followed_by_more_frames() -> anyhow::Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"_\r\n+OK\r\n");
+        assert_eq!(RespNull::decode(&mut buf)?, RespNull);
+        assert_eq!(&buf[..], b"+OK\r\n");
+        assert_eq!(
+            RespFrame::decode(&mut buf)?,
+            crate::RespSimpleString::new("OK").into()
+        );
+
+        buf.extend_from_slice(b"_\r");
+        assert_eq!(
+            RespNull::decode(&mut buf),
+            Err(RespDecodeError::NotComplete)
+        );
+        assert_eq!(&buf[..], b"_\r");
+
+        Ok(())
+    }
 }
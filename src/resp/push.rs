@@ -0,0 +1,111 @@
+use anyhow::Result;
+use bytes::{Buf, BytesMut};
+use std::ops::Deref;
+
+use crate::RespDecodeError;
+
+use crate::{parse_length, RespDecode, RespEncode, RespFrame, BUF_CAP, CRLF_LEN};
+
+// RESP3 out-of-band data like pub/sub messages, laid out like an array
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct RespPush(pub Vec<RespFrame>);
+impl RespPush {
+    pub fn new(frame_vec: Vec<RespFrame>) -> Self {
+        Self(frame_vec)
+    }
+}
+impl Deref for RespPush {
+    type Target = Vec<RespFrame>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+// - push: "><number-of-elements>\r\n<element-1>...<element-n>"
+impl RespEncode for RespPush {
+    fn encode(self) -> Result<Vec<u8>> {
+        let mut buf = BytesMut::with_capacity(BUF_CAP);
+        self.encode_to(&mut buf)?;
+        Ok(buf.to_vec())
+    }
+
+    fn encode_to(self, dst: &mut BytesMut) -> Result<()> {
+        dst.extend_from_slice(format!(">{}\r\n", self.0.len()).as_bytes());
+        for frame in self.0 {
+            frame.encode_to(dst)?;
+        }
+        Ok(())
+    }
+}
+
+// - push: "><number-of-elements>\r\n<element-1>...<element-n>"
+impl RespDecode for RespPush {
+    const FIRST_BYTE: [u8; 1] = [b'>'];
+
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespDecodeError> {
+        Self::decode_nested(buf, 0)
+    }
+}
+
+impl RespPush {
+    pub(crate) fn decode_nested(buf: &mut BytesMut, depth: usize) -> Result<Self, RespDecodeError> {
+        let frame_len = buf.len();
+        let (length_end_pos, length) =
+            parse_length(buf, &String::from_utf8_lossy(&Self::FIRST_BYTE))?;
+        let length: usize = length.try_into().map_err(|_| {
+            RespDecodeError::invalid_frame(1, format!("invalid push length {}", length))
+        })?;
+        buf.advance(length_end_pos + CRLF_LEN);
+
+        let mut frames = Vec::new();
+        for _ in 0..length {
+            let offset = frame_len - buf.len();
+            let value =
+                RespFrame::decode_nested(buf, depth + 1).map_err(|e| e.at_offset(offset))?;
+            frames.push(value);
+        }
+        Ok(Self::new(frames))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{RespBulkString, RespDecode, RespEncode, RespFrame, RespInteger};
+
+    use super::RespPush;
+
+    #[test]
+    fn test_push_encode() -> anyhow::Result<()> {
+        let frame: RespFrame = RespPush::new(vec![
+            RespBulkString::new("message").into(),
+            RespBulkString::new("news").into(),
+            RespInteger::new(1).into(),
+        ])
+        .into();
+        assert_eq!(
+            frame.encode()?,
+            b">3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n:1\r\n"
+        );
+
+        let frame: RespFrame = RespPush::new(Vec::new()).into();
+        assert_eq!(frame.encode()?, b">0\r\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_round_trip() -> anyhow::Result<()> {
+        let push = RespPush::new(vec![
+            RespBulkString::new("message").into(),
+            RespBulkString::new("news").into(),
+            RespBulkString::new("hello").into(),
+        ]);
+        let mut buf = BytesMut::from(&push.clone().encode()?[..]);
+        let frame = RespFrame::decode(&mut buf)?;
+        assert_eq!(frame, push.into());
+        assert!(buf.is_empty());
+        Ok(())
+    }
+}
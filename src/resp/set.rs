@@ -1,6 +1,12 @@
 use crate::RespDecodeError;
 use anyhow::Result;
 use bytes::{Buf, BytesMut};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    mem,
+    ops::Deref,
+};
 
 use crate::{parse_length, RespDecode, RespEncode, RespFrame, BUF_CAP, CRLF_LEN};
 
@@ -10,12 +16,17 @@ pub struct RespSet(Vec<RespFrame>);
 // - set: "~<number-of-elements>\r\n<element-1>...<element-n>"
 impl RespEncode for RespSet {
     fn encode(self) -> Result<Vec<u8>> {
-        let mut buf = Vec::with_capacity(BUF_CAP);
-        buf.extend_from_slice(&format!("~{}\r\n", self.0.len()).into_bytes());
+        let mut buf = BytesMut::with_capacity(BUF_CAP);
+        self.encode_to(&mut buf)?;
+        Ok(buf.to_vec())
+    }
+
+    fn encode_to(self, dst: &mut BytesMut) -> Result<()> {
+        dst.extend_from_slice(format!("~{}\r\n", self.0.len()).as_bytes());
         for frame in self.0 {
-            buf.extend_from_slice(&frame.encode().unwrap());
+            frame.encode_to(dst)?;
         }
-        Ok(buf)
+        Ok(())
     }
 }
 
@@ -24,24 +35,74 @@ impl RespDecode for RespSet {
     const FIRST_BYTE: [u8; 1] = [b'~'];
 
     fn decode(buf: &mut BytesMut) -> Result<Self, RespDecodeError> {
+        Self::decode_nested(buf, 0)
+    }
+}
+
+impl Deref for RespSet {
+    type Target = Vec<RespFrame>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl RespSet {
+    // repeated elements are dropped, the first occurrence of each keeps its place. frames
+    // are only compared with the ones sharing their `shallow_hash`, so a large set of
+    // strings or integers isn't compared pairwise
+    pub fn new(frame_vec: impl Into<Vec<RespFrame>>) -> Self {
+        let mut buckets: HashMap<u64, Vec<usize>> = HashMap::new();
+        let mut frames: Vec<RespFrame> = Vec::new();
+        for frame in frame_vec.into() {
+            let bucket = buckets.entry(shallow_hash(&frame)).or_default();
+            if bucket.iter().any(|&i| frames[i] == frame) {
+                continue;
+            }
+            bucket.push(frames.len());
+            frames.push(frame);
+        }
+        Self(frames)
+    }
+
+    // for elements already known to be distinct, like the members of a stored set
+    pub fn from_unique(frame_vec: impl Into<Vec<RespFrame>>) -> Self {
+        Self(frame_vec.into())
+    }
+
+    pub(crate) fn decode_nested(buf: &mut BytesMut, depth: usize) -> Result<Self, RespDecodeError> {
         let mut frames = Vec::new();
+        let frame_len = buf.len();
         let (length_end_pos, length) =
             parse_length(buf, &String::from_utf8_lossy(&Self::FIRST_BYTE))?;
 
         buf.advance(length_end_pos + CRLF_LEN);
 
         for _ in 0..length {
-            let value = RespFrame::decode(buf)?;
+            let offset = frame_len - buf.len();
+            let value =
+                RespFrame::decode_nested(buf, depth + 1).map_err(|e| e.at_offset(offset))?;
             frames.push(value);
         }
         Ok(Self::new(frames))
     }
 }
 
-impl RespSet {
-    pub fn new(frame_vec: impl Into<Vec<RespFrame>>) -> Self {
-        Self(frame_vec.into())
+// equal frames always hash the same. only the content of scalars is hashed, other frames
+// share a bucket per type and size and are told apart with `==`
+fn shallow_hash(frame: &RespFrame) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    mem::discriminant(frame).hash(&mut hasher);
+    match frame {
+        RespFrame::SimpleString(s) => s.as_bytes().hash(&mut hasher),
+        RespFrame::BulkString(s) => s.as_ref().hash(&mut hasher),
+        RespFrame::Integer(i) => (**i).hash(&mut hasher),
+        RespFrame::Boolean(b) => b.hash(&mut hasher),
+        RespFrame::Array(frames) => frames.len().hash(&mut hasher),
+        RespFrame::Set(frames) => frames.len().hash(&mut hasher),
+        _ => {}
     }
+    hasher.finish()
 }
 
 #[cfg(test)]
@@ -79,4 +140,52 @@ mod tests {
         ]);
         assert_eq!(frame, resp_set);
     }
+
+    #[test]
+    fn test_set_drops_repeated_elements() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"~2\r\n+a\r\n+a\r\n");
+        let set = RespSet::decode(&mut buf)?;
+        assert_eq!(set.len(), 1);
+        assert!(buf.is_empty());
+
+        // a simple and a bulk string with the same text are different elements
+        let set = RespSet::new(vec![
+            RespBulkString::new("b").into(),
+            RespSimpleString::new("a").into(),
+            RespBulkString::new("b").into(),
+            RespBulkString::new("a").into(),
+        ]);
+        assert_eq!(
+            *set,
+            vec![
+                RespBulkString::new("b").into(),
+                RespSimpleString::new("a").into(),
+                RespBulkString::new("a").into(),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_compares_elements_structurally() -> Result<()> {
+        let set = RespSet::new(vec![
+            crate::RespInteger::new(1).into(),
+            RespBulkString::new("1").into(),
+            crate::RespInteger::new(1).into(),
+            crate::RespArray::new(vec![RespBulkString::new("x").into()]).into(),
+            crate::RespArray::new(vec![RespBulkString::new("y").into()]).into(),
+            crate::RespArray::new(vec![RespBulkString::new("x").into()]).into(),
+        ]);
+        assert_eq!(set.len(), 4);
+
+        // trusted elements are kept as they are
+        let members = vec![
+            RespBulkString::new("a").into(),
+            RespBulkString::new("b").into(),
+        ];
+        assert_eq!(*RespSet::from_unique(members.clone()), members);
+        Ok(())
+    }
 }
@@ -31,8 +31,14 @@ impl RespDecode for RespSimpleError {
 }
 
 impl RespSimpleError {
+    // the message is a single line on the wire, a CR or LF in it would end the reply early
+    // and leave the rest to be read as another one, so they become spaces
     pub fn new(string: impl Into<String>) -> Self {
-        Self(string.into())
+        let mut string = string.into();
+        if string.contains(['\r', '\n']) {
+            string = string.replace(['\r', '\n'], " ");
+        }
+        Self(string)
     }
 }
 impl Deref for RespSimpleError {
@@ -68,4 +74,25 @@ mod tests {
         let frame: RespSimpleError = RespSimpleError::decode(&mut buf).unwrap();
         assert_eq!(frame, RespSimpleError::new("Error".to_string()));
     }
+
+    #[test]
+    fn test_error_encode_strips_cr_and_lf() -> Result<()> {
+        let resp_error: RespFrame =
+            RespSimpleError::new("ERR Protocol error: didn't end with \r\n here").into();
+        assert_eq!(
+            resp_error.encode()?,
+            b"-ERR Protocol error: didn't end with    here\r\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_decode_rejects_stray_cr_or_lf() {
+        let mut buf = bytes::BytesMut::from(&b"-ERR bad\rthing\r\n"[..]);
+        let err = RespSimpleError::decode(&mut buf).unwrap_err();
+        assert!(matches!(
+            err,
+            crate::RespDecodeError::InvalidFrame { offset: 8, .. }
+        ));
+    }
 }
@@ -77,4 +77,16 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_simple_string_decode_rejects_stray_cr_or_lf() {
+        for (input, offset) in [(&b"+OK\rX\r\n"[..], 3), (b"+O\nK\r\n", 2)] {
+            let mut buf = BytesMut::from(input);
+            let err = RespSimpleString::decode(&mut buf).unwrap_err();
+            assert_eq!(
+                err,
+                RespDecodeError::invalid_frame(offset, "unexpected CR or LF inside a simple frame")
+            );
+        }
+    }
 }